@@ -27,6 +27,9 @@
 //! A hand can be uniquely represented if the cards are sorted before being
 //! transformed into this format. The sort key is always the shrunken representation
 //! of the cards.
+//!
+//! Jokers use the values after the 52 regular cards. They have no rank or
+//! suit, and are always wild.
 
 use itertools::Itertools;
 use poker::{Card, Rank, Suit};
@@ -37,6 +40,9 @@ use variter::VarIter;
 pub struct SCard(u8);
 
 impl SCard {
+    /// The jokers that can be added to a deck.
+    pub const JOKERS: [SCard; 2] = [SCard(52), SCard(53)];
+
     #[inline]
     pub fn new(r: Rank, s: Suit) -> Self {
         Self((r as u8) << 2 | s as u8)
@@ -59,33 +65,97 @@ impl SCard {
             .map(|(s, r)| SCard::new(*r, *s))
     }
 
+    /// The 36-card deck used in short-deck hold'em, without the twos to fives.
+    pub fn short_deck() -> impl Iterator<Item = SCard> {
+        SCard::deck().filter(|c| c.rank() >= Rank::Six)
+    }
+
+    #[inline]
+    pub fn is_joker(&self) -> bool {
+        self.0 >= 52
+    }
+
+    /// Must not be called on a joker, which callers check for first with
+    /// [`SCard::is_joker`]. Only checked in debug builds.
     #[inline]
     pub fn suit(&self) -> Suit {
+        debug_assert!(!self.is_joker(), "jokers have no suit");
         let suit = self.0 & 0x3;
         unsafe { std::mem::transmute(suit) }
     }
 
+    /// Must not be called on a joker, which callers check for first with
+    /// [`SCard::is_joker`]. Only checked in debug builds.
     #[inline]
     pub fn rank(&self) -> Rank {
+        debug_assert!(!self.is_joker(), "jokers have no rank");
         let rank = self.0 >> 2;
         unsafe { std::mem::transmute(rank) }
     }
 }
 
+/// Which cards are wild, and may stand for any other card. Jokers are always
+/// wild.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wild {
+    /// Bit `r` is set if rank `r` is wild.
+    ranks: u16,
+}
+
+impl Wild {
+    /// Only jokers are wild.
+    pub const NONE: Wild = Wild { ranks: 0 };
+
+    /// Every card of rank `r` is wild, as well as any rank already wild.
+    pub fn with_rank(self, r: Rank) -> Self {
+        Wild {
+            ranks: self.ranks | 1 << r as u16,
+        }
+    }
+
+    #[inline]
+    pub fn is_wild(&self, card: SCard) -> bool {
+        card.is_joker() || self.ranks & 1 << (card.0 >> 2) != 0
+    }
+
+    /// Ranks which are wild.
+    pub fn ranks(&self) -> impl Iterator<Item = Rank> + '_ {
+        Rank::ALL_VARIANTS
+            .iter()
+            .copied()
+            .filter(|r| self.ranks & 1 << *r as u16 != 0)
+    }
+}
+
 impl From<Card> for SCard {
     fn from(c: Card) -> Self {
         SCard::new(c.rank(), c.suit())
     }
 }
 
+/// Panics on a joker, which `poker` cannot represent.
 impl From<SCard> for Card {
     fn from(c: SCard) -> Self {
+        assert!(!c.is_joker(), "jokers cannot be converted to poker cards");
         Card::new(c.rank(), c.suit())
     }
 }
 
+/// Rank-suit strings such as `"As"`, or `"Jk"` for a joker.
+impl std::fmt::Display for SCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.is_joker() {
+            true => write!(f, "Jk"),
+            false => write!(f, "{}", Card::from(*self).rank_suit_string()),
+        }
+    }
+}
+
 impl std::fmt::Debug for SCard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_joker() {
+            return f.debug_struct("SCard").field("raw", &self.0).finish();
+        }
         f.debug_struct("SCard")
             .field("rank", &self.rank())
             .field("suit", &self.suit())
@@ -94,11 +164,40 @@ impl std::fmt::Debug for SCard {
     }
 }
 
+/// Cards are serialized as rank-suit strings such as `"As"`. The first joker
+/// is written `"Jk"` and the second `"Jk2"`, so that a hand holding both
+/// reads back the same.
+impl serde::Serialize for SCard {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self == SCard::JOKERS[1] {
+            true => serializer.serialize_str("Jk2"),
+            false => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SCard {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        match &*s {
+            "Jk" => return Ok(SCard::JOKERS[0]),
+            "Jk2" => return Ok(SCard::JOKERS[1]),
+            _ => (),
+        }
+        s.parse::<Card>()
+            .map(SCard::from)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// A reduced representation of a hand.
 #[derive(PartialEq, Eq, Clone, Copy, Hash)]
 pub struct SHand(u32);
 
 impl SHand {
+    /// # Safety
+    ///
+    /// `hand` must be a valid packed hand, i.e. five sorted 6-bit card values.
     #[inline]
     pub unsafe fn unsafe_from_raw(hand: u32) -> Self {
         SHand(hand)
@@ -209,6 +308,44 @@ mod tests {
         assert_eq!(lcs, scs);
     }
 
+    #[test]
+    fn short_deck_starts_at_six() {
+        let deck = SCard::short_deck().collect::<Vec<_>>();
+        assert_eq!(deck.len(), 36);
+        assert!(deck.contains(&SCard::new(Rank::Six, Suit::Clubs)));
+        assert!(!deck.contains(&SCard::new(Rank::Five, Suit::Clubs)));
+    }
+
+    #[test]
+    fn jokers_are_always_wild() {
+        let deuces = Wild::NONE.with_rank(Rank::Two);
+        let joker = SCard::JOKERS[1];
+
+        assert!(joker.is_joker() && !SCard::deck().any(|c| c.is_joker()));
+        assert!(Wild::NONE.is_wild(joker) && deuces.is_wild(joker));
+        assert!(deuces.is_wild(SCard::new(Rank::Two, Suit::Hearts)));
+        assert!(!deuces.is_wild(SCard::new(Rank::Three, Suit::Hearts)));
+        assert_eq!(deuces.ranks().collect::<Vec<_>>(), [Rank::Two]);
+        assert_eq!(joker.to_string(), "Jk");
+    }
+
+    #[test]
+    fn scard_serde_roundtrip() {
+        let card = SCard::new(Rank::Ace, Suit::Spades);
+        let json = serde_json::to_string(&card).unwrap();
+
+        assert_eq!(json, r#""As""#);
+        assert_eq!(serde_json::from_str::<SCard>(&json).unwrap(), card);
+        assert!(serde_json::from_str::<SCard>(r#""Zz""#).is_err());
+
+        let jokers = serde_json::to_string(&SCard::JOKERS).unwrap();
+        assert_eq!(jokers, r#"["Jk","Jk2"]"#);
+        assert_eq!(
+            serde_json::from_str::<[SCard; 2]>(&jokers).unwrap(),
+            SCard::JOKERS
+        );
+    }
+
     #[test]
     fn hand_roundtrip() {
         let orig: &[SCard] = &[
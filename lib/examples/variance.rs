@@ -0,0 +1,143 @@
+//! Compares the error of each sampling strategy against the exact answer, as
+//! the number of samples grows. Run with
+//!
+//! ```sh
+//! cargo run --release -p poker-assistant --example variance
+//! ```
+
+use compact_poker::SCard;
+use poker::Card;
+use poker_assistant::prediction::{
+    model::PartialHand,
+    montecarlo::{with_sample_rng, SimParams},
+    range::Range,
+    sampling::Strategy,
+    stats::ScoreSummary,
+};
+
+const SAMPLES: [u64; 4] = [1000, 4000, 16000, 64000];
+const REPEATS: u64 = 40;
+
+fn cards(s: &str) -> Vec<SCard> {
+    s.split_whitespace()
+        .map(|c| c.parse::<Card>().unwrap().into())
+        .collect()
+}
+
+fn params<'a>(drawn: &[SCard], undrawn: u8, deck: &'a [SCard], seed: u64) -> SimParams<'a> {
+    SimParams {
+        player: PartialHand {
+            drawn: drawn.into(),
+            undrawn,
+        },
+        sample_deck: deck,
+        exchanges: Default::default(),
+        seed: Some(seed),
+        deck: Default::default(),
+        wild: Default::default(),
+    }
+}
+
+/// Root mean square error of the mean over repeated runs with different
+/// seeds.
+fn rmse(exact: f32, mean: impl Fn(u64) -> f32) -> f64 {
+    let sum = (0..REPEATS)
+        .map(|seed| (mean(seed) as f64 - exact as f64).powi(2))
+        .sum::<f64>();
+    (sum / REPEATS as f64).sqrt()
+}
+
+fn print_row(name: &str, errors: impl Iterator<Item = f64>) {
+    let errors = errors.map(|e| format!("{e:>10.6}")).collect::<String>();
+    println!("{name:<24}{errors}");
+}
+
+fn print_header(title: &str) {
+    println!("\n{title}");
+    let header = SAMPLES
+        .iter()
+        .map(|n| format!("{n:>10}"))
+        .collect::<String>();
+    println!("{:<24}{header}", "RMSE of mean at n =");
+}
+
+fn main() {
+    // Strength of a hand on the flop.
+    let drawn = cards("As Kh");
+    let deck = SCard::deck()
+        .filter(|c| !drawn.contains(c))
+        .collect::<Vec<_>>();
+    let exact = params(&drawn, 3, &deck, 0).summarize(u64::MAX, 10).mean;
+
+    print_header("As Kh with 3 cards to come");
+    for strategy in [
+        Strategy::Uniform,
+        Strategy::Stratified,
+        Strategy::Antithetic,
+        Strategy::SuitPermuted,
+    ] {
+        let errors = SAMPLES.iter().map(|n| {
+            rmse(exact, |seed| {
+                params(&drawn, 3, &deck, seed)
+                    .summarize_with(&strategy, *n, 10)
+                    .mean
+            })
+        });
+        print_row(&format!("{strategy:?}"), errors);
+    }
+
+    // Strength of an opponent holding a hand from a range, with two cards to
+    // come.
+    let board = cards("Ah 7d 2c");
+    let range: Range = "AA, KK, 77, AK:0.5, AQs, 87s".parse().unwrap();
+    let deck = SCard::deck()
+        .filter(|c| !board.contains(c))
+        .collect::<Vec<_>>();
+
+    let (mut total, mut weighted_mean) = (0.0, 0.0);
+    for (i, a) in deck.iter().enumerate() {
+        for b in &deck[i + 1..] {
+            let weight = range.weight(*a, *b);
+            if weight == 0.0 {
+                continue;
+            }
+            let mut drawn = board.clone();
+            drawn.extend([*a, *b]);
+            let rest = deck
+                .iter()
+                .filter(|c| !drawn.contains(c))
+                .copied()
+                .collect::<Vec<_>>();
+            total += weight;
+            weighted_mean +=
+                weight * params(&drawn, 2, &rest, 0).summarize(u64::MAX, 10).mean as f64;
+        }
+    }
+    let exact = (weighted_mean / total) as f32;
+
+    print_header("Range on Ah 7d 2c with 2 cards to come");
+    let errors = SAMPLES.iter().map(|n| {
+        rmse(exact, |seed| {
+            let params = params(&board, 4, &deck, seed);
+            let scores = (0..*n)
+                .map(|i| {
+                    let result = with_sample_rng(params.seed, i, |rng| params.run_sample(rng));
+                    let hole = &result.sampled_undrawn;
+                    (result.score, range.weight(hole[0], hole[1]))
+                })
+                .collect::<Vec<_>>();
+            ScoreSummary::from_weighted_scores(&scores, 10).mean
+        })
+    });
+    print_row("Uniform, weighted", errors);
+
+    let importance = Strategy::Importance(range.clone());
+    let errors = SAMPLES.iter().map(|n| {
+        rmse(exact, |seed| {
+            params(&board, 4, &deck, seed)
+                .summarize_with(&importance, *n, 10)
+                .mean
+        })
+    });
+    print_row("Importance", errors);
+}
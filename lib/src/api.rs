@@ -0,0 +1,242 @@
+//! Request and response types for serving simulations as JSON.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::prediction::{
+    deck::Deck,
+    equity::{Equity, Showdown},
+    model::{Game, PartialHand},
+    montecarlo::SimParams,
+    stats::ScoreSummary,
+    stud::cards_left,
+};
+
+/// The game to simulate.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameDescription {
+    /// Directives in the s-expression language of `poker-assistant-tui`.
+    Dsl(String),
+
+    /// A game seen from the player's point of view.
+    Game(Box<Game>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimulationRequest {
+    #[serde(flatten)]
+    pub game: GameDescription,
+
+    /// Number of samples to simulate. The server picks a default if absent.
+    #[serde(default)]
+    pub samples: Option<u64>,
+
+    /// Number of histogram bins in each summary.
+    #[serde(default = "default_bins")]
+    pub bins: usize,
+}
+
+fn default_bins() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimulationResponse {
+    pub hands: Vec<HandReport>,
+}
+
+/// Results for a single hand.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HandReport {
+    pub name: String,
+
+    /// Equity against the other hands in the response, if there are any.
+    pub equity: Option<Equity>,
+
+    /// Distribution of the final score of this hand.
+    pub summary: ScoreSummary,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    #[error("Card {0} is known more than once")]
+    DuplicateCard(String),
+
+    #[error("Card {0} is not in the {1} deck")]
+    NotInDeck(String, Deck),
+
+    #[error("Hand {0} would have {1} cards, but must have between 5 and 7")]
+    BadHandSize(String, usize),
+
+    #[error("Game needs {0} unknown cards, but only {1} are left in the deck")]
+    DeckExhausted(usize, usize),
+}
+
+/// Check that `game` can be simulated.
+pub fn validate_game(game: &Game) -> Result<(), GameError> {
+    let mut seen = HashSet::new();
+    let known = game
+        .known_existing_cards()
+        .chain(game.community.drawn.iter().copied())
+        .chain(game.player.exchanged.iter().copied())
+        .chain(game.dead.iter().copied());
+    // Games are never wild, so jokers are not in the deck either.
+    for c in known {
+        if !game.deck.contains(c) {
+            return Err(GameError::NotInDeck(c.to_string(), game.deck));
+        }
+        if !seen.insert(c) {
+            return Err(GameError::DuplicateCard(c.to_string()));
+        }
+    }
+
+    let deck = game.sample_deck();
+    let showdown = Showdown::from_game(game, &deck);
+    for (name, c) in hand_names(game).zip(&showdown.contenders) {
+        let size = c.known.len() + c.sampled.len();
+        if !(5..=7).contains(&size) {
+            return Err(GameError::BadHandSize(name, size));
+        }
+    }
+    let needed = showdown.n_sampled + showdown.n_replacements();
+    let left = cards_left(game);
+    if needed > left {
+        return Err(GameError::DeckExhausted(needed, left));
+    }
+    Ok(())
+}
+
+/// The player is called "player", and opponents "opponent 1", "opponent 2",
+/// and so on.
+fn hand_names(game: &Game) -> impl Iterator<Item = String> {
+    std::iter::once("player".to_owned())
+        .chain((1..=game.opponents.len()).map(|i| format!("opponent {i}")))
+}
+
+/// Simulate every hand in `game`, with equities for the player and each
+/// opponent.
+pub fn simulate_game(
+    game: &Game,
+    samples: u64,
+    bins: usize,
+) -> Result<SimulationResponse, GameError> {
+    validate_game(game)?;
+
+    let deck = game.sample_deck();
+    let showdown = Showdown::from_game(game, &deck);
+    let equities = showdown.equity(samples);
+
+    let hands = hand_names(game)
+        .zip(&showdown.contenders)
+        .zip(equities)
+        .map(|((name, c), equity)| {
+            let params = SimParams {
+                player: PartialHand {
+                    drawn: c.known.clone(),
+                    undrawn: c.sampled.len() as u8,
+                },
+                sample_deck: &deck,
+                exchanges: c.exchanges.clone(),
+                seed: None,
+                deck: game.deck,
+                wild: Default::default(),
+            };
+            HandReport {
+                name,
+                equity: Some(equity),
+                summary: params.summarize(samples, bins),
+            }
+        })
+        .collect();
+
+    Ok(SimulationResponse { hands })
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_poker::SCard;
+    use poker::{Rank, Suit};
+
+    use super::*;
+    use crate::game_repr::{get_deals, holdem};
+
+    #[test]
+    fn request_accepts_dsl_or_game() {
+        let request: SimulationRequest =
+            serde_json::from_str(r#"{"dsl": "(plot-cards self \"? ?\")", "samples": 10}"#).unwrap();
+        assert_eq!(
+            request.game,
+            GameDescription::Dsl(r#"(plot-cards self "? ?")"#.into())
+        );
+        assert_eq!(request.samples, Some(10));
+        assert_eq!(request.bins, 100);
+
+        let game = Game::from_deals(1, get_deals(holdem()));
+        let json = serde_json::to_string(&GameDescription::Game(Box::new(game.clone()))).unwrap();
+        let request: SimulationRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(request.game, GameDescription::Game(Box::new(game)));
+        assert_eq!(request.samples, None);
+    }
+
+    #[test]
+    fn simulate_game_reports_every_hand() {
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.player.hole.add_cards([
+            SCard::new(Rank::Ace, Suit::Spades),
+            SCard::new(Rank::Ace, Suit::Hearts),
+        ]);
+
+        let response = simulate_game(&game, 1000, 10).unwrap();
+
+        assert_eq!(response.hands.len(), 2);
+        assert_eq!(response.hands[1].name, "opponent 1");
+        assert_eq!(response.hands[0].summary.histogram.len(), 10);
+        assert!(response.hands[0].equity.unwrap().equity > 0.7);
+    }
+
+    #[test]
+    fn invalid_games_are_rejected() {
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.community
+            .drawn
+            .push(SCard::new(Rank::Ace, Suit::Spades));
+        game.player
+            .hole
+            .drawn
+            .push(SCard::new(Rank::Ace, Suit::Spades));
+        assert_eq!(
+            validate_game(&game),
+            Err(GameError::DuplicateCard("As".into()))
+        );
+
+        let game = Game::default();
+        assert_eq!(
+            validate_game(&game),
+            Err(GameError::BadHandSize("player".into(), 0))
+        );
+
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.deck = Deck::Short;
+        game.player
+            .hole
+            .add_cards([SCard::new(Rank::Five, Suit::Spades)]);
+        assert_eq!(
+            validate_game(&game),
+            Err(GameError::NotInDeck("5s".into(), Deck::Short))
+        );
+
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.player.hole.add_cards(SCard::JOKERS);
+        assert_eq!(
+            validate_game(&game),
+            Err(GameError::NotInDeck("Jk".into(), Deck::Standard))
+        );
+    }
+}
@@ -2,11 +2,24 @@ use std::{iter::Sum, ops::Add};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "type")]
 pub enum Round {
-    Deal { name: Option<String>, counts: Deal },
-    Exchange { name: Option<String>, max: u8 },
+    Deal {
+        name: Option<String>,
+        counts: Deal,
+    },
+    Exchange {
+        name: Option<String>,
+        max: u8,
+    },
+
+    /// Each player throws away `n` of their hole cards, which are not
+    /// replaced.
+    Discard {
+        name: Option<String>,
+        n: u8,
+    },
 }
 
 impl Round {
@@ -14,11 +27,12 @@ impl Round {
         match self {
             Round::Deal { name, .. } => name.as_deref(),
             Round::Exchange { name, .. } => name.as_deref(),
+            Round::Discard { name, .. } => name.as_deref(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Deal {
     #[serde(default)]
     pub stud: u8,
@@ -48,6 +62,17 @@ impl Sum for Deal {
     }
 }
 
+/// Hole cards each player discards over the whole hand.
+pub fn get_discards(rounds: &[Round]) -> u8 {
+    rounds
+        .iter()
+        .map(|r| match r {
+            Round::Discard { n, .. } => *n,
+            _ => 0,
+        })
+        .sum()
+}
+
 pub fn get_deals(rounds: impl IntoIterator<Item = Round>) -> impl IntoIterator<Item = Deal> {
     rounds.into_iter().filter_map(|r| match r {
         Round::Deal { counts: d, .. } => Some(d),
@@ -137,3 +162,133 @@ pub fn five_card_stud() -> Vec<Round> {
         },
     ]
 }
+
+pub fn seven_card_stud() -> Vec<Round> {
+    vec![
+        Round::Deal {
+            name: Some("Third Street".to_owned()),
+            counts: Deal {
+                hole: 2,
+                stud: 1,
+                ..Default::default()
+            },
+        },
+        Round::Deal {
+            name: Some("Fourth Street".to_owned()),
+            counts: Deal {
+                stud: 1,
+                ..Default::default()
+            },
+        },
+        Round::Deal {
+            name: Some("Fifth Street".to_owned()),
+            counts: Deal {
+                stud: 1,
+                ..Default::default()
+            },
+        },
+        Round::Deal {
+            name: Some("Sixth Street".to_owned()),
+            counts: Deal {
+                stud: 1,
+                ..Default::default()
+            },
+        },
+        Round::Deal {
+            name: Some("Seventh Street".to_owned()),
+            counts: Deal {
+                hole: 1,
+                ..Default::default()
+            },
+        },
+    ]
+}
+
+/// Razz deals like seven-card stud, but the lowest hand wins.
+pub fn razz() -> Vec<Round> {
+    seven_card_stud()
+}
+
+/// Stud eight-or-better deals like seven-card stud, but the pot is split
+/// between the highest hand and the lowest with no card above an eight.
+pub fn stud_eight() -> Vec<Round> {
+    seven_card_stud()
+}
+
+/// Short-deck hold'em deals like hold'em, from a deck without the twos to
+/// fives. See [`Deck::Short`](crate::prediction::deck::Deck::Short).
+pub fn short_deck_holdem() -> Vec<Round> {
+    holdem()
+}
+
+/// Pineapple deals like hold'em, but with three hole cards, one of which is
+/// discarded before the flop.
+pub fn pineapple() -> Vec<Round> {
+    let mut rounds = holdem();
+    rounds[0] = Round::Deal {
+        name: Some("Deal".to_owned()),
+        counts: Deal {
+            hole: 3,
+            ..Default::default()
+        },
+    };
+    rounds.insert(
+        1,
+        Round::Discard {
+            name: Some("Discard".to_owned()),
+            n: 1,
+        },
+    );
+    rounds
+}
+
+/// Crazy Pineapple deals like Pineapple, but the hole card is discarded after
+/// the flop.
+pub fn crazy_pineapple() -> Vec<Round> {
+    let mut rounds = pineapple();
+    rounds.swap(1, 2);
+    rounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stud_deals_seven_cards() {
+        let total = get_deals(seven_card_stud()).into_iter().sum::<Deal>();
+
+        assert_eq!(
+            total,
+            Deal {
+                hole: 3,
+                stud: 4,
+                community: 0
+            }
+        );
+        assert_eq!(razz(), seven_card_stud());
+        assert_eq!(stud_eight(), seven_card_stud());
+    }
+
+    #[test]
+    fn pineapple_discards_a_hole_card() {
+        for rounds in [pineapple(), crazy_pineapple()] {
+            let total = get_deals(rounds.clone()).into_iter().sum::<Deal>();
+            assert_eq!(total.hole, 3);
+            assert_eq!(total.community, 5);
+            assert_eq!(get_discards(&rounds), 1);
+        }
+
+        let names = |rounds: Vec<Round>| {
+            rounds
+                .iter()
+                .map(|r| r.name().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(crazy_pineapple()),
+            ["Deal", "Flop", "Discard", "Turn", "River"]
+        );
+        assert_eq!(get_discards(&holdem()), 0);
+    }
+}
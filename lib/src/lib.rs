@@ -1,6 +1,8 @@
 pub mod prediction;
+pub mod api;
 pub mod commands;
 pub mod game_repr;
+pub mod session;
 
 /*
 fn main() {
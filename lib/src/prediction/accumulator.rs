@@ -0,0 +1,410 @@
+//! Statistics gathered one sample at a time, so that memory use does not grow
+//! with the number of samples. Each rayon worker thread adds samples to its
+//! own accumulator, and the accumulators are merged at the end.
+
+use std::sync::Mutex;
+
+use poker_assistant_lookup::N_HANDS;
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+use strum::EnumCount;
+
+use super::{
+    category::HandCategory,
+    deck::relative_score,
+    equity::Equity,
+    stats::{score_quantile, ScoreSummary, Z_95},
+};
+
+/// Number of equal slices of the quantile range that scores are counted in.
+/// It is divisible by most histogram sizes, so bins line up with buckets.
+const QUANTILE_BUCKETS: usize = 14400;
+
+/// Running statistics of hand scores, taking constant memory however many
+/// are added.
+///
+/// Quantiles are approximate, rounded down to a multiple of
+/// `1 / QUANTILE_BUCKETS`. Everything else is exact. Summaries of every
+/// possible score use [`ScoreSummary::from_every_score`] for exact quantiles. Unweighted samples are
+/// totalled as integers, so results do not depend on the order accumulators
+/// are merged in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreAccumulator {
+    /// Weight of the scores in each quantile bucket.
+    buckets: Vec<f64>,
+
+    /// Weight of the scores in each hand category.
+    categories: [f64; HandCategory::COUNT],
+
+    samples: u64,
+    weight: f64,
+    squared_weights: f64,
+
+    /// Sums of the scores and their squares, for samples of weight 1.
+    sum: u128,
+    squared_sum: u128,
+
+    /// Weighted sums of the quantiles and their squares, for other samples.
+    weighted_sum: f64,
+    weighted_squared_sum: f64,
+}
+
+impl Default for ScoreAccumulator {
+    fn default() -> Self {
+        Self {
+            buckets: vec![0.0; QUANTILE_BUCKETS],
+            categories: [0.0; HandCategory::COUNT],
+            samples: 0,
+            weight: 0.0,
+            squared_weights: 0.0,
+            sum: 0,
+            squared_sum: 0,
+            weighted_sum: 0.0,
+            weighted_squared_sum: 0.0,
+        }
+    }
+}
+
+impl ScoreAccumulator {
+    pub fn add(&mut self, score: u32) {
+        self.add_weighted(score, 1.0);
+    }
+
+    pub fn add_weighted(&mut self, score: u32, weight: f64) {
+        let bucket = relative_score(score) as usize * QUANTILE_BUCKETS / N_HANDS;
+        self.buckets[bucket] += weight;
+        self.categories[HandCategory::from_score(score).index()] += weight;
+        self.samples += 1;
+        self.weight += weight;
+        self.squared_weights += weight * weight;
+
+        if weight == 1.0 {
+            let score = relative_score(score) as u128;
+            self.sum += score;
+            self.squared_sum += score * score;
+        } else {
+            let q = score_quantile(score) as f64;
+            self.weighted_sum += weight * q;
+            self.weighted_squared_sum += weight * q * q;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (a, b) in self.buckets.iter_mut().zip(&other.buckets) {
+            *a += b;
+        }
+        for (a, b) in self.categories.iter_mut().zip(&other.categories) {
+            *a += b;
+        }
+        self.samples += other.samples;
+        self.weight += other.weight;
+        self.squared_weights += other.squared_weights;
+        self.sum += other.sum;
+        self.squared_sum += other.squared_sum;
+        self.weighted_sum += other.weighted_sum;
+        self.weighted_squared_sum += other.weighted_squared_sum;
+    }
+
+    /// Number of samples added.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Weighted mean and standard deviation of the scores, as quantiles.
+    pub fn mean_stddev(&self) -> (f64, f64) {
+        let n_hands = N_HANDS as f64;
+        let sum = self.sum as f64 / n_hands + self.weighted_sum;
+        let squared_sum = self.squared_sum as f64 / (n_hands * n_hands) + self.weighted_squared_sum;
+        let mean = sum / self.weight;
+        let var = (squared_sum / self.weight - mean * mean).max(0.0);
+        (mean, var.sqrt())
+    }
+
+    /// Number of equally-weighted samples that would be as informative as
+    /// these. Equal to [`ScoreAccumulator::samples`] if none are weighted.
+    pub fn effective_samples(&self) -> f64 {
+        self.weight.powi(2) / self.squared_weights
+    }
+
+    /// Lowest quantile bucket whose cumulative weight exceeds `rank`.
+    fn quantile_at_rank(&self, rank: f64) -> f32 {
+        let mut cumulative = 0.0;
+        let mut last = 0;
+        for (i, w) in self.buckets.iter().enumerate().filter(|(_, w)| **w > 0.0) {
+            cumulative += w;
+            last = i;
+            if cumulative > rank {
+                break;
+            }
+        }
+        last as f32 / QUANTILE_BUCKETS as f32
+    }
+
+    /// Summarize the scores. Standard errors use the effective sample size,
+    /// and the histogram is scaled to about the number of samples. Panics if
+    /// nothing has been added.
+    pub fn summary(&self, n_bins: usize) -> ScoreSummary {
+        assert!(self.samples > 0, "no scores to summarize");
+
+        let total = self.weight;
+        let n_eff = self.effective_samples();
+        let (mean, stddev) = self.mean_stddev();
+
+        let mut bins = vec![0.0; n_bins];
+        for (i, w) in self.buckets.iter().enumerate() {
+            bins[i * n_bins / QUANTILE_BUCKETS] += w;
+        }
+
+        // Order statistics either side of the median, in units of weight.
+        let half_width = Z_95 * n_eff.sqrt() / 2.0 * total / n_eff;
+        let p50_interval = (
+            self.quantile_at_rank((total / 2.0 - half_width).floor().max(0.0)),
+            self.quantile_at_rank((total / 2.0 + half_width).ceil()),
+        );
+
+        ScoreSummary {
+            samples: self.samples as usize,
+            exhaustive: false,
+            mean: mean as f32,
+            stddev: stddev as f32,
+            mean_stderr: (stddev / n_eff.sqrt()) as f32,
+            p50: self.quantile_at_rank(total / 2.0),
+            p50_interval,
+            percentiles: (0..=100)
+                .map(|i| self.quantile_at_rank(i as f64 * total / 100.0))
+                .collect(),
+            histogram: bins
+                .iter()
+                .map(|b| (b * self.samples as f64 / total).round() as usize)
+                .collect(),
+            categories: self.categories.map(|c| c / total),
+        }
+    }
+}
+
+impl FromParallelIterator<u32> for ScoreAccumulator {
+    fn from_par_iter<I: IntoParallelIterator<Item = u32>>(scores: I) -> Self {
+        scores.into_par_iter().map(|s| (s, 1.0)).collect()
+    }
+}
+
+impl FromParallelIterator<(u32, f64)> for ScoreAccumulator {
+    fn from_par_iter<I: IntoParallelIterator<Item = (u32, f64)>>(scores: I) -> Self {
+        // Rayon may split the work into many more pieces than there are
+        // threads, so accumulators are kept per thread rather than per piece,
+        // as each holds a sizeable array of buckets. Only the thread owning a
+        // lock ever takes it.
+        let per_thread = (0..rayon::current_num_threads())
+            .map(|_| Mutex::new(Self::default()))
+            .collect::<Vec<_>>();
+        scores.into_par_iter().for_each(|(s, w)| {
+            let thread = rayon::current_thread_index().unwrap_or(0);
+            per_thread[thread].lock().unwrap().add_weighted(s, w);
+        });
+
+        per_thread
+            .into_iter()
+            .map(|acc| acc.into_inner().unwrap())
+            .reduce(|mut a, b| {
+                a.merge(&b);
+                a
+            })
+            .unwrap()
+    }
+}
+
+impl FromIterator<u32> for ScoreAccumulator {
+    fn from_iter<I: IntoIterator<Item = u32>>(scores: I) -> Self {
+        scores.into_iter().map(|s| (s, 1.0)).collect()
+    }
+}
+
+impl FromIterator<(u32, f64)> for ScoreAccumulator {
+    fn from_iter<I: IntoIterator<Item = (u32, f64)>>(scores: I) -> Self {
+        let mut acc = Self::default();
+        for (s, w) in scores {
+            acc.add_weighted(s, w);
+        }
+        acc
+    }
+}
+
+/// Pot shares are counted in units of this size so that totals are exact,
+/// and do not depend on the order they were added up in. It is divisible by
+/// the size of every tie of up to 12 hands, in either half of a split pot.
+const SHARE_UNITS: u64 = 55440;
+
+/// Units of `pot` won by each score. The best score wins, and tied winners
+/// split the pot.
+fn pot_units<T: Ord>(scores: &[T], pot: u64) -> impl Iterator<Item = u64> + '_ {
+    let best = scores.iter().max();
+    let n_best = scores.iter().filter(|s| Some(*s) == best).count() as u64;
+    scores.iter().map(move |s| match Some(s) == best {
+        true => pot / n_best,
+        false => 0,
+    })
+}
+
+/// Running totals of one contender's results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Tally {
+    wins: u64,
+    ties: u64,
+    units: u64,
+    squared_units: u64,
+}
+
+impl std::ops::AddAssign for Tally {
+    fn add_assign(&mut self, other: Self) {
+        self.wins += other.wins;
+        self.ties += other.ties;
+        self.units += other.units;
+        self.squared_units += other.squared_units;
+    }
+}
+
+/// Wins, ties and pot shares of each contender over many showdowns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EquityAccumulator {
+    tallies: Vec<Tally>,
+    samples: u64,
+    unconditioned: u64,
+}
+
+impl EquityAccumulator {
+    pub fn new(n_contenders: usize) -> Self {
+        Self {
+            tallies: vec![Tally::default(); n_contenders],
+            samples: 0,
+            unconditioned: 0,
+        }
+    }
+
+    /// Add the scores of one showdown, in contender order. The best score
+    /// wins, and tied winners split the pot.
+    pub fn add(&mut self, scores: &[u32]) {
+        self.add_units(pot_units(scores, SHARE_UNITS));
+    }
+
+    /// Add one showdown whose pot is split between the best `high` score and
+    /// the best `low` score of the hands which qualify for low. The best high
+    /// score wins the whole pot if none do.
+    pub fn add_split(&mut self, high: &[u32], low: &[Option<u32>]) {
+        if low.iter().all(Option::is_none) {
+            return self.add(high);
+        }
+        let half = SHARE_UNITS / 2;
+        self.add_units(
+            pot_units(high, half)
+                .zip(pot_units(low, half))
+                .map(|(h, l)| h + l),
+        );
+    }
+
+    /// Add the units of the pot won by each contender in one showdown.
+    fn add_units(&mut self, units: impl Iterator<Item = u64>) {
+        for (t, units) in self.tallies.iter_mut().zip(units) {
+            if units > 0 {
+                *t += Tally {
+                    wins: (units == SHARE_UNITS) as u64,
+                    ties: (units < SHARE_UNITS) as u64,
+                    units,
+                    squared_units: units * units,
+                };
+            }
+        }
+        self.samples += 1;
+    }
+
+    /// Count a showdown in which some hand could not be dealt consistently
+    /// with what is known of it.
+    pub fn add_unconditioned(&mut self) {
+        self.unconditioned += 1;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (a, b) in self.tallies.iter_mut().zip(&other.tallies) {
+            *a += *b;
+        }
+        self.samples += other.samples;
+        self.unconditioned += other.unconditioned;
+    }
+
+    /// Number of showdowns added.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Equity of each contender.
+    pub fn equities(&self) -> Vec<Equity> {
+        let n = self.samples as f64;
+        let units = SHARE_UNITS as f64;
+        self.tallies
+            .iter()
+            .map(|t| {
+                let mean = t.units as f64 / units / n;
+                let var = (t.squared_units as f64 / (units * units) / n - mean * mean).max(0.0);
+                Equity {
+                    win: t.wins as f64 / n,
+                    tie: t.ties as f64 / n,
+                    equity: mean,
+                    stderr: (var / n).sqrt(),
+                    unconditioned: self.unconditioned as f64 / n,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merging_matches_adding_in_one_place() {
+        let n = N_HANDS as u32;
+        let scores = (0..1000).map(|i| i * 7 % n).collect::<Vec<_>>();
+
+        let whole = scores.iter().copied().collect::<ScoreAccumulator>();
+        let mut merged = scores[..300].iter().copied().collect::<ScoreAccumulator>();
+        merged.merge(&scores[300..].iter().copied().collect());
+        let parallel = scores.into_par_iter().collect::<ScoreAccumulator>();
+
+        assert_eq!(whole, merged);
+        assert_eq!(whole, parallel);
+        assert_eq!(whole.samples(), 1000);
+        assert_eq!(whole.effective_samples(), 1000.0);
+    }
+
+    #[test]
+    fn equity_splits_ties() {
+        let mut acc = EquityAccumulator::new(3);
+        acc.add(&[5, 5, 1]);
+        let mut other = EquityAccumulator::new(3);
+        other.add(&[1, 2, 9]);
+        acc.merge(&other);
+
+        let equity = acc.equities();
+
+        assert_eq!(acc.samples(), 2);
+        assert_eq!(equity[0].equity, 0.25);
+        assert_eq!(equity[0].tie, 0.5);
+        assert_eq!(equity[2].win, 0.5);
+        assert_eq!(equity[2].equity, 0.5);
+    }
+
+    #[test]
+    fn split_pots_halve_shares() {
+        let mut acc = EquityAccumulator::new(3);
+        // The first hand wins high, and ties for low with the third.
+        acc.add_split(&[9, 5, 1], &[Some(4), None, Some(4)]);
+        // Nobody qualifies for low, so the second hand scoops.
+        acc.add_split(&[1, 9, 5], &[None, None, None]);
+
+        let equity = acc.equities();
+
+        assert_eq!(equity[0].equity, (0.5 + 0.25) / 2.0);
+        assert_eq!(equity[0].tie, 0.5);
+        assert_eq!(equity[1].win, 0.5);
+        assert_eq!(equity[2].equity, 0.25 / 2.0);
+    }
+}
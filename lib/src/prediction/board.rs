@@ -0,0 +1,279 @@
+//! Texture of the community cards: how paired, suited and connected they
+//! are, and which hands are strongest on them.
+
+use std::cmp::Reverse;
+
+use compact_poker::SCard;
+use serde::{Deserialize, Serialize};
+
+use super::{category::HandCategory, deck::Deck, model::Game};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pairing {
+    Unpaired,
+    Paired,
+    TwoPair,
+    Trips,
+    FullHouse,
+    Quads,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suitedness {
+    /// No two cards share a suit.
+    Rainbow,
+
+    /// Some cards share a suit, but not all of them.
+    TwoTone,
+
+    /// Every card has the same suit.
+    Monotone,
+}
+
+/// A class of equally strong hands that can be made on the board.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoardHand {
+    pub category: HandCategory,
+
+    /// Best five cards of one of the hands.
+    pub cards: [SCard; 5],
+
+    /// Every pair of hole cards making a hand this strong.
+    pub holes: Vec<[SCard; 2]>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BoardTexture {
+    pub cards: Vec<SCard>,
+
+    /// Number of community cards still to come.
+    pub to_come: u8,
+
+    pub pairing: Pairing,
+    pub suitedness: Suitedness,
+
+    /// Most cards of any one suit.
+    pub max_suited: u8,
+
+    /// Whether a player can already have a flush with two hole cards.
+    pub flush_possible: bool,
+
+    /// Whether a flush is not yet possible, but could be with the cards to
+    /// come.
+    pub flush_draw: bool,
+
+    /// Most distinct ranks within any five consecutive ranks, counting aces
+    /// as high or low.
+    pub connectedness: u8,
+
+    /// High cards of the straights a player can already have with two hole
+    /// cards, as rank characters from highest to lowest, e.g. `['A', 'K']`.
+    pub straights: Vec<char>,
+
+    /// Whether a straight is not yet possible, but could be with the cards to
+    /// come.
+    pub straight_draw: bool,
+
+    /// Strongest hands that can be made with two hole cards from the deck,
+    /// the nuts first. Empty if fewer than 3 cards are on the board.
+    pub top_hands: Vec<BoardHand>,
+}
+
+/// Characters of each rank, from two to ace.
+pub(super) const RANK_CHARS: &[u8; 13] = b"23456789TJQKA";
+
+impl BoardTexture {
+    /// Analyse `board`, with `to_come` more cards to be dealt. Hole cards are
+    /// drawn from `sample_deck`, hands are ranked as in `deck`, and the `top_n`
+    /// strongest hands are listed.
+    pub fn new(
+        board: &[SCard],
+        to_come: u8,
+        deck: Deck,
+        sample_deck: &[SCard],
+        top_n: usize,
+    ) -> Self {
+        let mut rank_counts = [0u8; 13];
+        let mut suit_counts = [0u8; 4];
+        for c in board {
+            rank_counts[c.rank() as usize] += 1;
+            suit_counts[c.suit() as usize] += 1;
+        }
+
+        let mut groups = rank_counts
+            .iter()
+            .filter(|n| **n > 1)
+            .copied()
+            .collect::<Vec<_>>();
+        groups.sort_unstable_by(|a, b| b.cmp(a));
+        let pairing = match groups.as_slice() {
+            [] => Pairing::Unpaired,
+            [4, ..] => Pairing::Quads,
+            [3, 2, ..] | [3, 3, ..] => Pairing::FullHouse,
+            [3, ..] => Pairing::Trips,
+            [2, 2, ..] => Pairing::TwoPair,
+            _ => Pairing::Paired,
+        };
+
+        let max_suited = suit_counts.iter().copied().max().unwrap_or_default();
+        let suitedness = match (max_suited as usize, board.len()) {
+            (0..=1, _) => Suitedness::Rainbow,
+            (n, len) if n == len => Suitedness::Monotone,
+            _ => Suitedness::TwoTone,
+        };
+
+        // Whether each rank is on the board, with aces at both ends.
+        let present = |i: usize| rank_counts[(i + 12) % 13] > 0;
+        let in_window = |high: usize| (high - 4..=high).filter(|i| present(*i)).count() as u8;
+        let windows = (4..=13).rev().map(|high| (high, in_window(high)));
+        let connectedness = windows.clone().map(|(_, n)| n).max().unwrap_or_default();
+        let straights = windows
+            .filter(|(_, n)| *n >= 3)
+            .map(|(high, _)| RANK_CHARS[(high + 12) % 13] as char)
+            .collect::<Vec<_>>();
+
+        BoardTexture {
+            cards: board.to_vec(),
+            to_come,
+            pairing,
+            suitedness,
+            max_suited,
+            flush_possible: max_suited >= 3,
+            flush_draw: max_suited < 3 && max_suited + to_come >= 3,
+            connectedness,
+            straight_draw: straights.is_empty() && connectedness + to_come >= 3,
+            straights,
+            top_hands: top_hands(board, deck, sample_deck, top_n),
+        }
+    }
+
+    /// Analyse the community cards of a game.
+    pub fn from_game(game: &Game, top_n: usize) -> Self {
+        Self::new(
+            &game.community.drawn,
+            game.community.undrawn,
+            game.deck,
+            &game.sample_deck(),
+            top_n,
+        )
+    }
+
+    /// The strongest hand that can be made on the board, if any.
+    pub fn nuts(&self) -> Option<&BoardHand> {
+        self.top_hands.first()
+    }
+}
+
+/// The `n` strongest classes of hand that can be made with two hole cards
+/// from `sample_deck`, ranked as in `deck`. Hands are equally strong if they
+/// have the same category and ranks.
+fn top_hands(board: &[SCard], deck: Deck, sample_deck: &[SCard], n: usize) -> Vec<BoardHand> {
+    if board.len() < 3 {
+        return vec![];
+    }
+
+    let mut made = sample_deck
+        .iter()
+        .enumerate()
+        .flat_map(|(i, a)| sample_deck[i + 1..].iter().map(move |b| [*a, *b]))
+        .map(|hole| {
+            let mut cards = board.to_vec();
+            cards.extend(hole);
+            let (hand, score) = deck.score_superhand(&cards);
+            (score, hand.members(), hole)
+        })
+        .collect::<Vec<_>>();
+    made.sort_unstable_by_key(|(score, ..)| Reverse(*score));
+
+    let strength = |score: u32, cards: &[SCard; 5]| {
+        let mut ranks = cards.map(|c| c.rank());
+        ranks.sort_unstable();
+        (HandCategory::from_score(score), ranks)
+    };
+
+    let mut hands: Vec<BoardHand> = vec![];
+    let mut last = None;
+    for (score, cards, hole) in made {
+        let key = strength(score, &cards);
+        if last.as_ref() == Some(&key) {
+            hands.last_mut().unwrap().holes.push(hole);
+            continue;
+        }
+        if hands.len() == n {
+            break;
+        }
+        hands.push(BoardHand {
+            category: key.0,
+            cards,
+            holes: vec![hole],
+        });
+        last = Some(key);
+    }
+    hands
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::Card;
+
+    use super::*;
+
+    fn cards(s: &str) -> Vec<SCard> {
+        s.split_whitespace()
+            .map(|c| c.parse::<Card>().unwrap().into())
+            .collect()
+    }
+
+    fn texture(board: &str, to_come: u8) -> BoardTexture {
+        let board = cards(board);
+        let deck = SCard::deck()
+            .filter(|c| !board.contains(c))
+            .collect::<Vec<_>>();
+        BoardTexture::new(&board, to_come, Deck::Standard, &deck, 3)
+    }
+
+    #[test]
+    fn classifies_flops() {
+        let t = texture("Ah Kh Qh", 2);
+        assert_eq!(t.pairing, Pairing::Unpaired);
+        assert_eq!(t.suitedness, Suitedness::Monotone);
+        assert!(t.flush_possible && !t.flush_draw);
+        assert_eq!(t.connectedness, 3);
+        assert_eq!(t.straights, vec!['A']);
+
+        let nuts = t.nuts().unwrap();
+        assert_eq!(nuts.category, HandCategory::StraightFlush);
+        assert_eq!(
+            nuts.holes,
+            vec![<[SCard; 2]>::try_from(cards("Th Jh")).unwrap()]
+        );
+
+        let t = texture("7c 7d 2s", 2);
+        assert_eq!(t.pairing, Pairing::Paired);
+        assert_eq!(t.suitedness, Suitedness::Rainbow);
+        assert!(!t.flush_possible && t.flush_draw);
+        assert!(t.straights.is_empty() && t.straight_draw);
+        assert_eq!(t.nuts().unwrap().category, HandCategory::FourOfAKind);
+        assert_eq!(t.top_hands[1].category, HandCategory::FullHouse);
+    }
+
+    #[test]
+    fn classifies_rivers() {
+        let t = texture("5c 4d 3c Ah Kc", 0);
+        assert_eq!(t.suitedness, Suitedness::TwoTone);
+        assert_eq!(t.max_suited, 3);
+        assert!(!t.flush_draw && !t.straight_draw);
+        assert_eq!(t.straights, vec!['7', '6', '5']);
+        assert_eq!(t.connectedness, 4);
+        let nuts = t.nuts().unwrap();
+        assert_eq!(nuts.category, HandCategory::Flush);
+        assert_eq!(
+            nuts.holes,
+            vec![<[SCard; 2]>::try_from(cards("Qc Ac")).unwrap()]
+        );
+
+        let t = texture("9s 9d 9h 2c 2d", 0);
+        assert_eq!(t.pairing, Pairing::FullHouse);
+        assert!(texture("Ac", 4).top_hands.is_empty());
+    }
+}
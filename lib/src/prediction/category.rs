@@ -0,0 +1,232 @@
+use compact_poker::SCard;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    deck::{short_deck_score, Deck},
+    wild::FIVE_OF_A_KIND,
+};
+
+/// Category of a 5-card poker hand, from worst to best.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumIter,
+    strum::EnumCount,
+)]
+pub enum HandCategory {
+    #[strum(serialize = "High card")]
+    HighCard,
+    #[strum(serialize = "Pair")]
+    Pair,
+    #[strum(serialize = "Two pair")]
+    TwoPair,
+    #[strum(serialize = "Three of a kind")]
+    ThreeOfAKind,
+    #[strum(serialize = "Straight")]
+    Straight,
+    #[strum(serialize = "Flush")]
+    Flush,
+    #[strum(serialize = "Full house")]
+    FullHouse,
+    #[strum(serialize = "Four of a kind")]
+    FourOfAKind,
+    #[strum(serialize = "Straight flush")]
+    StraightFlush,
+    #[strum(serialize = "Five of a kind")]
+    FiveOfAKind,
+}
+
+/// Number of distinct 5-card hands in each category, in the same order as
+/// [`HandCategory`].
+const CATEGORY_SIZES: [u32; 9] = [
+    1_302_540, 1_098_240, 123_552, 54_912, 10_200, 5_108, 3_744, 624, 40,
+];
+
+/// Number of distinct short-deck hands in each category, from worst to best.
+const SHORT_DECK_CATEGORY_SIZES: [(HandCategory, u32); 9] = [
+    (HandCategory::HighCard, 122_400),
+    (HandCategory::Pair, 193_536),
+    (HandCategory::TwoPair, 36_288),
+    (HandCategory::ThreeOfAKind, 16_128),
+    (HandCategory::Straight, 6_120),
+    (HandCategory::FullHouse, 1_728),
+    (HandCategory::Flush, 480),
+    (HandCategory::FourOfAKind, 288),
+    (HandCategory::StraightFlush, 24),
+];
+
+impl HandCategory {
+    /// Category of a hand given its absolute score.
+    ///
+    /// Scores are ranks in the lookup table, which is sorted by strength, so
+    /// each category occupies a contiguous range of scores.
+    pub fn from_score(score: u32) -> Self {
+        use strum::IntoEnumIterator;
+
+        if score >= FIVE_OF_A_KIND {
+            return HandCategory::FiveOfAKind;
+        }
+        if Deck::of_score(score) == Deck::Short {
+            let mut upper = 0;
+            for (category, size) in SHORT_DECK_CATEGORY_SIZES {
+                upper += size;
+                if score < short_deck_score(upper) {
+                    return category;
+                }
+            }
+            return HandCategory::StraightFlush;
+        }
+
+        let mut upper = 0;
+        for (category, size) in HandCategory::iter().zip(CATEGORY_SIZES) {
+            upper += size;
+            if score < upper {
+                return category;
+            }
+        }
+        HandCategory::StraightFlush
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// Position of this category from worst to best when hands are ranked as
+    /// in `deck`. A flush beats a full house in the short deck.
+    pub fn strength(&self, deck: Deck) -> usize {
+        match (deck, self) {
+            (Deck::Short, HandCategory::Flush) => HandCategory::FullHouse.index(),
+            (Deck::Short, HandCategory::FullHouse) => HandCategory::Flush.index(),
+            _ => self.index(),
+        }
+    }
+}
+
+/// Cards in `sample_deck` which would improve the category of the best hand
+/// in `known` if they were drawn next. Hands are ranked as in `deck`.
+///
+/// Returns nothing if fewer than 5 cards are known, as there is no hand to
+/// improve yet.
+pub fn outs(known: &[SCard], deck: Deck, sample_deck: &[SCard]) -> Vec<SCard> {
+    if known.len() < 5 {
+        return vec![];
+    }
+
+    let current = HandCategory::from_score(deck.score_superhand(known).1).strength(deck);
+    let mut cards = known.to_vec();
+    sample_deck
+        .iter()
+        .copied()
+        .filter(|c| {
+            cards.push(*c);
+            let (_, score) = deck.score_superhand(&cards);
+            cards.pop();
+            HandCategory::from_score(score).strength(deck) > current
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::{Rank, Suit};
+    use poker_assistant_lookup::{LOOKUP, N_HANDS, N_SHORT_DECK_HANDS};
+
+    use super::*;
+
+    fn hand(cards: &str) -> Vec<SCard> {
+        cards
+            .split_whitespace()
+            .map(|c| c.parse::<poker::Card>().unwrap().into())
+            .collect()
+    }
+
+    #[test]
+    fn category_sizes_cover_all_hands() {
+        assert_eq!(CATEGORY_SIZES.iter().sum::<u32>() as usize, N_HANDS);
+        assert_eq!(
+            SHORT_DECK_CATEGORY_SIZES
+                .iter()
+                .map(|(_, n)| n)
+                .sum::<u32>() as usize,
+            N_SHORT_DECK_HANDS
+        );
+    }
+
+    #[test]
+    fn categorizes_hands() {
+        let cases = [
+            ("2c 5d 9h Js Ks", HandCategory::HighCard),
+            ("Ac Ad 2h 7s 9c", HandCategory::Pair),
+            ("2c 2d 3h 3s Ac", HandCategory::TwoPair),
+            ("2c 2d 2h 3s Ac", HandCategory::ThreeOfAKind),
+            ("Ac 2d 3h 4s 5c", HandCategory::Straight),
+            ("2h 5h 9h Jh Kh", HandCategory::Flush),
+            ("2c 2d 2h 3s 3c", HandCategory::FullHouse),
+            ("Ac Ad Ah As Kc", HandCategory::FourOfAKind),
+            ("As Ks Qs Js Ts", HandCategory::StraightFlush),
+        ];
+
+        for (cards, expected) in cases {
+            let cards = hand(cards);
+            assert_eq!(
+                HandCategory::from_score(LOOKUP[&cards[..]]),
+                expected,
+                "{cards:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn outs_to_a_flush() {
+        let known = hand("2h 5h 9h Jh Kc");
+        let deck = SCard::deck()
+            .filter(|c| !known.contains(c))
+            .collect::<Vec<_>>();
+
+        let outs = outs(&known, Deck::Standard, &deck);
+
+        // 9 hearts for the flush, 3 cards each to pair the 2, 5, 9 and J, and
+        // the 2 non-heart kings.
+        assert_eq!(outs.len(), 9 + 4 * 3 + 2);
+        assert!(outs.contains(&SCard::new(Rank::Ace, Suit::Hearts)));
+    }
+
+    #[test]
+    fn outs_use_the_deck_ranking() {
+        let known = hand("Ah 6c 7d 8s Kc");
+        let deck = SCard::short_deck()
+            .filter(|c| !known.contains(c))
+            .collect::<Vec<_>>();
+        let nine = SCard::new(Rank::Nine, Suit::Spades);
+
+        // A-6-7-8-9 is only a straight in the short deck.
+        assert!(outs(&known, Deck::Short, &deck).contains(&nine));
+        assert!(!outs(&known, Deck::Standard, &deck).contains(&nine));
+    }
+
+    #[test]
+    fn short_deck_flushes_beat_full_houses() {
+        let known = hand("Ah Ad Ac Kh Kd 9h Th");
+        let deck = SCard::short_deck()
+            .filter(|c| !known.contains(c))
+            .collect::<Vec<_>>();
+
+        let outs = outs(&known, Deck::Short, &deck);
+
+        // Any of the 5 remaining hearts makes a flush, and the last ace four
+        // of a kind. The last king only makes a better full house.
+        assert!(outs.contains(&SCard::new(Rank::Six, Suit::Hearts)));
+        assert!(outs.contains(&SCard::new(Rank::Ace, Suit::Spades)));
+        assert!(!outs.contains(&SCard::new(Rank::King, Suit::Spades)));
+        assert_eq!(outs.len(), 5 + 1);
+    }
+}
@@ -0,0 +1,290 @@
+//! Lazy enumeration of k-combinations in lexicographic order.
+//!
+//! Each combination has a rank, its position in that order, so work can be
+//! split into ranges of ranks and resumed from any of them.
+
+use std::ops::Range;
+
+use num_integer::binomial;
+use rayon::iter::{
+    plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer},
+    IndexedParallelIterator, ParallelIterator,
+};
+use smallvec::SmallVec;
+
+use super::model::HandVec;
+
+/// Indices of the chosen items, in increasing order.
+type Indices = SmallVec<[usize; 10]>;
+
+/// Every way of choosing `k` of `items`, in lexicographic order of their
+/// indices.
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations::with_ranks(items, k, 0..n_combinations(items.len(), k))
+}
+
+/// Parallel version of [`combinations`].
+pub fn par_combinations<T: Clone + Sync>(items: &[T], k: usize) -> ParCombinations<'_, T> {
+    ParCombinations {
+        items,
+        k,
+        ranks: 0..n_combinations(items.len(), k),
+    }
+}
+
+/// Number of ways of choosing `k` of `n` items.
+pub fn n_combinations(n: usize, k: usize) -> u64 {
+    match k <= n {
+        true => binomial(n as u64, k as u64),
+        false => 0,
+    }
+}
+
+/// Position of the combination with the given increasing indices, out of the
+/// combinations of `indices.len()` of `n` items.
+pub fn rank(n: usize, indices: &[usize]) -> u64 {
+    let k = indices.len();
+    let mut rank = 0;
+    let mut first = 0;
+    for (i, index) in indices.iter().enumerate() {
+        rank += (first..*index)
+            .map(|x| n_combinations(n - x - 1, k - i - 1))
+            .sum::<u64>();
+        first = index + 1;
+    }
+    rank
+}
+
+/// Indices of the combination of `k` of `n` items at position `rank`. Panics
+/// if there is no such combination.
+pub fn unrank(n: usize, k: usize, mut rank: u64) -> Indices {
+    assert!(rank < n_combinations(n, k), "rank out of range");
+    let mut indices = Indices::new();
+    let mut x = 0;
+    for i in 0..k {
+        loop {
+            let below = n_combinations(n - x - 1, k - i - 1);
+            if rank < below {
+                break;
+            }
+            rank -= below;
+            x += 1;
+        }
+        indices.push(x);
+        x += 1;
+    }
+    indices
+}
+
+/// Iterator over a range of ranks of the combinations of `k` items.
+/// Combinations of up to 7 items are yielded without allocating.
+#[derive(Debug, Clone)]
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    front: Indices,
+    back: Indices,
+    remaining: u64,
+}
+
+impl<'a, T: Clone> Combinations<'a, T> {
+    /// The combinations with the given ranks. Panics if the range goes past
+    /// the last combination.
+    pub fn with_ranks(items: &'a [T], k: usize, ranks: Range<u64>) -> Self {
+        let n = items.len();
+        let remaining = ranks.end.saturating_sub(ranks.start);
+        let (front, back) = match remaining {
+            0 => Default::default(),
+            _ => (unrank(n, k, ranks.start), unrank(n, k, ranks.end - 1)),
+        };
+        Self {
+            items,
+            front,
+            back,
+            remaining,
+        }
+    }
+
+    fn chosen(&self, indices: &[usize]) -> HandVec<T> {
+        indices.iter().map(|i| self.items[*i].clone()).collect()
+    }
+}
+
+/// Step to the next combination in lexicographic order, if there is one.
+fn advance(indices: &mut [usize], n: usize) {
+    let k = indices.len();
+    if let Some(i) = (0..k).rev().find(|i| indices[*i] < n - k + i) {
+        indices[i] += 1;
+        for j in i + 1..k {
+            indices[j] = indices[j - 1] + 1;
+        }
+    }
+}
+
+/// Step to the previous combination in lexicographic order, if there is one.
+fn retreat(indices: &mut [usize], n: usize) {
+    let k = indices.len();
+    let lowest = |i: usize| if i == 0 { 0 } else { indices[i - 1] + 1 };
+    if let Some(i) = (0..k).rev().find(|i| indices[*i] > lowest(*i)) {
+        indices[i] -= 1;
+        for (j, index) in indices.iter_mut().enumerate().skip(i + 1) {
+            *index = n - k + j;
+        }
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<'_, T> {
+    type Item = HandVec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let chosen = self.chosen(&self.front);
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            advance(&mut self.front, self.items.len());
+        }
+        Some(chosen)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Combinations<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let chosen = self.chosen(&self.back);
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            retreat(&mut self.back, self.items.len());
+        }
+        Some(chosen)
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Combinations<'_, T> {}
+
+/// Parallel iterator over a range of ranks of the combinations of `k` items,
+/// split between threads by rank.
+#[derive(Debug, Clone)]
+pub struct ParCombinations<'a, T> {
+    items: &'a [T],
+    k: usize,
+    ranks: Range<u64>,
+}
+
+impl<'a, T: Clone + Sync> ParCombinations<'a, T> {
+    /// The combinations with the given ranks.
+    pub fn with_ranks(items: &'a [T], k: usize, ranks: Range<u64>) -> Self {
+        Self { items, k, ranks }
+    }
+}
+
+impl<T: Clone + Send + Sync> ParallelIterator for ParCombinations<'_, T> {
+    type Item = HandVec<T>;
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T: Clone + Send + Sync> IndexedParallelIterator for ParCombinations<'_, T> {
+    fn len(&self) -> usize {
+        (self.ranks.end - self.ranks.start) as usize
+    }
+
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        callback.callback(self)
+    }
+}
+
+impl<'a, T: Clone + Send + Sync> Producer for ParCombinations<'a, T> {
+    type Item = HandVec<T>;
+    type IntoIter = Combinations<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Combinations::with_ranks(self.items, self.k, self.ranks)
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let mid = self.ranks.start + index as u64;
+        (
+            Self {
+                ranks: self.ranks.start..mid,
+                ..self.clone()
+            },
+            Self {
+                ranks: mid..self.ranks.end,
+                ..self
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::*;
+
+    #[test]
+    fn enumerates_in_order() {
+        assert_eq!(
+            combinations(&[1, 2], 2).collect::<Vec<_>>(),
+            vec![smallvec![1, 2] as HandVec<i32>]
+        );
+
+        let expected: Vec<HandVec<i32>> = vec![
+            smallvec![10, 11, 12],
+            smallvec![10, 11, 13],
+            smallvec![10, 11, 14],
+            smallvec![10, 12, 13],
+            smallvec![10, 12, 14],
+            smallvec![10, 13, 14],
+            smallvec![11, 12, 13],
+            smallvec![11, 12, 14],
+            smallvec![11, 13, 14],
+            smallvec![12, 13, 14],
+        ];
+        let items = [10, 11, 12, 13, 14];
+        assert_eq!(combinations(&items, 3).collect::<Vec<_>>(), expected);
+
+        let mut reversed = combinations(&items, 3).rev().collect::<Vec<_>>();
+        reversed.reverse();
+        assert_eq!(reversed, expected);
+    }
+
+    #[test]
+    fn ranks_roundtrip() {
+        for (r, c) in combinations(&(0..9).collect::<Vec<_>>(), 4).enumerate() {
+            assert_eq!(rank(9, &c), r as u64);
+            assert_eq!(unrank(9, 4, r as u64).as_slice(), c.as_slice());
+        }
+    }
+
+    #[test]
+    fn ranges_resume_where_they_left_off() {
+        let items = (0..20).collect::<Vec<_>>();
+        let all = combinations(&items, 12).collect::<Vec<_>>();
+        let mut resumed = Combinations::with_ranks(&items, 12, 0..1000).collect::<Vec<_>>();
+        resumed.extend(Combinations::with_ranks(&items, 12, 1000..all.len() as u64));
+
+        assert_eq!(all.len(), 125970);
+        assert_eq!(resumed, all);
+        assert_eq!(par_combinations(&items, 12).collect::<Vec<_>>(), all);
+        assert_eq!(combinations(&items, 0).count(), 1);
+        assert_eq!(combinations(&items, 21).count(), 0);
+    }
+}
@@ -0,0 +1,158 @@
+//! Decks a game can be dealt from, and how hands dealt from them are scored.
+//!
+//! Scores of short-deck hands lie above every standard score, spread over a
+//! range as wide as the standard one. Their quantile and category can then be
+//! found from the score alone, wherever it ends up. Scores are only ever
+//! compared with scores from the same deck. Five of a kind, which only wild
+//! cards can make, is scored above both ranges.
+
+use compact_poker::{SCard, SHand, Wild};
+use poker_assistant_lookup::{LOOKUP, N_HANDS, N_SHORT_DECK_HANDS, SHORT_DECK_LOOKUP};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    combinations::combinations,
+    wild::{score_wild, FIVE_OF_A_KIND},
+};
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Deck {
+    /// All 52 cards, ranked as usual.
+    #[default]
+    Standard,
+
+    /// 36 cards without the twos to fives, as in short-deck hold'em. A flush
+    /// beats a full house, and A-6-7-8-9 is the lowest straight.
+    Short,
+}
+
+impl Deck {
+    /// Every card in the deck.
+    pub fn cards(&self) -> Vec<SCard> {
+        match self {
+            Deck::Standard => SCard::deck().collect(),
+            Deck::Short => SCard::short_deck().collect(),
+        }
+    }
+
+    /// Jokers are not part of any deck. They are added to it separately.
+    pub fn contains(&self, card: SCard) -> bool {
+        match self {
+            _ if card.is_joker() => false,
+            Deck::Standard => true,
+            Deck::Short => card.rank() >= poker::Rank::Six,
+        }
+    }
+
+    /// Absolute score of a 5-card hand dealt from this deck.
+    pub fn score(&self, hand: SHand) -> u32 {
+        match self {
+            Deck::Standard => LOOKUP[hand],
+            Deck::Short => short_deck_score(SHORT_DECK_LOOKUP[hand]),
+        }
+    }
+
+    /// The best 5-card hand within `hand`, and its score. Jokers are wild.
+    pub fn score_superhand(&self, hand: &[SCard]) -> (SHand, u32) {
+        self.score_superhand_wild(hand, Wild::NONE)
+    }
+
+    /// The best 5-card hand within `hand`, and its score, where the cards
+    /// `wild` are wild.
+    pub fn score_superhand_wild(&self, hand: &[SCard], wild: Wild) -> (SHand, u32) {
+        if hand.iter().any(|c| wild.is_wild(*c)) {
+            return score_wild(*self, wild, hand);
+        }
+        combinations(hand, 5)
+            .map(|h| {
+                let sh = SHand::from(&h[..]);
+                (sh, self.score(sh))
+            })
+            .max_by_key(|(_h, s)| *s)
+            .unwrap()
+    }
+
+    /// Deck a score was given in. Five of a kind is counted as short.
+    pub fn of_score(score: u32) -> Self {
+        match score as usize >= N_HANDS {
+            true => Deck::Short,
+            false => Deck::Standard,
+        }
+    }
+}
+
+/// Score of the short-deck hand at `rank` in order of strength.
+pub(super) fn short_deck_score(rank: u32) -> u32 {
+    let scaled = rank as u64 * N_HANDS as u64 / N_SHORT_DECK_HANDS as u64;
+    (N_HANDS as u64 + scaled) as u32
+}
+
+/// Score on the standard scale, from 0 to `N_HANDS`, of a hand with the same
+/// quantile among the hands of its own deck. Five of a kind is at the top.
+#[inline]
+pub fn relative_score(score: u32) -> u32 {
+    match score >= FIVE_OF_A_KIND {
+        true => N_HANDS as u32 - 1,
+        false => score % N_HANDS as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::{Rank, Suit};
+
+    use super::*;
+    use crate::prediction::{category::HandCategory, stats::score_quantile};
+
+    #[test]
+    fn short_deck_scores_keep_their_order() {
+        let scores = (0..N_SHORT_DECK_HANDS as u32)
+            .map(short_deck_score)
+            .collect::<Vec<_>>();
+        assert!(scores.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(Deck::of_score(scores[0]), Deck::Short);
+        assert_eq!(score_quantile(scores[0]), 0.0);
+        assert!(score_quantile(*scores.last().unwrap()) > 0.999);
+    }
+
+    #[test]
+    fn flushes_beat_full_houses_in_a_short_deck() {
+        use Rank::*;
+        use Suit::*;
+
+        let cards = |cards: &[(Rank, Suit)]| {
+            cards
+                .iter()
+                .map(|(r, s)| SCard::new(*r, *s))
+                .collect::<Vec<_>>()
+        };
+        let board = [(Six, Hearts), (Six, Spades), (Nine, Hearts), (Jack, Hearts)];
+        let flush = cards(&[&board[..], &[(Seven, Hearts), (Eight, Hearts)]].concat());
+        let full_house = cards(&[&board[..], &[(Six, Clubs), (Nine, Clubs)]].concat());
+
+        for (deck, flush_wins) in [(Deck::Standard, false), (Deck::Short, true)] {
+            let flush = deck.score_superhand(&flush).1;
+            let full_house = deck.score_superhand(&full_house).1;
+            assert_eq!(flush > full_house, flush_wins);
+            assert_eq!(HandCategory::from_score(flush), HandCategory::Flush);
+            assert_eq!(
+                HandCategory::from_score(full_house),
+                HandCategory::FullHouse
+            );
+        }
+    }
+}
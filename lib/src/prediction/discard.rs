@@ -0,0 +1,141 @@
+//! Choosing which hole cards to throw away in Pineapple and Crazy Pineapple.
+
+use compact_poker::SCard;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    combinations::combinations,
+    equity::{Equity, Showdown},
+    model::{DiscardError, Game, HandVec},
+    range::Range,
+};
+
+pub struct DiscardParams<'a> {
+    /// The game before the next discard round, with our hole cards and those
+    /// of the opponents dealt.
+    pub game: &'a Game,
+
+    /// Weights of each opponent's hole cards once they have discarded. If not
+    /// provided, every pair is equally likely.
+    pub opponent: Option<&'a Range>,
+
+    /// Seed for reproducible results. Every option is evaluated over the same
+    /// samples. See [`with_sample_rng`](super::montecarlo::with_sample_rng).
+    pub seed: Option<u64>,
+}
+
+/// Outcome of keeping some hole cards and throwing away the rest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DiscardOption {
+    /// Hole cards kept.
+    pub hold: HandVec,
+
+    /// Hole cards thrown away.
+    pub discard: HandVec,
+
+    /// Equity against the opponents.
+    pub equity: Equity,
+}
+
+impl DiscardParams<'_> {
+    /// Every way of splitting our hole cards into cards kept and cards
+    /// thrown away in the next discard round.
+    pub fn options(&self) -> impl Iterator<Item = (HandVec, HandVec)> + '_ {
+        let hole = &self.game.player.hole.drawn;
+        let n = self.game.discards.first().copied().unwrap_or_default() as usize;
+        combinations(hole, n.min(hole.len())).map(|discard| {
+            (
+                hole.iter()
+                    .filter(|c| !discard.contains(c))
+                    .copied()
+                    .collect(),
+                discard.into_iter().collect(),
+            )
+        })
+    }
+
+    /// Evaluate throwing away `discard` over `n` samples.
+    pub fn evaluate(&self, discard: &[SCard], n: u64) -> Result<DiscardOption, DiscardError> {
+        let mut game = self.game.clone();
+        game.discard(discard)?;
+        let deck = game.sample_deck();
+        let equity = Showdown::from_game(&game, &deck)
+            .with_seed(self.seed)
+            .with_opponent_range(self.opponent)
+            .equity(n)[0];
+
+        Ok(DiscardOption {
+            hold: game.player.hole.drawn,
+            discard: discard.iter().copied().collect(),
+            equity,
+        })
+    }
+
+    /// Every option evaluated over `n` samples, best first. Fails if there
+    /// is no discard round left, or an opponent has too few hole cards.
+    pub fn advise(&self, n: u64) -> Result<Vec<DiscardOption>, DiscardError> {
+        let mut options = self
+            .options()
+            .map(|(_, discard)| self.evaluate(&discard, n))
+            .collect::<Result<Vec<_>, _>>()?;
+        options.sort_by(|a, b| b.equity.equity.total_cmp(&a.equity.equity));
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_repr::{crazy_pineapple, pineapple};
+
+    fn cards(cards: &str) -> HandVec {
+        cards
+            .split_whitespace()
+            .map(|c| c.parse::<poker::Card>().unwrap().into())
+            .collect()
+    }
+
+    fn dealt(rounds: &[crate::game_repr::Round], hole: &str, board: &str) -> Game {
+        let mut game = Game::from_rounds(1, rounds);
+        game.player.hole.add_cards(cards(hole));
+        game.community.add_cards(cards(board));
+        for o in game.opponents.iter_mut() {
+            o.hole.add_cards(3);
+        }
+        game
+    }
+
+    #[test]
+    fn keeps_the_pair() {
+        let game = dealt(&pineapple(), "As Ah 7c", "");
+        let params = DiscardParams {
+            game: &game,
+            opponent: None,
+            seed: Some(1),
+        };
+
+        let options = params.advise(4000).unwrap();
+
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0].discard, cards("7c"));
+        assert_eq!(options[0].hold, cards("As Ah"));
+        assert!(options[0].equity.equity > 0.8, "{:?}", options[0]);
+    }
+
+    #[test]
+    fn keeps_the_flopped_set_against_a_range() {
+        let game = dealt(&crazy_pineapple(), "9s 9d Ah", "9c Kh 2d");
+        let kings: Range = "KK, AK".parse().unwrap();
+        let params = DiscardParams {
+            game: &game,
+            opponent: Some(&kings),
+            seed: Some(1),
+        };
+
+        let options = params.advise(2000).unwrap();
+
+        // Keeping an ace only pairs against a range full of kings and aces.
+        assert_eq!(options[0].hold, cards("9s 9d"));
+        assert!(options[2].equity.equity < 0.2, "{:?}", options[2]);
+    }
+}
@@ -0,0 +1,589 @@
+//! Choosing which cards to exchange in draw poker.
+
+use compact_poker::SCard;
+use rand::{seq::SliceRandom, Rng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use super::{
+    accumulator::EquityAccumulator,
+    category::HandCategory,
+    combinations::combinations,
+    equity::Equity,
+    model::{Game, HandVec, PartialHand},
+    montecarlo::{score_superhand, with_sample_rng, SimParams},
+    stats::ScoreSummary,
+};
+
+/// How a hand chooses which cards to exchange.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub enum DrawPolicy {
+    /// Never exchange anything.
+    StandPat,
+
+    /// Keep made hands and cards sharing a rank, otherwise four cards to a
+    /// flush or straight, otherwise the highest cards.
+    #[default]
+    Heuristic,
+
+    /// Keep these cards in the next exchange, and play later ones with the
+    /// heuristic. See [`DrawPolicy::best_mean`].
+    Keep(HandVec),
+}
+
+impl DrawPolicy {
+    /// Keep the cards giving the best mean final score over one exchange of
+    /// up to `max` cards, with each option evaluated over `n` samples.
+    pub fn best_mean(hand: &[SCard], max: u8, sample_deck: &[SCard], n: u64) -> Self {
+        let params = DrawParams {
+            hand: hand.into(),
+            max_exchange: max,
+            sample_deck,
+            opponents: vec![],
+            seed: None,
+        };
+        Self::Keep(params.advise(n, 1).swap_remove(0).hold)
+    }
+
+    /// Cards of `hand` to keep when up to `max` may be exchanged.
+    pub fn hold(&self, hand: &[SCard], max: u8) -> HandVec {
+        let min_kept = hand.len().saturating_sub(max as usize);
+        let mut kept = match self {
+            DrawPolicy::StandPat => return hand.into(),
+            DrawPolicy::Heuristic => heuristic_hold(hand),
+            DrawPolicy::Keep(cards) => hand.iter().filter(|c| cards.contains(c)).copied().collect(),
+        };
+        let rest = hand
+            .iter()
+            .filter(|c| !kept.contains(c))
+            .copied()
+            .collect::<HandVec>();
+        kept.extend(strongest(&rest, min_kept.saturating_sub(kept.len())));
+        kept
+    }
+
+    /// The policy for the exchange after this one.
+    fn next(&self) -> Self {
+        match self {
+            DrawPolicy::Keep(_) => DrawPolicy::Heuristic,
+            policy => policy.clone(),
+        }
+    }
+}
+
+/// Most hands dealt when looking for one consistent with a [`PastDraw`].
+const MAX_DEALS: usize = 1000;
+
+/// An exchange a hand has already made, of which only the number of cards is
+/// known.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct PastDraw {
+    /// Number of cards exchanged.
+    pub n: u8,
+
+    /// Most cards that could have been exchanged.
+    pub max: u8,
+
+    /// How the hand is assumed to have chosen which cards to exchange.
+    #[serde(default)]
+    pub policy: DrawPolicy,
+}
+
+impl PastDraw {
+    /// Whether the policy would exchange exactly `n` cards of `hand`.
+    pub fn explains(&self, hand: &[SCard]) -> bool {
+        hand.len() - self.policy.hold(hand, self.max).len() == self.n as usize
+    }
+
+    /// Deal `dealt` again until, together with `known`, it is a hand that
+    /// would have made this draw. Cards are swapped with random ones from
+    /// `spare`, so the two stay disjoint. Returns whether such a hand was
+    /// found within [`MAX_DEALS`] deals, or before running out of spare
+    /// cards.
+    pub fn condition(
+        &self,
+        known: &[SCard],
+        dealt: &mut [SCard],
+        spare: &mut [SCard],
+        rng: &mut impl Rng,
+    ) -> bool {
+        let mut hand = known.iter().copied().collect::<HandVec>();
+        for _ in 0..MAX_DEALS {
+            hand.truncate(known.len());
+            hand.extend(dealt.iter().copied());
+            if self.explains(&hand) {
+                return true;
+            }
+            if spare.is_empty() {
+                return false;
+            }
+            for c in dealt.iter_mut() {
+                std::mem::swap(c, &mut spare[rng.gen_range(0..spare.len())]);
+            }
+        }
+        false
+    }
+
+    /// The hand after this draw, given the hand before it and at least `n`
+    /// replacements.
+    pub fn replay(&self, hand: &[SCard], replacements: &[SCard]) -> HandVec {
+        let n_kept = hand.len() - self.n as usize;
+        let kept = self.policy.hold(hand, self.max);
+        // Hands the policy would not have drawn this many to keep their
+        // strongest cards.
+        let mut cards = match kept.len() == n_kept {
+            true => kept,
+            false => strongest(hand, n_kept),
+        };
+        cards.extend(replacements[..self.n as usize].iter().copied());
+        cards
+    }
+}
+
+/// Exchange rounds still to come before the showdown.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Exchanges {
+    /// Most cards that may be exchanged in each round.
+    pub rounds: SmallVec<[u8; 3]>,
+
+    /// How the hand chooses which cards to exchange.
+    pub policy: DrawPolicy,
+}
+
+impl Exchanges {
+    pub fn new(rounds: impl IntoIterator<Item = u8>, policy: DrawPolicy) -> Self {
+        Self {
+            rounds: rounds.into_iter().collect(),
+            policy,
+        }
+    }
+
+    /// Most replacement cards the hand can receive.
+    pub fn max_replacements(&self) -> usize {
+        self.rounds.iter().map(|max| *max as usize).sum()
+    }
+
+    /// Play every round on `hand`, taking replacements in order from
+    /// `replacements`, which must hold at least
+    /// [`Exchanges::max_replacements`] cards.
+    pub fn play(&self, hand: &mut HandVec, replacements: &[SCard]) {
+        let mut policy = self.policy.clone();
+        let mut replacements = replacements.iter().copied();
+        for max in &self.rounds {
+            let mut kept = policy.hold(hand, *max);
+            kept.extend(replacements.by_ref().take(hand.len() - kept.len()));
+            *hand = kept;
+            policy = policy.next();
+        }
+    }
+}
+
+pub struct DrawParams<'a> {
+    /// Our hand before the exchange.
+    pub hand: HandVec,
+
+    /// Most cards that may be exchanged.
+    pub max_exchange: u8,
+
+    /// Deck replacements are drawn from. It should not contain our hand.
+    pub sample_deck: &'a [SCard],
+
+    /// Exchange each opponent made. Opponents are dealt a hand as big as
+    /// ours, consistent with the exchange.
+    pub opponents: Vec<PastDraw>,
+
+    /// Seed for reproducible results. See [`with_sample_rng`].
+    pub seed: Option<u64>,
+}
+
+/// Outcome of keeping some cards and exchanging the rest.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DrawOption {
+    /// Cards kept.
+    pub hold: HandVec,
+
+    /// Cards exchanged.
+    pub discard: HandVec,
+
+    /// Distribution of the final hand's score.
+    pub summary: ScoreSummary,
+
+    /// Equity against the opponents, if there are any.
+    pub equity: Option<Equity>,
+}
+
+impl DrawOption {
+    /// What options are ranked by: equity if there are opponents, otherwise
+    /// the mean final score.
+    pub fn value(&self) -> f64 {
+        match &self.equity {
+            Some(e) => e.equity,
+            None => self.summary.mean as f64,
+        }
+    }
+}
+
+impl<'a> DrawParams<'a> {
+    /// Our hand from a game, against its opponents and the number of cards
+    /// they exchanged in the last exchange, if there was one.
+    pub fn from_game(game: &Game, max_exchange: u8, sample_deck: &'a [SCard]) -> Self {
+        Self {
+            hand: game.player.hole.drawn.clone(),
+            max_exchange,
+            sample_deck,
+            opponents: game
+                .opponents
+                .iter()
+                .map(|o| PastDraw {
+                    n: o.exchanged as u8,
+                    max: game.last_exchange.unwrap_or_default(),
+                    policy: DrawPolicy::Heuristic,
+                })
+                .collect(),
+            seed: None,
+        }
+    }
+
+    /// Most cards dealt from the sample deck in one sample: our replacements,
+    /// and each opponent's hand before the exchange and its replacements.
+    /// Options can only be evaluated if the sample deck holds this many.
+    pub fn cards_needed(&self) -> usize {
+        let size = self.hand.len();
+        (self.max_exchange as usize).min(size)
+            + self
+                .opponents
+                .iter()
+                .map(|d| size + d.n as usize)
+                .sum::<usize>()
+    }
+
+    /// Every legal way of splitting the hand into cards kept and cards
+    /// exchanged, exchanging fewer cards first.
+    pub fn options(&self) -> impl Iterator<Item = (HandVec, HandVec)> + '_ {
+        let indices = (0..self.hand.len()).collect::<Vec<_>>();
+        let max = (self.max_exchange as usize).min(self.hand.len());
+        (0..=max).flat_map(move |k| {
+            combinations(&indices, k)
+                .map(|discarded| {
+                    let (discard, hold) = self
+                        .hand
+                        .iter()
+                        .enumerate()
+                        .partition::<Vec<_>, _>(|(i, _)| discarded.contains(i));
+                    (
+                        hold.into_iter().map(|(_, c)| *c).collect(),
+                        discard.into_iter().map(|(_, c)| *c).collect(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Evaluate keeping `hold` over `n` samples, or every possible draw if
+    /// there are fewer than `n`.
+    pub fn evaluate(&self, hold: &[SCard], n: u64, n_bins: usize) -> DrawOption {
+        let sim = SimParams {
+            player: PartialHand {
+                drawn: hold.iter().copied().collect(),
+                undrawn: (self.hand.len() - hold.len()) as u8,
+            },
+            sample_deck: self.sample_deck,
+            exchanges: Default::default(),
+            seed: self.seed,
+            deck: Default::default(),
+            wild: Default::default(),
+        };
+
+        let equity = (!self.opponents.is_empty()).then(|| {
+            let k = self.opponents.len() + 1;
+            (0..n)
+                .into_par_iter()
+                .map(|i| with_sample_rng(self.seed, i, |rng| self.run_sample(hold, rng)))
+                .fold(
+                    || EquityAccumulator::new(k),
+                    |mut acc, (scores, conditioned)| {
+                        acc.add(&scores);
+                        if !conditioned {
+                            acc.add_unconditioned();
+                        }
+                        acc
+                    },
+                )
+                .reduce(
+                    || EquityAccumulator::new(k),
+                    |mut a, b| {
+                        a.merge(&b);
+                        a
+                    },
+                )
+                .equities()[0]
+        });
+
+        DrawOption {
+            hold: hold.iter().copied().collect(),
+            discard: self
+                .hand
+                .iter()
+                .filter(|c| !hold.contains(c))
+                .copied()
+                .collect(),
+            summary: sim.summarize(n, n_bins),
+            equity,
+        }
+    }
+
+    /// Every option evaluated over `n` samples, best first.
+    pub fn advise(&self, n: u64, n_bins: usize) -> Vec<DrawOption> {
+        let mut options = self
+            .options()
+            .map(|(hold, _)| self.evaluate(&hold, n, n_bins))
+            .collect::<Vec<_>>();
+        options.sort_by(|a, b| b.value().total_cmp(&a.value()));
+        options
+    }
+
+    /// Final scores of us keeping `hold`, followed by each opponent, and
+    /// whether every opponent was dealt a hand consistent with its exchange.
+    fn run_sample(&self, hold: &[SCard], mut rng: impl Rng) -> (SmallVec<[u32; 9]>, bool) {
+        let size = self.hand.len();
+        let n_ours = size - hold.len();
+        let n_sampled = n_ours
+            + self
+                .opponents
+                .iter()
+                .map(|d| size + d.n as usize)
+                .sum::<usize>();
+        let mut pool = self
+            .sample_deck
+            .choose_multiple(&mut rng, n_sampled)
+            .copied()
+            .collect::<SmallVec<[SCard; 32]>>();
+        assert_eq!(pool.len(), n_sampled, "not enough cards left to deal");
+        let mut spare = spare_cards(self.sample_deck, &pool);
+
+        let mut cards = hold.iter().copied().collect::<HandVec>();
+        cards.extend(pool[..n_ours].iter().copied());
+        let mut scores = SmallVec::new();
+        scores.push(score_superhand(&cards).1);
+
+        let mut conditioned = true;
+        let mut next = n_ours;
+        for draw in &self.opponents {
+            let (dealt, rest) = pool[next..].split_at_mut(size);
+            conditioned &= draw.condition(&[], dealt, &mut spare, &mut rng);
+            scores.push(score_superhand(&draw.replay(dealt, rest)).1);
+            next += size + draw.n as usize;
+        }
+        (scores, conditioned)
+    }
+}
+
+/// Cards of `deck` which are not in `pool`.
+pub(super) fn spare_cards(deck: &[SCard], pool: &[SCard]) -> Vec<SCard> {
+    deck.iter().filter(|c| !pool.contains(c)).copied().collect()
+}
+
+/// The `n` strongest cards of `hand`: the biggest groups of the same rank
+/// first, then the highest cards.
+fn strongest(hand: &[SCard], n: usize) -> HandVec {
+    let count = |c: &SCard| hand.iter().filter(|o| o.rank() == c.rank()).count();
+    let mut cards = hand.iter().copied().collect::<HandVec>();
+    cards.sort_by_key(|c| std::cmp::Reverse((count(c), c.rank())));
+    cards.truncate(n);
+    cards
+}
+
+/// Cards of [`DrawPolicy::Heuristic`] worth keeping before topping up with
+/// the highest cards.
+fn heuristic_hold(hand: &[SCard]) -> HandVec {
+    if hand.len() >= 5
+        && HandCategory::from_score(score_superhand(hand).1) >= HandCategory::Straight
+    {
+        return hand.into();
+    }
+
+    let count = |c: &SCard| hand.iter().filter(|o| o.rank() == c.rank()).count();
+    let grouped = hand
+        .iter()
+        .filter(|c| count(c) > 1)
+        .copied()
+        .collect::<HandVec>();
+    if !grouped.is_empty() {
+        return grouped;
+    }
+    flush_draw(hand)
+        .or_else(|| straight_draw(hand))
+        .unwrap_or_default()
+}
+
+/// Four cards of the same suit, if there are exactly four.
+fn flush_draw(hand: &[SCard]) -> Option<HandVec> {
+    (0..4)
+        .map(|suit| {
+            hand.iter()
+                .filter(|c| c.suit() as usize == suit)
+                .copied()
+                .collect::<HandVec>()
+        })
+        .find(|cards| cards.len() == 4)
+}
+
+/// Four cards of distinct ranks within five consecutive ranks, counting aces
+/// as high or low, preferring the highest. Assumes no two cards share a rank.
+fn straight_draw(hand: &[SCard]) -> Option<HandVec> {
+    (4..=13).rev().find_map(|high| {
+        let cards = hand
+            .iter()
+            .filter(|c| {
+                let rank = c.rank() as usize + 1;
+                (high - 4..=high).contains(&rank) || (high == 4 && rank == 13)
+            })
+            .copied()
+            .collect::<HandVec>();
+        (cards.len() == 4).then_some(cards)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::Card;
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::prediction::category::HandCategory;
+
+    fn params(hand: &str, opponent_draws: Vec<u8>) -> (HandVec, Vec<SCard>, Vec<PastDraw>) {
+        let hand = hand
+            .split_whitespace()
+            .map(|c| c.parse::<Card>().unwrap().into())
+            .collect::<HandVec>();
+        let deck = SCard::deck()
+            .filter(|c| !hand.contains(c))
+            .collect::<Vec<_>>();
+        let opponents = opponent_draws
+            .into_iter()
+            .map(|n| PastDraw {
+                n,
+                max: 3,
+                policy: DrawPolicy::Heuristic,
+            })
+            .collect();
+        (hand, deck, opponents)
+    }
+
+    #[test]
+    fn enumerates_every_option() {
+        let (hand, deck, opponents) = params("As Ah Ad 7c 2s", vec![]);
+        let draw = DrawParams {
+            hand,
+            max_exchange: 3,
+            sample_deck: &deck,
+            opponents,
+            seed: Some(1),
+        };
+
+        let options = draw.options().collect::<Vec<_>>();
+
+        assert_eq!(options.len(), 1 + 5 + 10 + 10);
+        assert_eq!(options[0].0, draw.hand);
+        assert!(options
+            .iter()
+            .all(|(hold, discard)| hold.len() + discard.len() == 5));
+    }
+
+    #[test]
+    fn keeps_made_hands_and_draws_to_them() {
+        let (hand, deck, opponents) = params("As Ah Ad 7c 2s", vec![]);
+        let trips = DrawParams {
+            hand,
+            max_exchange: 3,
+            sample_deck: &deck,
+            opponents,
+            seed: Some(1),
+        };
+
+        let best = &trips.advise(20000, 10)[0];
+
+        assert_eq!(best.discard.len(), 2);
+        assert!(best.summary.exhaustive);
+        assert!(best.summary.categories[HandCategory::FourOfAKind.index()] > 0.04);
+
+        let (hand, deck, opponents) = params("Ks Qs Js Ts 2d", vec![1, 3]);
+        let straight_flush_draw = DrawParams {
+            hand,
+            max_exchange: 3,
+            sample_deck: &deck,
+            opponents,
+            seed: Some(1),
+        };
+
+        let options = straight_flush_draw.advise(2000, 10);
+
+        assert_eq!(options[0].discard.len(), 1);
+        assert_eq!(options[0].discard[0].rank(), poker::Rank::Two);
+        assert!(options[0].equity.is_some());
+        assert!(options.windows(2).all(|w| w[0].value() >= w[1].value()));
+    }
+
+    #[test]
+    fn policies_choose_holds() {
+        let kept = |policy: &DrawPolicy, hand: &str, max| {
+            let (hand, ..) = params(hand, vec![]);
+            let mut held = policy
+                .hold(&hand, max)
+                .iter()
+                .map(|c| poker::Card::from(*c).rank_suit_string())
+                .collect::<Vec<_>>();
+            held.sort();
+            held.join(" ")
+        };
+        let heuristic = DrawPolicy::Heuristic;
+
+        assert_eq!(kept(&heuristic, "9s Ts Jh Qd Kc", 3), "9s Jh Kc Qd Ts");
+        assert_eq!(kept(&heuristic, "7s 7h Kd 4c 2s", 3), "7h 7s");
+        assert_eq!(kept(&heuristic, "7s 7h Kd 4c 2s", 1), "4c 7h 7s Kd");
+        assert_eq!(kept(&heuristic, "2s 8s Js Ks 3d", 3), "2s 8s Js Ks");
+        assert_eq!(kept(&heuristic, "As 2d 3h 4c 9s", 3), "2d 3h 4c As");
+        assert_eq!(kept(&heuristic, "As Qd 8h 5c 3s", 3), "As Qd");
+        assert_eq!(
+            kept(&DrawPolicy::StandPat, "As Qd 8h 5c 3s", 3),
+            "3s 5c 8h As Qd"
+        );
+
+        let keep = DrawPolicy::Keep(params("As", vec![]).0);
+        assert_eq!(kept(&keep, "As Qd 8h 5c 3s", 3), "As Qd");
+
+        let (mut hand, deck, _) = params("As Ah Kd 7c 2s", vec![]);
+        Exchanges::new([3, 1], DrawPolicy::Heuristic).play(&mut hand, &deck[..4]);
+        assert_eq!(hand.len(), 5);
+        // The second draw throws away the lowest of the first replacements.
+        assert!(hand.contains(&deck[3]) && !hand.contains(&deck[0]));
+    }
+
+    #[test]
+    fn past_draws_condition_hands() {
+        let (_, mut deck, _) = params("", vec![]);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let one_card = PastDraw {
+            n: 1,
+            max: 3,
+            policy: DrawPolicy::Heuristic,
+        };
+
+        let (dealt, spare) = deck.split_at_mut(5);
+        assert!(one_card.condition(&[], dealt, spare, &mut rng));
+        assert!(one_card.explains(dealt));
+        assert!(dealt.iter().all(|c| !spare.contains(c)));
+
+        let hand = dealt.iter().copied().collect::<HandVec>();
+        let after = one_card.replay(&hand, &spare[..1]);
+        assert_eq!(after.len(), 5);
+        assert_eq!(after.iter().filter(|c| !hand.contains(c)).count(), 1);
+        assert!(after.contains(&spare[0]));
+
+        // A straight flush stands pat, and there is nothing to deal instead.
+        let mut straight_flush = SCard::deck().take(5).collect::<Vec<_>>();
+        assert!(!one_card.condition(&[], &mut straight_flush, &mut [], &mut rng));
+    }
+}
@@ -0,0 +1,493 @@
+use compact_poker::{SCard, Wild};
+use rand::{seq::SliceRandom, Rng};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+
+use super::{
+    accumulator::EquityAccumulator,
+    deck::Deck,
+    draw::{spare_cards, DrawPolicy, Exchanges, PastDraw},
+    low::Pot,
+    model::{Game, HandVec, OtherPlayer},
+    montecarlo::with_sample_rng,
+    range::Range,
+};
+
+/// One hand taking part in a showdown.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Contender {
+    /// Cards known to be in this hand.
+    pub known: HandVec,
+
+    /// Indices into the pool of sampled cards of the cards this hand receives.
+    ///
+    /// Several contenders may share an index, e.g. for community cards.
+    pub sampled: SmallVec<[usize; 7]>,
+
+    /// An exchange this hand has already made. Its sampled cards, which must
+    /// not be shared with other contenders, are dealt as they were before the
+    /// exchange, conditioned on the hand having made it.
+    pub drew: Option<PastDraw>,
+
+    /// Exchanges this hand makes once every card has been dealt. Replacements
+    /// are sampled separately for each contender.
+    pub exchanges: Exchanges,
+}
+
+/// Several hands competing for the same pot, whose unknown cards are sampled
+/// jointly from one deck.
+pub struct Showdown<'a> {
+    pub contenders: Vec<Contender>,
+
+    /// Number of cards to deal for each trial, not counting replacements.
+    pub n_sampled: usize,
+
+    /// Deck to sample from.
+    pub sample_deck: &'a [SCard],
+
+    /// Seed for reproducible results. See [`with_sample_rng`].
+    pub seed: Option<u64>,
+
+    /// Deck the cards are dealt from, which decides how hands are ranked.
+    pub deck: Deck,
+
+    /// Cards which are wild, besides jokers.
+    pub wild: Wild,
+
+    /// How the pot is won.
+    pub pot: Pot,
+
+    /// Weights of each opponent's first two sampled cards, their hole cards
+    /// in hold'em. If not provided, every pair is equally likely.
+    pub opponent_range: Option<&'a Range>,
+}
+
+pub struct ShowdownResult {
+    /// Score of each contender, in the same order as [`Showdown::contenders`].
+    pub scores: SmallVec<[u32; 9]>,
+
+    /// Low score of each contender which qualifies for the low half of a
+    /// split pot. Empty unless the pot is [`Pot::HighLow`].
+    pub lows: SmallVec<[Option<u32>; 9]>,
+
+    /// Whether every hand dealt again to match an exchange or a range was
+    /// found one.
+    pub conditioned: bool,
+}
+
+impl ShowdownResult {
+    /// Share of the pot won by each contender. Tied winners split the pot,
+    /// or its half if it is split between high and low.
+    pub fn shares(&self) -> Vec<f64> {
+        let mut acc = EquityAccumulator::new(self.scores.len());
+        match self.lows.is_empty() {
+            true => acc.add(&self.scores),
+            false => acc.add_split(&self.scores, &self.lows),
+        }
+        acc.equities().iter().map(|e| e.equity).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Equity {
+    /// Probability of winning the whole pot.
+    pub win: f64,
+
+    /// Probability of splitting the pot.
+    pub tie: f64,
+
+    /// Expected share of the pot.
+    pub equity: f64,
+
+    /// Standard error of [`Equity::equity`].
+    #[serde(default)]
+    pub stderr: f64,
+
+    /// Share of samples in which some hand could not be dealt consistently
+    /// with what is known of it, and was played as dealt at random instead.
+    #[serde(default)]
+    pub unconditioned: f64,
+}
+
+impl Equity {
+    /// Half-width of the confidence interval of [`Equity::equity`] at `z`
+    /// standard errors, e.g. [`Z_95`](super::stats::Z_95).
+    pub fn margin(&self, z: f64) -> f64 {
+        z * self.stderr
+    }
+}
+
+impl<'a> Showdown<'a> {
+    /// The player is the first contender, followed by each opponent.
+    ///
+    /// Hole cards still to be discarded are left out of the hands dealt, as
+    /// if the discards had been made. Our own discard, if our hole cards are
+    /// already known, must be chosen first. See
+    /// [`DiscardParams`](super::discard::DiscardParams).
+    pub fn from_game(game: &Game, sample_deck: &'a [SCard]) -> Self {
+        let mut n_sampled = 0;
+        let mut take = |n: usize| {
+            let range = n_sampled..n_sampled + n;
+            n_sampled += n;
+            range
+        };
+
+        let community = take(game.community.undrawn as usize);
+        let pending = game.pending_discards();
+        let exchanges = Exchanges::new(game.exchanges.iter().copied(), DrawPolicy::Heuristic);
+        let community_known = game.community.drawn.iter().copied();
+
+        let drew = |o: &OtherPlayer| {
+            game.last_exchange.map(|max| PastDraw {
+                n: o.exchanged as u8,
+                max,
+                policy: DrawPolicy::Heuristic,
+            })
+        };
+
+        let player = Contender {
+            known: game
+                .player
+                .hole
+                .drawn
+                .iter()
+                .chain(game.player.stud.drawn.iter())
+                .copied()
+                .chain(community_known.clone())
+                .collect(),
+            sampled: take(
+                (game.player.hole.undrawn.saturating_sub(pending) + game.player.stud.undrawn)
+                    as usize,
+            )
+            .chain(community.clone())
+            .collect(),
+            drew: None,
+            exchanges: exchanges.clone(),
+        };
+
+        let opponents = game
+            .opponents
+            .iter()
+            .map(|o| Contender {
+                known: o
+                    .stud
+                    .drawn
+                    .iter()
+                    .copied()
+                    .chain(community_known.clone())
+                    .collect(),
+                sampled: take(
+                    (o.hole.total_cards().saturating_sub(pending) + o.stud.undrawn) as usize,
+                )
+                .chain(community.clone())
+                .collect(),
+                drew: drew(o),
+                exchanges: exchanges.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Showdown {
+            contenders: std::iter::once(player).chain(opponents).collect(),
+            n_sampled,
+            sample_deck,
+            seed: None,
+            deck: game.deck,
+            wild: Wild::NONE,
+            pot: Pot::High,
+            opponent_range: None,
+        }
+    }
+
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+
+    pub fn with_pot(self, pot: Pot) -> Self {
+        Self { pot, ..self }
+    }
+
+    pub fn with_opponent_range(self, opponent_range: Option<&'a Range>) -> Self {
+        Self {
+            opponent_range,
+            ..self
+        }
+    }
+
+    /// Assume every contender that has already exchanged chose its cards with
+    /// `policy`.
+    pub fn with_draw_model(mut self, policy: DrawPolicy) -> Self {
+        for drew in self.contenders.iter_mut().filter_map(|c| c.drew.as_mut()) {
+            drew.policy = policy.clone();
+        }
+        self
+    }
+
+    /// Number of replacement cards the contenders receive or can receive in
+    /// exchanges.
+    pub fn n_replacements(&self) -> usize {
+        self.contenders
+            .iter()
+            .map(|c| c.drew.as_ref().map_or(0, |d| d.n as usize) + c.exchanges.max_replacements())
+            .sum()
+    }
+
+    pub fn run_sample(&self, mut rng: impl Rng) -> ShowdownResult {
+        let mut pool = self
+            .sample_deck
+            .choose_multiple(&mut rng, self.n_sampled + self.n_replacements())
+            .copied()
+            .collect::<SmallVec<[SCard; 32]>>();
+
+        // Deal again the hands of contenders which have exchanged until they
+        // are consistent with the exchange, and the hole cards of opponents
+        // from their range.
+        let mut conditioned = true;
+        if self.opponent_range.is_some() || self.contenders.iter().any(|c| c.drew.is_some()) {
+            let mut spare = spare_cards(self.sample_deck, &pool);
+            let mut locked = SmallVec::<[usize; 32]>::new();
+            for c in &self.contenders {
+                let Some(drew) = &c.drew else {
+                    continue;
+                };
+                let mut dealt = c.sampled.iter().map(|i| pool[*i]).collect::<HandVec>();
+                conditioned &= drew.condition(&c.known, &mut dealt, &mut spare, &mut rng);
+                for (i, card) in c.sampled.iter().zip(dealt) {
+                    pool[*i] = card;
+                }
+                locked.extend(c.sampled.iter().copied());
+            }
+            if let Some(range) = self.opponent_range {
+                for c in &self.contenders[1..] {
+                    let [a, b, ..] = c.sampled[..] else {
+                        continue;
+                    };
+                    locked.extend([a, b]);
+                    // Hole cards in the range may be any not yet held, even
+                    // ones dealt to the board, which gets the cards swapped
+                    // out instead.
+                    let free = (0..pool.len())
+                        .filter(|i| !locked.contains(i))
+                        .collect::<SmallVec<[usize; 32]>>();
+                    let mut available = free.iter().map(|i| pool[*i]).collect::<Vec<_>>();
+                    available.extend(spare.iter().copied());
+
+                    let mut dealt = [pool[a], pool[b]];
+                    conditioned &= range.condition(&mut dealt, &mut available, &mut rng);
+                    [pool[a], pool[b]] = dealt;
+                    for (i, card) in free.iter().zip(&available) {
+                        pool[*i] = *card;
+                    }
+                    spare.copy_from_slice(&available[free.len()..]);
+                }
+            }
+        }
+
+        let mut next = self.n_sampled;
+        let (scores, lows): (SmallVec<_>, SmallVec<_>) = self
+            .contenders
+            .iter()
+            .map(|c| {
+                let mut cards = c.known.clone();
+                cards.extend(c.sampled.iter().map(|i| pool[*i]));
+                if let Some(drew) = &c.drew {
+                    cards = drew.replay(&cards, &pool[next..]);
+                    next += drew.n as usize;
+                }
+                let n = c.exchanges.max_replacements();
+                c.exchanges.play(&mut cards, &pool[next..next + n]);
+                next += n;
+                self.pot.score(self.deck, self.wild, &cards)
+            })
+            .unzip();
+
+        ShowdownResult {
+            scores,
+            lows: match self.pot {
+                Pot::HighLow => lows,
+                _ => SmallVec::new(),
+            },
+            conditioned,
+        }
+    }
+
+    /// Equity of each contender over `n` random samples.
+    pub fn equity(&self, n: u64) -> Vec<Equity> {
+        let k = self.contenders.len();
+        (0..n)
+            .into_par_iter()
+            .map(|i| with_sample_rng(self.seed, i, |rng| self.run_sample(rng)))
+            .fold(
+                || EquityAccumulator::new(k),
+                |mut acc, result| {
+                    match result.lows.is_empty() {
+                        true => acc.add(&result.scores),
+                        false => acc.add_split(&result.scores, &result.lows),
+                    }
+                    if !result.conditioned {
+                        acc.add_unconditioned();
+                    }
+                    acc
+                },
+            )
+            .reduce(
+                || EquityAccumulator::new(k),
+                |mut a, b| {
+                    a.merge(&b);
+                    a
+                },
+            )
+            .equities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use compact_poker::SCard;
+    use poker::{Rank, Suit};
+
+    use super::*;
+    use crate::game_repr::{five_card_draw, get_deals, holdem, pineapple};
+
+    #[test]
+    fn from_game_shares_community_cards() {
+        let mut game = Game::from_deals(2, get_deals(holdem()));
+        game.player
+            .hole
+            .add_cards([SCard::new(Rank::Ace, Suit::Spades)]);
+        let deck = game.sample_deck();
+
+        let showdown = Showdown::from_game(&game, &deck);
+
+        assert_eq!(deck.len(), 51);
+        assert_eq!(showdown.n_sampled, 5 + 1 + 2 + 2);
+        assert_eq!(showdown.contenders.len(), 3);
+        assert_eq!(
+            showdown.contenders[0].sampled.as_slice(),
+            &[5, 0, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            showdown.contenders[2].sampled.as_slice(),
+            &[8, 9, 0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn aces_beat_a_random_hand() {
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.player.hole.add_cards([
+            SCard::new(Rank::Ace, Suit::Spades),
+            SCard::new(Rank::Ace, Suit::Hearts),
+        ]);
+        let deck = game.sample_deck();
+
+        let equity = Showdown::from_game(&game, &deck).equity(20000);
+
+        // Pocket aces have roughly 85% equity against a random hand.
+        assert!((equity[0].equity - 0.85).abs() < 0.02, "{equity:?}");
+        assert!((equity[0].equity + equity[1].equity - 1.0).abs() < 1e-9);
+        // The standard error of a proportion near 0.85 over 20000 samples.
+        assert!((equity[0].stderr - 0.0025).abs() < 0.0005, "{equity:?}");
+    }
+
+    #[test]
+    fn seeded_equity_is_reproducible_across_thread_counts() {
+        let mut game = Game::from_deals(2, get_deals(holdem()));
+        game.player.hole.add_cards([
+            SCard::new(Rank::Queen, Suit::Spades),
+            SCard::new(Rank::Jack, Suit::Spades),
+        ]);
+        let deck = game.sample_deck();
+        let equity = |seed, threads| {
+            let showdown = Showdown::from_game(&game, &deck).with_seed(Some(seed));
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| showdown.equity(5000))
+        };
+
+        let single = equity(7, 1);
+        assert_eq!(single, equity(7, 4));
+        assert_ne!(single, equity(8, 4));
+    }
+
+    #[test]
+    fn opponents_that_stand_pat_are_strong() {
+        let mut game = Game::from_rounds(1, &five_card_draw());
+        game.player.hole.add_cards([
+            SCard::new(Rank::King, Suit::Spades),
+            SCard::new(Rank::King, Suit::Hearts),
+            SCard::new(Rank::Seven, Suit::Clubs),
+            SCard::new(Rank::Seven, Suit::Diamonds),
+            SCard::new(Rank::Two, Suit::Spades),
+        ]);
+        game.exchanges.clear();
+        game.last_exchange = Some(3);
+        let deck = game.sample_deck();
+        let mut equity = |exchanged| {
+            game.opponents[0].exchanged = exchanged;
+            let showdown = Showdown::from_game(&game, &deck).with_seed(Some(1));
+            assert_eq!(showdown.n_replacements(), exchanged);
+            showdown.equity(4000)[0].equity
+        };
+
+        // Kings up beat most pairs drawing three, but not the straights,
+        // flushes and full houses that stand pat.
+        let against_pair = equity(3);
+        let against_pat = equity(0);
+        assert!(against_pair > 0.7, "{against_pair}");
+        assert!(against_pat < 0.4, "{against_pat}");
+    }
+
+    #[test]
+    fn opponents_in_a_strong_range_beat_us() {
+        let mut game = Game::from_deals(1, get_deals(holdem()));
+        game.player.hole.add_cards([
+            SCard::new(Rank::King, Suit::Spades),
+            SCard::new(Rank::Queen, Suit::Spades),
+        ]);
+        let deck = game.sample_deck();
+        let aces: Range = "AA".parse().unwrap();
+
+        let equity = Showdown::from_game(&game, &deck)
+            .with_seed(Some(1))
+            .with_opponent_range(Some(&aces))
+            .equity(4000);
+
+        // King-queen suited has about 20% equity against aces.
+        assert!((equity[0].equity - 0.2).abs() < 0.04, "{equity:?}");
+    }
+
+    #[test]
+    fn ranges_deal_their_last_hand() {
+        let mut game = Game::from_deals(2, get_deals(holdem()));
+        game.player.hole.add_cards([
+            SCard::new(Rank::King, Suit::Spades),
+            SCard::new(Rank::King, Suit::Hearts),
+        ]);
+        let deck = game.sample_deck();
+        let kings: Range = "KK".parse().unwrap();
+
+        let equity = Showdown::from_game(&game, &deck)
+            .with_seed(Some(1))
+            .with_opponent_range(Some(&kings))
+            .equity(2000);
+
+        // The first opponent always has the other kings, which the second
+        // cannot have too.
+        assert!(equity[0].tie > 0.7, "{equity:?}");
+        assert_eq!(equity[0].unconditioned, 1.0);
+    }
+
+    #[test]
+    fn pending_discards_are_not_dealt() {
+        let game = Game::from_rounds(2, &pineapple());
+        let deck = game.sample_deck();
+
+        let showdown = Showdown::from_game(&game, &deck);
+
+        assert_eq!(showdown.n_sampled, 5 + 3 * 2);
+        assert!(showdown.contenders.iter().all(|c| c.sampled.len() == 7));
+    }
+}
@@ -0,0 +1,177 @@
+//! Scoring hands for low, as in razz and the low half of stud eight-or-better.
+//!
+//! Hands are ranked ace-to-five: aces play low, and straights and flushes do
+//! not count against a hand. A-2-3-4-5 is the best low. Like high scores, a
+//! higher low score is a better hand, so the same showdown logic applies.
+
+use compact_poker::{SCard, Wild};
+use serde::{Deserialize, Serialize};
+
+use super::{combinations::combinations, deck::Deck};
+
+/// How the pot of a showdown is won.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Pot {
+    /// The best hand wins.
+    #[default]
+    High,
+
+    /// The lowest hand wins, as in razz.
+    Low,
+
+    /// The pot is split between the best hand and the lowest hand with no
+    /// card above an eight, as in stud eight-or-better. The best hand wins
+    /// all of it if no hand qualifies for low.
+    HighLow,
+}
+
+impl Pot {
+    /// Score of `hand` for the pot, and for the low half of a split pot if
+    /// it qualifies. High hands are ranked as in `deck`.
+    pub fn score(&self, deck: Deck, wild: Wild, hand: &[SCard]) -> (u32, Option<u32>) {
+        match self {
+            Pot::High => (deck.score_superhand_wild(hand, wild).1, None),
+            Pot::Low => (low_score(hand, wild), None),
+            Pot::HighLow => (
+                deck.score_superhand_wild(hand, wild).1,
+                eight_or_better_score(hand, wild),
+            ),
+        }
+    }
+}
+
+/// Powers of 13, for keys written as digits of rank values.
+const RANK_POWERS: [u32; 6] = [1, 13, 169, 2197, 28561, 371293];
+
+/// Number of distinct keys of a 5-card low: one block for each pattern of
+/// pairs, from no pair to four of a kind.
+const N_LOW_KEYS: u32 = 6 * RANK_POWERS[5];
+
+/// Value of a rank for low, from 0 for the ace to 12 for the king.
+fn low_value(c: SCard) -> usize {
+    (c.rank() as usize + 1) % 13
+}
+
+/// Key of a 5-card hand for low, where lower keys are better hands: the
+/// pattern of pairs, then the values of each group of equal rank, largest
+/// group first and highest value first. Wild cards take the lowest rank not
+/// already in the hand.
+fn low_key(hand: &[SCard], wild: Wild) -> u32 {
+    let mut counts = [0u8; 13];
+    for c in hand.iter().filter(|c| !wild.is_wild(**c)) {
+        counts[low_value(*c)] += 1;
+    }
+    for _ in hand.iter().filter(|c| wild.is_wild(**c)) {
+        let lowest = counts.iter().position(|n| *n == 0).unwrap();
+        counts[lowest] += 1;
+    }
+
+    let mut groups = (0..13)
+        .rev()
+        .filter(|v| counts[*v] > 0)
+        .map(|v| (counts[v], v as u32))
+        .collect::<Vec<_>>();
+    groups.sort_by_key(|(n, _)| std::cmp::Reverse(*n));
+    let pattern = match groups.iter().map(|(n, _)| *n).collect::<Vec<_>>()[..] {
+        [1, ..] => 0,
+        [2, 1, ..] => 1,
+        [2, 2, ..] => 2,
+        [3, 1, ..] => 3,
+        [3, 2] => 4,
+        _ => 5,
+    };
+    let values = groups
+        .iter()
+        .zip(RANK_POWERS[..5].iter().rev())
+        .map(|((_, v), p)| v * p)
+        .sum::<u32>();
+    pattern * RANK_POWERS[5] + values
+}
+
+/// Key of the best low within `hand`.
+fn best_low_key(hand: &[SCard], wild: Wild) -> u32 {
+    combinations(hand, 5)
+        .map(|h| low_key(&h, wild))
+        .min()
+        .unwrap()
+}
+
+/// Score of the best ace-to-five low within `hand`, where higher scores are
+/// better lows. The cards `wild` are wild, as well as any jokers.
+pub fn low_score(hand: &[SCard], wild: Wild) -> u32 {
+    N_LOW_KEYS - best_low_key(hand, wild)
+}
+
+/// Score of the best low within `hand` with five different ranks of eight or
+/// lower, if there is one. Scores compare with [`low_score`].
+pub fn eight_or_better_score(hand: &[SCard], wild: Wild) -> Option<u32> {
+    // Any qualifying low beats every low that does not qualify, so only the
+    // best low needs checking.
+    let key = best_low_key(hand, wild);
+    let highest = key / RANK_POWERS[4];
+    (highest <= 7).then_some(N_LOW_KEYS - key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(cards: &str) -> Vec<SCard> {
+        cards
+            .split_whitespace()
+            .map(|c| match c {
+                "Jk" => SCard::JOKERS[0],
+                c => c.parse::<poker::Card>().unwrap().into(),
+            })
+            .collect()
+    }
+
+    fn low(cards: &str) -> u32 {
+        low_score(&hand(cards), Wild::NONE)
+    }
+
+    #[test]
+    fn ranks_lows() {
+        // The wheel is the best low, straights and flushes do not count.
+        assert!(low("5h 4h 3h 2h Ah") > low("6c 4d 3h 2s Ac"));
+        assert!(low("6c 4d 3h 2s Ac") > low("6c 5d 3h 2s Ac"));
+        assert!(low("Kc Qd Jh 9s 8c") > low("Ac Ad 2h 3s 4c"));
+        assert!(low("2c 2d 3h 4s 5c") > low("Ac Ad 2h 2s 3c"));
+        assert!(low("Ac Ad 2h 2s 3c") > low("Ac Ad Ah 2s 3c"));
+        assert_eq!(low("5h 4h 3h 2h Ah"), low("5c 4d 3s 2d Ac"));
+
+        // The best five of seven cards play, and a joker fills the gap.
+        assert_eq!(low("Kc Kd 5h 4h 3h 2h Ah"), low("5h 4h 3h 2h Ah"));
+        assert_eq!(low("Kc 5h 4h 3h Jk"), low("Kc 5h 4h 3h Ah"));
+    }
+
+    #[test]
+    fn eight_or_better_qualifies() {
+        let score = |cards: &str| eight_or_better_score(&hand(cards), Wild::NONE);
+
+        assert_eq!(score("8c 7d 4h 2s Ac Kc Kd"), Some(low("8c 7d 4h 2s Ac")));
+        assert_eq!(score("9c 7d 4h 2s Ac Kc Kd"), None);
+        assert_eq!(score("8c 8d 4h 2s Ac Kc Kd"), None);
+        assert_eq!(
+            Pot::HighLow.score(Deck::Standard, Wild::NONE, &hand("5h 4h 3h 2h Ah")),
+            (
+                Deck::Standard.score_superhand(&hand("5h 4h 3h 2h Ah")).1,
+                Some(low("5h 4h 3h 2h Ah"))
+            )
+        );
+    }
+}
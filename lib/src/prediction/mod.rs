@@ -1,2 +1,18 @@
+pub mod accumulator;
+pub mod board;
+pub mod category;
+pub mod combinations;
+pub mod deck;
+pub mod discard;
+pub mod draw;
+pub mod equity;
+pub mod low;
 pub mod model;
 pub mod montecarlo;
+pub mod ofc;
+pub mod range;
+pub mod sampling;
+pub mod stats;
+pub mod strength;
+pub mod stud;
+pub mod wild;
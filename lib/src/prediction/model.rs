@@ -1,12 +1,29 @@
 use compact_poker::SCard;
+use serde::{Deserialize, Serialize};
 use smallvec::{smallvec, SmallVec};
 
-use crate::game_repr::Deal;
+use super::deck::Deck;
+use crate::game_repr::{get_deals, Deal, Round};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum DiscardError {
+    #[error("There is no discard round left")]
+    NoDiscardRound,
+
+    #[error("Expected {expected} cards to discard, got {got}")]
+    WrongCardCount { expected: u8, got: usize },
+
+    #[error("{0} is not in our hand")]
+    NotInHand(String),
+
+    #[error("Opponent {0} has fewer than {1} hole cards to discard")]
+    OpponentTooFewCards(usize, u8),
+}
 
 /// We use 7 to be able to hold a 7-card stud hand.
 pub type HandVec<C = SCard> = SmallVec<[C; 7]>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct Player<Hole, Exchanged> {
     /// Cards unknown to other players.
     pub hole: Hole,
@@ -21,7 +38,7 @@ pub struct Player<Hole, Exchanged> {
 pub type ThisPlayer = Player<PartialHand<HandVec>, HandVec>;
 pub type OtherPlayer = Player<PartialHand<u8>, usize>;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct PartialHand<Drawn = HandVec> {
     /// Cards drawn so far.
     pub drawn: Drawn,
@@ -65,14 +82,68 @@ impl PartialHand<HandVec> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct Game {
     pub player: ThisPlayer,
     pub opponents: SmallVec<[OtherPlayer; 8]>,
     pub community: PartialHand,
+
+    /// Most cards that may be exchanged in each exchange round still to
+    /// come.
+    #[serde(default)]
+    pub exchanges: SmallVec<[u8; 3]>,
+
+    /// Most cards that could be exchanged in the last exchange round played,
+    /// if there was one. Opponents' [`Player::exchanged`] counts are for
+    /// that round.
+    #[serde(default)]
+    pub last_exchange: Option<u8>,
+
+    /// Hole cards each player throws away in each discard round still to
+    /// come.
+    #[serde(default)]
+    pub discards: SmallVec<[u8; 2]>,
+
+    /// Cards seen but out of play, such as the upcards of players who have
+    /// folded. They are never dealt.
+    #[serde(default)]
+    pub dead: Vec<SCard>,
+
+    /// Number of unknown cards out of play, such as the face-down cards of
+    /// players who have folded. Which cards they are is unknown, so they stay
+    /// in the sample deck, but they can no longer be dealt.
+    #[serde(default)]
+    pub mucked: u8,
+
+    /// Deck the game is dealt from.
+    #[serde(default)]
+    pub deck: Deck,
 }
 
 impl Game {
+    /// A game about to be played with the given schedule.
+    pub fn from_rounds(n_opponents: usize, rounds: &[Round]) -> Self {
+        let exchanges = rounds
+            .iter()
+            .filter_map(|r| match r {
+                Round::Exchange { max, .. } => Some(*max),
+                _ => None,
+            })
+            .collect();
+        let discards = rounds
+            .iter()
+            .filter_map(|r| match r {
+                Round::Discard { n, .. } => Some(*n),
+                _ => None,
+            })
+            .collect();
+        Game {
+            exchanges,
+            discards,
+            ..Self::from_deals(n_opponents, get_deals(rounds.to_vec()))
+        }
+    }
+
     pub fn from_deals(n_opponents: usize, deals: impl IntoIterator<Item = Deal>) -> Self {
         let net_deal: Deal = deals.into_iter().sum();
 
@@ -90,7 +161,56 @@ impl Game {
                 }; n_opponents
             ],
             community: PartialHand::undrawn(net_deal.community),
+            exchanges: smallvec![],
+            last_exchange: None,
+            discards: smallvec![],
+            dead: vec![],
+            mucked: 0,
+            deck: Deck::Standard,
+        }
+    }
+
+    /// Hole cards each player still has to throw away.
+    pub fn pending_discards(&self) -> u8 {
+        self.discards.iter().sum()
+    }
+
+    /// Throw away `cards` from our hole cards in the next discard round.
+    /// They become dead cards, and each opponent mucks as many unseen cards.
+    /// The game is left as it is if the discard cannot be made.
+    pub fn discard(&mut self, cards: &[SCard]) -> Result<(), DiscardError> {
+        let expected = *self.discards.first().ok_or(DiscardError::NoDiscardRound)?;
+        if cards.len() != expected as usize {
+            return Err(DiscardError::WrongCardCount {
+                expected,
+                got: cards.len(),
+            });
+        }
+        for (i, c) in cards.iter().enumerate() {
+            if !self.player.hole.drawn.contains(c) || cards[..i].contains(c) {
+                return Err(DiscardError::NotInHand(c.to_string()));
+            }
+        }
+        if let Some(i) = self.opponents.iter().position(|o| o.hole.drawn < expected) {
+            return Err(DiscardError::OpponentTooFewCards(i + 1, expected));
         }
+
+        self.player.hole.drawn.retain(|c| !cards.contains(c));
+        self.dead.extend_from_slice(cards);
+        for o in self.opponents.iter_mut() {
+            o.hole.drawn -= expected;
+            self.mucked += expected;
+        }
+        self.discards.remove(0);
+        Ok(())
+    }
+
+    /// Remove an opponent who has folded. Their upcards become dead cards,
+    /// and their face-down cards are mucked.
+    pub fn fold(&mut self, opponent: usize) {
+        let folded = self.opponents.remove(opponent);
+        self.dead.extend(folded.stud.drawn);
+        self.mucked += folded.hole.drawn;
     }
 
     #[inline]
@@ -104,6 +224,20 @@ impl Game {
 
         player_hole.chain(player_stud).chain(opponent_stud)
     }
+
+    /// Cards that may still be dealt: the game's deck without known existing
+    /// cards, community cards, cards we exchanged away and dead cards.
+    pub fn sample_deck(&self) -> Vec<SCard> {
+        let mut deck = self.deck.cards();
+        let seen = self
+            .known_existing_cards()
+            .chain(self.community.drawn.iter().copied())
+            .chain(self.player.exchanged.iter().copied())
+            .chain(self.dead.iter().copied())
+            .collect::<Vec<_>>();
+        deck.retain(|c| !seen.contains(c));
+        deck
+    }
 }
 
 #[cfg(test)]
@@ -116,7 +250,7 @@ mod tests {
         let game = Game::from_deals(
             2,
             holdem().into_iter().filter_map(|r| match r {
-                Round::Deal(d) => Some(d),
+                Round::Deal { counts, .. } => Some(counts),
                 _ => None,
             }),
         );
@@ -134,8 +268,81 @@ mod tests {
                     exchanged: 0,
                     stud: PartialHand::undrawn(0)
                 }; 2],
-                community: PartialHand::undrawn(5)
+                community: PartialHand::undrawn(5),
+                exchanges: smallvec![],
+                last_exchange: None,
+                discards: smallvec![],
+                dead: vec![],
+                mucked: 0,
+                deck: Deck::Standard,
             }
         )
     }
+
+    #[test]
+    fn from_rounds_records_exchanges() {
+        let game = Game::from_rounds(1, &five_card_draw());
+
+        assert_eq!(game.player.hole, PartialHand::undrawn(5));
+        assert_eq!(game.exchanges.as_slice(), &[3]);
+    }
+
+    #[test]
+    fn folded_upcards_are_dead() {
+        let mut game = Game::from_rounds(2, &five_card_stud());
+        let king = SCard::new(poker::Rank::King, poker::Suit::Hearts);
+        let queen = SCard::new(poker::Rank::Queen, poker::Suit::Hearts);
+        game.opponents[0].hole.add_cards(1);
+        game.opponents[0].stud.add_cards([king]);
+        game.opponents[1].stud.add_cards([queen]);
+
+        game.fold(0);
+
+        assert_eq!(game.opponents.len(), 1);
+        assert_eq!(game.opponents[0].stud.drawn.as_slice(), &[queen]);
+        assert_eq!(game.dead, [king]);
+        assert_eq!(game.mucked, 1);
+        let deck = game.sample_deck();
+        assert_eq!(deck.len(), 50);
+        assert!(!deck.contains(&king) && !deck.contains(&queen));
+    }
+
+    #[test]
+    fn discarded_hole_cards_are_dead() {
+        let mut game = Game::from_rounds(2, &pineapple());
+        let cards = |cards: &str| {
+            cards
+                .split_whitespace()
+                .map(|c| c.parse::<poker::Card>().unwrap().into())
+                .collect::<HandVec>()
+        };
+        assert_eq!(game.discards.as_slice(), &[1]);
+        game.player.hole.add_cards(cards("As Kd 7c"));
+        for o in game.opponents.iter_mut() {
+            o.hole.add_cards(3);
+        }
+
+        assert_eq!(
+            game.discard(&cards("7c 7c")),
+            Err(DiscardError::WrongCardCount {
+                expected: 1,
+                got: 2
+            })
+        );
+        assert_eq!(
+            game.discard(&cards("Ah")),
+            Err(DiscardError::NotInHand("Ah".into()))
+        );
+        game.discard(&cards("7c")).unwrap();
+        assert_eq!(
+            game.discard(&cards("As")),
+            Err(DiscardError::NoDiscardRound)
+        );
+
+        assert_eq!(game.player.hole.drawn, cards("As Kd"));
+        assert_eq!(game.pending_discards(), 0);
+        assert!(game.opponents.iter().all(|o| o.hole.total_cards() == 2));
+        assert_eq!(game.mucked, 2);
+        assert_eq!(game.sample_deck().len(), 49);
+    }
 }
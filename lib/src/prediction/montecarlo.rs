@@ -1,12 +1,20 @@
-use compact_poker::{SCard, SHand};
+use std::{cell::RefCell, ops::Range};
+
+use compact_poker::{SCard, SHand, Wild};
 
 use num_integer::binomial;
-use poker_assistant_lookup::LOOKUP;
-use rand::{seq::SliceRandom, Rng};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
 use smallvec::{smallvec, SmallVec};
 
-use super::model::{HandVec, PartialHand};
+use super::{
+    accumulator::ScoreAccumulator,
+    combinations::par_combinations,
+    deck::Deck,
+    draw::Exchanges,
+    model::{HandVec, PartialHand},
+    stats::{ScoreSummary, Z_95},
+};
 
 pub struct SimParams<'a> {
     /// Cards owned or ownable by the player.
@@ -20,10 +28,23 @@ pub struct SimParams<'a> {
     ///
     /// This is usually a set of (full deck) - (known cards owned by all players) - (cards in community) - (cards known to be thrown away)
     pub sample_deck: &'a [SCard],
+
+    /// Exchanges the player makes once every card has been drawn, treating
+    /// all of its cards as exchangeable.
+    pub exchanges: Exchanges,
+
+    /// Seed for reproducible results. See [`with_sample_rng`].
+    pub seed: Option<u64>,
+
+    /// Deck the cards are dealt from, which decides how hands are ranked.
+    pub deck: Deck,
+
+    /// Cards which are wild, besides jokers.
+    pub wild: Wild,
 }
 
 pub struct SimResult {
-    /// Cards that we randomly picked.
+    /// Cards that we randomly picked, before any exchange.
     pub sampled_undrawn: HandVec,
 
     /// Using those randomly-picked cards, the best hand we could have gotten.
@@ -35,18 +56,21 @@ pub struct SimResult {
 
 impl SimParams<'_> {
     pub fn run_sample(&self, mut rng: impl Rng) -> SimResult {
-        // Generate sampled player cards
-        let sampled_undrawn = self
+        // Generate sampled player cards, followed by replacements
+        let undrawn = self.player.undrawn as usize;
+        let sampled = self
             .sample_deck
-            .choose_multiple(&mut rng, self.player.undrawn.into())
+            .choose_multiple(&mut rng, undrawn + self.exchanges.max_replacements())
             .copied()
-            .collect::<HandVec>();
+            .collect::<SmallVec<[SCard; 16]>>();
+        let sampled_undrawn = sampled[..undrawn].iter().copied().collect::<HandVec>();
 
         // Build set of all hands we own
         let mut cards = sampled_undrawn.clone();
         cards.extend(self.player.drawn.iter().copied());
+        self.exchanges.play(&mut cards, &sampled[undrawn..]);
 
-        let (best_hand, score) = score_superhand(&cards[..]);
+        let (best_hand, score) = self.deck.score_superhand_wild(&cards[..], self.wild);
 
         SimResult {
             sampled_undrawn,
@@ -55,13 +79,21 @@ impl SimParams<'_> {
         }
     }
 
+    /// Number of ways of drawing the undrawn cards. Unbounded if there are
+    /// exchanges to come, as those are only ever sampled.
     pub fn n_possibilities(&self) -> u64 {
-        binomial(self.sample_deck.len() as u64, self.player.undrawn as u64)
+        match self.exchanges.max_replacements() {
+            0 => binomial(self.sample_deck.len() as u64, self.player.undrawn as u64),
+            _ => u64::MAX,
+        }
     }
 
+    /// Every way of drawing the undrawn cards, ignoring exchanges.
     pub fn run_exhaustive(&self) -> Vec<SimResult> {
         if self.player.undrawn == 0 {
-            let (best_hand, score) = score_superhand(&self.player.drawn);
+            let (best_hand, score) = self
+                .deck
+                .score_superhand_wild(&self.player.drawn, self.wild);
 
             return vec![SimResult {
                 sampled_undrawn: smallvec![],
@@ -70,106 +102,139 @@ impl SimParams<'_> {
             }];
         }
 
-        let draw_combos = combos(self.sample_deck, self.player.undrawn as usize);
-
-        draw_combos
-            .into_par_iter()
+        par_combinations(self.sample_deck, self.player.undrawn as usize)
             .map(|sampled_undrawn| {
                 let mut cards = sampled_undrawn.clone();
                 cards.extend(self.player.drawn.iter().copied());
-                let (best_hand, score) = score_superhand(&cards[..]);
+                let (best_hand, score) = self.deck.score_superhand_wild(&cards[..], self.wild);
 
                 SimResult {
-                    sampled_undrawn: sampled_undrawn.clone(),
+                    sampled_undrawn,
                     best_hand,
                     score,
                 }
             })
             .collect()
     }
-}
 
-/// Panics if provided hand is empty. Returns (hand of 5, score)
-pub fn score_superhand(hand: &[SCard]) -> (SHand, u32) {
-    let possible_hands = combos(hand, 5);
-
-    possible_hands
-        .into_iter()
-        .map(|h| {
-            let sh = SHand::from(&h[..]);
-            let score = LOOKUP[sh];
-            (sh, score)
-        })
-        .max_by_key(|(_h, s)| *s)
-        .unwrap()
-}
+    /// Scores of `n` random samples, or of every possibility if there are
+    /// fewer than `n` of them.
+    pub fn collect_scores(&self, n: u64) -> Vec<u32> {
+        self.scores(n)
+    }
 
-/// 21 is used because 7 choose 5 = 21.
-type CombosVec<T> = SmallVec<[T; 21]>;
+    /// Scores of [`SimParams::collect_scores`], gathered into any parallel
+    /// collection.
+    fn scores<C: FromParallelIterator<u32>>(&self, n: u64) -> C {
+        if n <= self.n_possibilities() {
+            return self.sample_scores(0..n);
+        }
+        if self.player.undrawn == 0 {
+            return rayon::iter::once(
+                self.deck
+                    .score_superhand_wild(&self.player.drawn, self.wild)
+                    .1,
+            )
+            .collect();
+        }
 
-/// Enumerate all combinations of the provided slice.
-///
-/// This only works for 1 <= choose <= 7. However, it should be very efficient.
-fn combos<T: Clone>(items: &[T], choose: usize) -> CombosVec<HandVec<T>> {
-    if choose == items.len() {
-        return smallvec![items.into()];
+        par_combinations(self.sample_deck, self.player.undrawn as usize)
+            .map(|mut cards| {
+                cards.extend(self.player.drawn.iter().copied());
+                self.deck.score_superhand_wild(&cards, self.wild).1
+            })
+            .collect()
     }
 
-    let mut current = smallvec![];
-    let mut dst = smallvec![];
-
-    macro_rules! make_for_loop {
-        ($card_range:expr, ) => {
-            dst.push(current.clone());
-        };
+    /// Scores of the samples with the given indices.
+    fn sample_scores<C: FromParallelIterator<u32>>(&self, indices: Range<u64>) -> C {
+        indices
+            .into_par_iter()
+            .map(|i| with_sample_rng(self.seed, i, |rng| self.run_sample(rng)))
+            .map(|result| result.score)
+            .collect()
+    }
 
-        // using unary encoding to count nesting
-        ($card_range:expr, $_:ident $($tail:ident)*) => {
-            let range: &[T] = $card_range;
-            for i in 0..range.len() {
-                current.push(range[i].clone());
-                make_for_loop!(&range[i+1..], $( $tail )*);
-                current.pop();
-            }
-        };
+    /// Statistics of [`SimParams::collect_scores`], without keeping every
+    /// score.
+    pub fn accumulate(&self, n: u64) -> ScoreAccumulator {
+        self.scores(n)
     }
 
-    match choose {
-        0 => (),
-        1 => {
-            make_for_loop!(&items[..], a);
-        }
-        2 => {
-            make_for_loop!(&items[..], a a);
-        }
-        3 => {
-            make_for_loop!(&items[..], a a a);
-        }
-        4 => {
-            make_for_loop!(&items[..], a a a a);
-        }
-        5 => {
-            make_for_loop!(&items[..], a a a a a);
-        }
-        6 => {
-            make_for_loop!(&items[..], a a a a a a);
-        }
-        7 => {
-            make_for_loop!(&items[..], a a a a a a a);
-        }
-        8 => {
-            make_for_loop!(&items[..], a a a a a a a a);
-        }
-        9 => {
-            make_for_loop!(&items[..], a a a a a a a a a);
+    /// Summary of [`SimParams::collect_scores`], with `n_bins` histogram bins.
+    ///
+    /// When every possibility is enumerated, there are no more of them than
+    /// samples asked for, so they are all kept to give exact order statistics.
+    pub fn summarize(&self, n: u64, n_bins: usize) -> ScoreSummary {
+        match n > self.n_possibilities() {
+            true => ScoreSummary::from_every_score(&mut self.collect_scores(n), n_bins),
+            false => self.accumulate(n).summary(n_bins),
         }
-        10 => {
-            make_for_loop!(&items[..], a a a a a a a a a a);
+    }
+
+    /// Sample in parallel batches until the 95% confidence interval of the
+    /// mean is at most `precision` either side, or `max` samples have been
+    /// taken. Every possibility is enumerated instead once that would take
+    /// no more samples.
+    pub fn summarize_to_precision(&self, precision: f32, max: u64, n_bins: usize) -> ScoreSummary {
+        let n_possibilities = self.n_possibilities();
+        let mut acc = ScoreAccumulator::default();
+        loop {
+            let n = acc.samples();
+            let needed = match n {
+                0 => PRECISION_BATCH,
+                _ => {
+                    let (_, stddev) = acc.mean_stddev();
+                    (Z_95 * stddev / precision as f64).powi(2).ceil() as u64
+                }
+            };
+
+            if needed.max(n) >= n_possibilities && max >= n_possibilities {
+                return self.summarize(n_possibilities + 1, n_bins);
+            }
+            if (n > 0 && needed <= n) || n >= max {
+                return acc.summary(n_bins);
+            }
+            acc.merge(&self.sample_scores(n..n + PRECISION_BATCH.min(max - n)));
         }
-        _ => unimplemented!(),
     }
+}
+
+/// Number of samples taken at a time by [`SimParams::summarize_to_precision`].
+const PRECISION_BATCH: u64 = 10_000;
+
+std::thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Run `f` with a fast RNG local to the current thread.
+pub fn with_thread_rng<T>(f: impl FnOnce(&mut SmallRng) -> T) -> T {
+    RNG.with_borrow_mut(f)
+}
+
+/// Run `f` with the RNG for the sample at `index`.
+///
+/// With a seed, every sample gets its own stream derived from the seed and
+/// its index, so results are the same however samples are split between
+/// threads. Without one, the thread's RNG is used.
+pub fn with_sample_rng<T>(seed: Option<u64>, index: u64, f: impl FnOnce(&mut SmallRng) -> T) -> T {
+    match seed {
+        Some(seed) => f(&mut SmallRng::seed_from_u64(stream_seed(seed, index))),
+        None => with_thread_rng(f),
+    }
+}
 
-    dst
+/// SplitMix64 output for the `index`th step from `seed`.
+fn stream_seed(seed: u64, index: u64) -> u64 {
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Panics if provided hand is empty. Returns (hand of 5, score)
+pub fn score_superhand(hand: &[SCard]) -> (SHand, u32) {
+    Deck::Standard.score_superhand(hand)
 }
 
 #[cfg(test)]
@@ -178,31 +243,84 @@ mod tests {
     use smallvec::smallvec;
 
     #[test]
-    fn get_possible_hands_works_equal_size() {
-        let result = combos(&[1, 2], 2);
+    fn summarize_to_precision_stops_early() {
+        use poker::{Rank, Suit};
 
-        let expected: Vec<HandVec<i32>> = vec![smallvec![1, 2]];
+        let drawn: HandVec = smallvec![
+            SCard::new(Rank::Ace, Suit::Spades),
+            SCard::new(Rank::King, Suit::Spades),
+        ];
+        let mut deck = SCard::deck().collect::<Vec<_>>();
+        deck.retain(|c| !drawn.contains(c));
+        let params = SimParams {
+            player: PartialHand { drawn, undrawn: 5 },
+            sample_deck: &deck,
+            exchanges: Default::default(),
+            seed: None,
+            deck: Default::default(),
+            wild: Default::default(),
+        };
+
+        let summary = params.summarize_to_precision(0.01, 1_000_000, 10);
 
-        assert_eq!(result.to_vec(), expected);
+        assert!(!summary.exhaustive);
+        assert!(summary.samples < 1_000_000);
+        assert!(summary.mean_margin(Z_95) <= 0.01);
     }
 
     #[test]
-    fn get_possible_hands_works() {
-        let result = combos(&[10, 11, 12, 13, 14], 3);
-
-        let expected: Vec<HandVec<i32>> = vec![
-            smallvec![10, 11, 12],
-            smallvec![10, 11, 13],
-            smallvec![10, 11, 14],
-            smallvec![10, 12, 13],
-            smallvec![10, 12, 14],
-            smallvec![10, 13, 14],
-            smallvec![11, 12, 13],
-            smallvec![11, 12, 14],
-            smallvec![11, 13, 14],
-            smallvec![12, 13, 14],
-        ];
+    fn summarize_to_precision_enumerates_small_spaces() {
+        let deck = SCard::deck().collect::<Vec<_>>();
+        let params = SimParams {
+            player: PartialHand {
+                drawn: deck[..4].into(),
+                undrawn: 1,
+            },
+            sample_deck: &deck[4..],
+            exchanges: Default::default(),
+            seed: None,
+            deck: Default::default(),
+            wild: Default::default(),
+        };
+
+        let summary = params.summarize_to_precision(0.0001, 1_000_000, 10);
+
+        assert!(summary.exhaustive);
+        assert_eq!(summary.samples, 48);
+    }
+
+    #[test]
+    fn seeded_scores_are_reproducible_across_thread_counts() {
+        let deck = SCard::deck().collect::<Vec<_>>();
+        let params = SimParams {
+            player: PartialHand {
+                drawn: deck[..2].into(),
+                undrawn: 5,
+            },
+            sample_deck: &deck[2..],
+            exchanges: Default::default(),
+            seed: Some(42),
+            deck: Default::default(),
+            wild: Default::default(),
+        };
+
+        let run = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| params.collect_scores(2000))
+        };
 
-        assert_eq!(result.to_vec(), expected);
+        let single = run(1);
+        assert_eq!(single, run(3));
+        assert_eq!(
+            params.summarize_to_precision(0.01, 100_000, 10),
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(2)
+                .build()
+                .unwrap()
+                .install(|| params.summarize_to_precision(0.01, 100_000, 10))
+        );
     }
 }
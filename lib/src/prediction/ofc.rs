@@ -0,0 +1,715 @@
+//! Open-face Chinese poker: 13 cards set face up in three rows, each of which
+//! must be at least as strong as the row above it.
+//!
+//! Royalties follow the usual American scale, and a player whose rows are out
+//! of order has fouled: they earn no royalties and lose every row.
+
+use std::{fmt, str::FromStr};
+
+use compact_poker::{SCard, SHand};
+use poker::Rank;
+use rand::seq::SliceRandom;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use super::{category::HandCategory, deck::Deck, model::HandVec, montecarlo::with_sample_rng};
+
+/// Bonus for winning all three rows against an opponent.
+pub const SCOOP_BONUS: i32 = 3;
+
+/// Points lost against an opponent who has not fouled, when fouling: every
+/// row and the scoop bonus.
+pub const FOUL_PENALTY: i32 = 3 + SCOOP_BONUS;
+
+/// Rows of an arrangement, from the weakest to the strongest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, strum::Display)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Row {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl Row {
+    pub const ALL: [Row; 3] = [Row::Top, Row::Middle, Row::Bottom];
+
+    /// Number of cards the row holds once complete.
+    pub fn size(&self) -> usize {
+        match self {
+            Row::Top => 3,
+            Row::Middle | Row::Bottom => 5,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArrangementError {
+    #[error("Could not parse card {0}")]
+    BadCard(String),
+
+    #[error("Expected 3 rows separated by '/', got {0}")]
+    BadRowCount(usize),
+
+    #[error("The {0} row holds at most {1} cards, got {2}")]
+    RowTooLong(Row, usize, usize),
+
+    #[error("Card {0} is placed more than once")]
+    DuplicateCard(String),
+
+    #[error("The {0} row is not complete")]
+    Incomplete(Row),
+
+    #[error("The {0} row beats the row below it")]
+    Fouled(Row),
+}
+
+/// Cards placed in each row, complete or not.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Arrangement {
+    pub top: HandVec,
+    pub middle: HandVec,
+    pub bottom: HandVec,
+}
+
+impl Arrangement {
+    pub fn row(&self, row: Row) -> &HandVec {
+        match row {
+            Row::Top => &self.top,
+            Row::Middle => &self.middle,
+            Row::Bottom => &self.bottom,
+        }
+    }
+
+    pub fn row_mut(&mut self, row: Row) -> &mut HandVec {
+        match row {
+            Row::Top => &mut self.top,
+            Row::Middle => &mut self.middle,
+            Row::Bottom => &mut self.bottom,
+        }
+    }
+
+    /// Every card placed, from the top row down.
+    pub fn cards(&self) -> impl Iterator<Item = SCard> + '_ {
+        Row::ALL
+            .into_iter()
+            .flat_map(|r| self.row(r).iter().copied())
+    }
+
+    /// Number of cards each row still has room for.
+    pub fn space(&self) -> [usize; 3] {
+        Row::ALL.map(|r| r.size().saturating_sub(self.row(r).len()))
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.space() == [0; 3]
+    }
+
+    /// Check that no row is too long and no card is placed twice.
+    pub fn check(&self) -> Result<(), ArrangementError> {
+        for row in Row::ALL {
+            let len = self.row(row).len();
+            if len > row.size() {
+                return Err(ArrangementError::RowTooLong(row, row.size(), len));
+            }
+        }
+        let cards = self.cards().collect::<Vec<_>>();
+        match cards
+            .iter()
+            .enumerate()
+            .find(|(i, c)| cards[..*i].contains(c))
+        {
+            Some((_, c)) => Err(ArrangementError::DuplicateCard(c.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the arrangement is complete and has not fouled.
+    pub fn validate(&self) -> Result<(), ArrangementError> {
+        self.check()?;
+        if let Some(row) = Row::ALL.into_iter().find(|r| self.row(*r).len() < r.size()) {
+            return Err(ArrangementError::Incomplete(row));
+        }
+        if beats(&self.top, &self.middle) {
+            return Err(ArrangementError::Fouled(Row::Top));
+        }
+        if self.score(Row::Middle) > self.score(Row::Bottom) {
+            return Err(ArrangementError::Fouled(Row::Middle));
+        }
+        Ok(())
+    }
+
+    /// Whether a complete arrangement has its rows out of order.
+    pub fn is_fouled(&self) -> bool {
+        matches!(self.validate(), Err(ArrangementError::Fouled(_)))
+    }
+
+    /// Score of a complete row, only comparable with the same row of another
+    /// arrangement.
+    pub fn score(&self, row: Row) -> u32 {
+        match row {
+            Row::Top => score_top(&self.top),
+            _ => Deck::Standard.score(SHand::from(&self.row(row)[..])),
+        }
+    }
+
+    /// Royalty earned by a complete row.
+    pub fn royalty(&self, row: Row) -> u32 {
+        if row == Row::Top {
+            return top_royalty(&self.top);
+        }
+
+        use HandCategory::*;
+        let cards = self.row(row);
+        let category = HandCategory::from_score(self.score(row));
+        let royal = category == StraightFlush
+            && cards.iter().any(|c| c.rank() == Rank::King)
+            && cards.iter().any(|c| c.rank() == Rank::Ace);
+        match (row, category) {
+            (Row::Bottom, StraightFlush) if royal => 25,
+            (Row::Middle, StraightFlush) if royal => 50,
+            (Row::Bottom, Straight) => 2,
+            (Row::Bottom, Flush) => 4,
+            (Row::Bottom, FullHouse) => 6,
+            (Row::Bottom, FourOfAKind) => 10,
+            (Row::Bottom, StraightFlush) => 15,
+            (Row::Middle, ThreeOfAKind) => 2,
+            (Row::Middle, Straight) => 4,
+            (Row::Middle, Flush) => 8,
+            (Row::Middle, FullHouse) => 12,
+            (Row::Middle, FourOfAKind) => 20,
+            (Row::Middle, StraightFlush) => 30,
+            _ => 0,
+        }
+    }
+
+    /// Royalties earned by a complete arrangement, or none if it has fouled.
+    pub fn royalties(&self) -> u32 {
+        match self.is_fouled() {
+            true => 0,
+            false => Row::ALL.iter().map(|r| self.royalty(*r)).sum(),
+        }
+    }
+
+    /// Points won against `other`, both being complete: one per row won, the
+    /// scoop bonus for winning every row, and the difference in royalties.
+    pub fn points(&self, other: &Arrangement) -> i32 {
+        let royalties = self.royalties() as i32 - other.royalties() as i32;
+        let rows = match (self.is_fouled(), other.is_fouled()) {
+            (true, true) => 0,
+            (true, false) => -FOUL_PENALTY,
+            (false, true) => FOUL_PENALTY,
+            (false, false) => {
+                let rows = Row::ALL
+                    .map(|r| self.score(r).cmp(&other.score(r)) as i32)
+                    .into_iter()
+                    .sum::<i32>();
+                match rows {
+                    3 => 3 + SCOOP_BONUS,
+                    -3 => -3 - SCOOP_BONUS,
+                    rows => rows,
+                }
+            }
+        };
+        rows + royalties
+    }
+
+    /// What a complete arrangement is worth: points against each opponent,
+    /// or without opponents, its royalties less [`FOUL_PENALTY`] if it has
+    /// fouled.
+    pub fn value(&self, opponents: &[Arrangement]) -> i32 {
+        match opponents.is_empty() {
+            true if self.is_fouled() => -FOUL_PENALTY,
+            true => self.royalties() as i32,
+            false => opponents.iter().map(|o| self.points(o)).sum(),
+        }
+    }
+
+    /// This arrangement with `placed` added to each row.
+    fn with(&self, placed: &[HandVec; 3]) -> Self {
+        let mut arrangement = self.clone();
+        for (row, cards) in Row::ALL.iter().zip(placed) {
+            arrangement.row_mut(*row).extend(cards.iter().copied());
+        }
+        arrangement
+    }
+
+    /// The arrangement completed by placing `cards` one at a time, in the
+    /// order they are dealt, each in the row whose [`Arrangement::outlook`]
+    /// is then best. Cards still to come are not known when placing each
+    /// card, as in play.
+    fn complete(&self, cards: &[SCard]) -> Self {
+        let mut arrangement = self.clone();
+        for card in cards {
+            let placed = Row::ALL
+                .into_iter()
+                .filter(|r| arrangement.row(*r).len() < r.size())
+                .map(|r| {
+                    let mut placed = arrangement.clone();
+                    placed.row_mut(r).push(*card);
+                    placed
+                })
+                .max_by(|a, b| a.outlook().total_cmp(&b.outlook()))
+                .expect("more cards than places left");
+            arrangement = placed;
+        }
+        arrangement
+    }
+
+    /// What a row has made so far: its category, then its ranks, biggest
+    /// groups first. Straights and flushes only count once the row is
+    /// complete.
+    fn made(&self, row: Row) -> (HandCategory, HandVec<Rank>) {
+        let cards = self.row(row);
+        let groups = grouped_ranks(cards);
+        let category = match (row, &groups[..]) {
+            (Row::Top, _) => top_category(cards),
+            _ if cards.len() == row.size() => HandCategory::from_score(self.score(row)),
+            (_, [(4, _), ..]) => HandCategory::FourOfAKind,
+            (_, [(3, _), (2, _), ..]) => HandCategory::FullHouse,
+            (_, [(3, _), ..]) => HandCategory::ThreeOfAKind,
+            (_, [(2, _), (2, _), ..]) => HandCategory::TwoPair,
+            (_, [(2, _), ..]) => HandCategory::Pair,
+            _ => HandCategory::HighCard,
+        };
+        (category, groups.into_iter().map(|(_, r)| r).collect())
+    }
+
+    /// How promising a partial arrangement looks when placing one card at a
+    /// time. Rows out of order cost the most, and foul for certain once the
+    /// weaker row is complete, as rows only get stronger. Beyond that, made
+    /// hands are worth more lower down, royalties count once a row is
+    /// complete, high cards are kept off the top row while it has no pair,
+    /// and rows are filled evenly.
+    fn outlook(&self) -> f64 {
+        let made = Row::ALL.map(|r| self.made(r));
+        let mut outlook = 0.0;
+        for (i, row) in Row::ALL.into_iter().enumerate() {
+            let len = self.row(row).len();
+            let complete = len == row.size();
+            if i > 0 && made[i - 1] > made[i] {
+                outlook -= match complete {
+                    true => 1000.0,
+                    false => 10.0,
+                };
+            }
+            outlook += (i + 1) as f64 * made[i].0 as usize as f64;
+            if complete {
+                outlook += self.royalty(row) as f64;
+            }
+            outlook -= (len as f64 / row.size() as f64).powi(2);
+        }
+        if let (HandCategory::HighCard, [high, ..]) = (made[0].0, &made[0].1[..]) {
+            outlook -= *high as usize as f64 / 12.0;
+        }
+        outlook
+    }
+}
+
+impl fmt::Display for Arrangement {
+    /// Rows from the top down, separated by slashes, e.g.
+    /// `"Ah Ad 2c / 9s 9c 9h 3d 4d / Ks Qs Js Ts 8s"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = Row::ALL.map(|r| {
+            self.row(r)
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        write!(f, "{}", rows.join(" / "))
+    }
+}
+
+impl FromStr for Arrangement {
+    type Err = ArrangementError;
+
+    /// Rows from the top down, separated by slashes. Rows may be incomplete
+    /// or empty.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .split('/')
+            .map(|row| {
+                row.split_whitespace()
+                    .map(|c| {
+                        c.parse::<poker::Card>()
+                            .map(SCard::from)
+                            .map_err(|_| ArrangementError::BadCard(c.to_owned()))
+                    })
+                    .collect::<Result<HandVec, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let [top, middle, bottom] = <[HandVec; 3]>::try_from(rows)
+            .map_err(|rows| ArrangementError::BadRowCount(rows.len()))?;
+
+        let arrangement = Arrangement {
+            top,
+            middle,
+            bottom,
+        };
+        arrangement.check()?;
+        Ok(arrangement)
+    }
+}
+
+/// Ranks of a hand, the biggest groups of the same rank first, then the
+/// highest.
+fn grouped_ranks(cards: &[SCard]) -> HandVec<(usize, Rank)> {
+    let mut groups = HandVec::<(usize, Rank)>::new();
+    for c in cards {
+        match groups.iter_mut().find(|(_, r)| *r == c.rank()) {
+            Some((n, _)) => *n += 1,
+            None => groups.push((1, c.rank())),
+        }
+    }
+    groups.sort_by(|a, b| b.cmp(a));
+    groups
+}
+
+/// Category of a 3-card top row: only high cards, pairs and three of a kind
+/// can be made.
+fn top_category(top: &[SCard]) -> HandCategory {
+    match grouped_ranks(top).first() {
+        Some((3, _)) => HandCategory::ThreeOfAKind,
+        Some((2, _)) => HandCategory::Pair,
+        _ => HandCategory::HighCard,
+    }
+}
+
+/// Score of a 3-card top row, by category, then the rank of the pair or three
+/// of a kind, then the kickers.
+pub fn score_top(top: &[SCard]) -> u32 {
+    grouped_ranks(top)
+        .iter()
+        .chain(std::iter::repeat(&(0, Rank::Two)))
+        .take(3)
+        .fold(top_category(top) as u32, |score, (n, r)| {
+            (score << 4) | ((*n > 0) as u32 * (*r as u32 + 1))
+        })
+}
+
+/// Whether the top row beats the middle row. A top row matching the middle
+/// row's ranks as far as it goes is beaten by the middle row's extra kickers.
+fn beats(top: &[SCard], middle: &[SCard]) -> bool {
+    let middle_category = HandCategory::from_score(Deck::Standard.score(SHand::from(middle)));
+    let ranks = |cards| {
+        grouped_ranks(cards)
+            .into_iter()
+            .map(|(_, r)| r)
+            .collect::<HandVec<_>>()
+    };
+    (top_category(top), ranks(top)) > (middle_category, ranks(middle))
+}
+
+/// Royalty earned by a top row: from a pair of sixes up, and any three of a
+/// kind.
+fn top_royalty(top: &[SCard]) -> u32 {
+    match grouped_ranks(top).first() {
+        Some((3, r)) => 10 + *r as u32,
+        Some((2, r)) if *r >= Rank::Six => *r as u32 - Rank::Five as u32,
+        _ => 0,
+    }
+}
+
+/// Every way of placing `cards` into rows with room for up to `space` cards,
+/// the first cards first.
+fn placements(cards: &[SCard], space: [usize; 3]) -> Vec<[HandVec; 3]> {
+    let Some((card, rest)) = cards.split_first() else {
+        return vec![Default::default()];
+    };
+    (0..3)
+        .filter(|i| space[*i] > 0)
+        .flat_map(|i| {
+            let mut left = space;
+            left[i] -= 1;
+            placements(rest, left).into_iter().map(move |mut placed| {
+                placed[i].insert(0, *card);
+                placed
+            })
+        })
+        .collect()
+}
+
+pub struct PlacementParams<'a> {
+    /// Our arrangement so far.
+    pub arrangement: Arrangement,
+
+    /// Cards to place now.
+    pub cards: HandVec,
+
+    /// Arrangements of the opponents so far. Their empty places are filled
+    /// from the sample deck too.
+    pub opponents: Vec<Arrangement>,
+
+    /// Deck the rest of each arrangement is drawn from. It should not contain
+    /// any card placed or to place.
+    pub sample_deck: &'a [SCard],
+
+    /// Seed for reproducible results. Every placement is evaluated over the
+    /// same samples. See [`with_sample_rng`].
+    pub seed: Option<u64>,
+}
+
+/// Outcome of placing the cards in some rows.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Placement {
+    /// Our arrangement after placing the cards.
+    pub arrangement: Arrangement,
+
+    /// Mean [`Arrangement::value`] of the completed arrangement. Later cards
+    /// are placed by a simple policy rather than perfect play, so this
+    /// underestimates what a strong player would make of it.
+    pub value: f64,
+
+    /// Standard error of [`Placement::value`].
+    pub stderr: f64,
+
+    /// Probability of fouling under the same policy.
+    pub foul: f64,
+
+    /// Mean royalties.
+    pub royalties: f64,
+}
+
+impl PlacementParams<'_> {
+    /// Every way of placing the cards in rows with room for them.
+    pub fn options(&self) -> Vec<Arrangement> {
+        placements(&self.cards, self.arrangement.space())
+            .iter()
+            .map(|placed| self.arrangement.with(placed))
+            .collect()
+    }
+
+    /// Evaluate `arrangement` over `n` samples. In each, the empty places of
+    /// every arrangement are dealt from the sample deck one card at a time,
+    /// and each card is placed without knowing the cards to come. See
+    /// [`Arrangement::complete`].
+    pub fn evaluate(&self, arrangement: &Arrangement, n: u64) -> Placement {
+        let needed = std::iter::once(arrangement)
+            .chain(&self.opponents)
+            .map(|a| a.space().iter().sum::<usize>())
+            .collect::<Vec<_>>();
+
+        let (sum, sum_sq, fouls, royalties) = (0..n)
+            .into_par_iter()
+            .map(|i| {
+                with_sample_rng(self.seed, i, |rng| {
+                    let pool = self
+                        .sample_deck
+                        .choose_multiple(rng, needed.iter().sum())
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let mut dealt = pool.iter().copied();
+                    let mut complete = |a: &Arrangement, n: usize| {
+                        let cards = dealt.by_ref().take(n).collect::<Vec<_>>();
+                        a.complete(&cards)
+                    };
+                    let ours = complete(arrangement, needed[0]);
+                    let opponents = self
+                        .opponents
+                        .iter()
+                        .zip(&needed[1..])
+                        .map(|(a, n)| complete(a, *n))
+                        .collect::<Vec<_>>();
+                    let value = ours.value(&opponents) as f64;
+                    (
+                        value,
+                        value * value,
+                        ours.is_fouled() as u64,
+                        ours.royalties() as u64,
+                    )
+                })
+            })
+            .reduce(
+                || (0.0, 0.0, 0, 0),
+                |a, b| (a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3),
+            );
+
+        let n = n.max(1) as f64;
+        let value = sum / n;
+        let variance = (sum_sq / n - value * value).max(0.0);
+        Placement {
+            arrangement: arrangement.clone(),
+            value,
+            stderr: (variance / n).sqrt(),
+            foul: fouls as f64 / n,
+            royalties: royalties as f64 / n,
+        }
+    }
+
+    /// Every placement evaluated over `n` samples, best first.
+    pub fn advise(&self, n: u64) -> Vec<Placement> {
+        let mut options = self
+            .options()
+            .iter()
+            .map(|a| self.evaluate(a, n))
+            .collect::<Vec<_>>();
+        options.sort_by(|a, b| b.value.total_cmp(&a.value));
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arrangement(s: &str) -> Arrangement {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_check() {
+        let a = arrangement("Ah Ad 2c / 9s 9c 9h 3d 4d / Ks Qs Js Ts 8s");
+        assert_eq!(a.to_string(), "Ah Ad 2c / 9s 9c 9h 3d 4d / Ks Qs Js Ts 8s");
+        assert!(a.is_complete());
+        assert_eq!(arrangement("Ah / / 2c 3c").space(), [2, 5, 3]);
+
+        assert_eq!(
+            "Ah Ad / 9s".parse::<Arrangement>(),
+            Err(ArrangementError::BadRowCount(2))
+        );
+        assert_eq!(
+            "Ah Ad 2c 3c / / ".parse::<Arrangement>(),
+            Err(ArrangementError::RowTooLong(Row::Top, 3, 4))
+        );
+        assert_eq!(
+            "Ah / Ah / ".parse::<Arrangement>(),
+            Err(ArrangementError::DuplicateCard("Ah".into()))
+        );
+        assert_eq!(
+            arrangement("Ah / / ").validate(),
+            Err(ArrangementError::Incomplete(Row::Top))
+        );
+    }
+
+    #[test]
+    fn rows_must_get_stronger() {
+        // Aces on top beat a pair of kings in the middle.
+        let fouled = arrangement("Ah Ad 2c / Ks Kc 9h 3d 4d / Qs Qc Js Ts 8s");
+        assert_eq!(fouled.validate(), Err(ArrangementError::Fouled(Row::Top)));
+        assert_eq!(fouled.royalties(), 0);
+
+        // Two pair in the middle beats one pair at the bottom.
+        let fouled = arrangement("2h 3d 4c / Ks Kc 9h 9d 5d / Qs Qc Js Ts 8s");
+        assert_eq!(
+            fouled.validate(),
+            Err(ArrangementError::Fouled(Row::Middle))
+        );
+
+        // The middle row's extra kickers beat the same pair and kicker on top.
+        let kickers = arrangement("Kh Kd Ac / Ks Kc Ah 3d 2d / Qs Qc Qd Ts 8s");
+        assert_eq!(kickers.validate(), Ok(()));
+        let kickers = arrangement("Kh Kd Ac / Ks Kc Qh 3d 2d / Qs Qc Qd Ts 8s");
+        assert!(kickers.is_fouled());
+    }
+
+    #[test]
+    fn top_scores_and_royalties() {
+        let top = |s: &str| {
+            s.split_whitespace()
+                .map(|c| c.parse::<poker::Card>().unwrap().into())
+                .collect::<HandVec>()
+        };
+        let order = [
+            "7h 5d 2c", "Ah Kd Qc", "2h 2d 3c", "2h 2d Ac", "Ah Ad Kc", "2h 2d 2c",
+        ];
+        let scores = order.map(|s| score_top(&top(s)));
+        assert!(scores.windows(2).all(|w| w[0] < w[1]), "{scores:?}");
+
+        assert_eq!(top_royalty(&top("5h 5d Ac")), 0);
+        assert_eq!(top_royalty(&top("6h 6d 2c")), 1);
+        assert_eq!(top_royalty(&top("Ah Ad Kc")), 9);
+        assert_eq!(top_royalty(&top("2h 2d 2c")), 10);
+        assert_eq!(top_royalty(&top("Ah Ad Ac")), 22);
+    }
+
+    #[test]
+    fn royalties_and_points() {
+        let strong = arrangement("Qh Qd 2c / 8s 8c 8h 3d 4d / Ks Qs Js Ts 9s");
+        assert_eq!(strong.validate(), Ok(()));
+        assert_eq!(strong.royalties(), 7 + 2 + 15);
+
+        let royal = arrangement("2h 3d 4c / 5s 6c 7h 8d 9h / As Ks Qs Js Ts");
+        assert_eq!(royal.royalty(Row::Bottom), 25);
+        assert_eq!(royal.royalty(Row::Middle), 4);
+
+        let weak = arrangement("2h 3d 5c / 6s 7c 8d Td Jh / Ac Kc Qc Jc 9c");
+        assert_eq!(weak.royalties(), 4);
+        // A scoop, with the royalties of each side.
+        assert_eq!(strong.points(&weak), 3 + SCOOP_BONUS + 24 - 4);
+        assert_eq!(weak.points(&strong), -strong.points(&weak));
+
+        let fouled = arrangement("Ah Ad 2c / Ks Kc 9h 3d 4d / Qs Qc Js Ts 8s");
+        assert_eq!(weak.points(&fouled), FOUL_PENALTY + 4);
+        assert_eq!(fouled.points(&fouled), 0);
+    }
+
+    #[test]
+    fn placements_respect_space() {
+        let cards = arrangement("Ah Kh Qh / / ").top;
+
+        assert_eq!(placements(&cards, [1, 1, 1]).len(), 6);
+        assert_eq!(placements(&cards, [0, 5, 5]).len(), 8);
+    }
+
+    #[test]
+    fn completes_without_seeing_later_cards() {
+        let cards = arrangement("Ah Kd Qc / 7h 7c 2s 3s 4d / 8h 8d 9s 9c Jd")
+            .cards()
+            .collect::<Vec<_>>();
+        let a = arrangement("/ / ").complete(&cards);
+
+        // Pairs go low, the top row stays weak, and the rows are filled.
+        assert!(a.is_complete());
+        assert_eq!(a.validate(), Ok(()));
+        assert!(a.top.iter().all(|c| c.rank() < Rank::Ace), "{a}");
+    }
+
+    #[test]
+    fn advisor_does_not_know_the_future() {
+        let ours = arrangement("/ / ");
+        let placed = arrangement("As Kd 7h / 7c 2s / ")
+            .cards()
+            .collect::<HandVec>();
+        let deck = SCard::deck()
+            .filter(|c| !placed.contains(c))
+            .collect::<Vec<_>>();
+        let params = PlacementParams {
+            arrangement: ours,
+            cards: placed,
+            opponents: vec![],
+            sample_deck: &deck,
+            seed: Some(1),
+        };
+
+        // Even the best start fouls sometimes once later cards are not known.
+        let best = params.evaluate(&arrangement("As / Kd / 7h 7c 2s"), 500);
+        assert!(best.foul > 0.0 && best.foul < 0.5, "{best:?}");
+    }
+
+    #[test]
+    fn advisor_avoids_fouling() {
+        // Another ace on top would beat the middle row's three kings.
+        let ours = arrangement("Ah Ad / Ks Kd Kc 6c / Qs Qd Qc 8h 8d");
+        let placed = arrangement("Ac / / ").top;
+        let deck = SCard::deck()
+            .filter(|c| !ours.cards().chain(placed.iter().copied()).any(|o| o == *c))
+            .collect::<Vec<_>>();
+        let params = PlacementParams {
+            arrangement: ours,
+            cards: placed,
+            opponents: vec![],
+            sample_deck: &deck,
+            seed: Some(1),
+        };
+
+        let options = params.advise(500);
+
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0].arrangement.middle.len(), 5);
+        assert_eq!(options[1].foul, 1.0);
+        // Only the last ace on top fouls.
+        assert!(options[0].foul < 0.1, "{:?}", options[0]);
+        assert!(options[0].royalties > 15.0, "{:?}", options[0]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Weighted ranges of two-card starting hands, such as `"AA, KK, AKs, AQo:0.5"`.
+
+use std::str::FromStr;
+
+use compact_poker::SCard;
+use poker::Rank;
+use rand::Rng;
+use smallvec::SmallVec;
+
+/// A class of starting hands, like `AKs` or `TT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandClass {
+    pub high: Rank,
+    pub low: Rank,
+
+    /// Whether the cards share a suit, or `None` to match either.
+    pub suited: Option<bool>,
+}
+
+impl HandClass {
+    /// Jokers are in no class.
+    pub fn contains(&self, a: SCard, b: SCard) -> bool {
+        if a.is_joker() || b.is_joker() {
+            return false;
+        }
+        let (high, low) = match a.rank() >= b.rank() {
+            true => (a.rank(), b.rank()),
+            false => (b.rank(), a.rank()),
+        };
+        high == self.high
+            && low == self.low
+            && self.suited.map_or(true, |s| s == (a.suit() == b.suit()))
+    }
+}
+
+/// Weights of two-card starting hands. Hands not in any class have weight 0.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Range {
+    pub classes: Vec<(HandClass, f64)>,
+}
+
+impl Range {
+    /// Weight of the first class containing the hand.
+    pub fn weight(&self, a: SCard, b: SCard) -> f64 {
+        self.classes
+            .iter()
+            .find(|(class, _)| class.contains(a, b))
+            .map_or(0.0, |(_, w)| *w)
+    }
+
+    /// Deal `dealt`, a two-card hand, again from the hands of the range whose
+    /// cards are in `dealt` or `spare`, each with a probability proportional
+    /// to its weight. Cards are swapped with ones from `spare`, so the two
+    /// stay disjoint. Returns false, leaving `dealt` as it is, if no hand of
+    /// the range is left.
+    pub fn condition(
+        &self,
+        dealt: &mut [SCard; 2],
+        spare: &mut [SCard],
+        rng: &mut impl Rng,
+    ) -> bool {
+        let mut by_rank: [SmallVec<[SCard; 4]>; 13] = Default::default();
+        for c in dealt.iter().chain(spare.iter()).filter(|c| !c.is_joker()) {
+            by_rank[c.rank() as usize].push(*c);
+        }
+
+        // Each pair of ranks once, as classes such as AK and AKs overlap.
+        let mut rank_pairs = SmallVec::<[(Rank, Rank); 16]>::new();
+        for (class, _) in self.classes.iter().filter(|(_, w)| *w > 0.0) {
+            if !rank_pairs.contains(&(class.high, class.low)) {
+                rank_pairs.push((class.high, class.low));
+            }
+        }
+
+        let mut hands = SmallVec::<[(SCard, SCard, f64); 64]>::new();
+        let mut total = 0.0;
+        for (high, low) in rank_pairs {
+            let highs = &by_rank[high as usize];
+            for (i, a) in highs.iter().enumerate() {
+                let lows = match high == low {
+                    true => &highs[i + 1..],
+                    false => &by_rank[low as usize][..],
+                };
+                for b in lows {
+                    let weight = self.weight(*a, *b);
+                    if weight > 0.0 {
+                        hands.push((*a, *b, weight));
+                        total += weight;
+                    }
+                }
+            }
+        }
+        if hands.is_empty() {
+            return false;
+        }
+
+        let mut r = rng.gen::<f64>() * total;
+        let (a, b, _) = *hands
+            .iter()
+            .find(|(.., weight)| {
+                r -= weight;
+                r < 0.0
+            })
+            .unwrap_or(&hands[hands.len() - 1]);
+        let chosen = [a, b];
+        let mut incoming = chosen
+            .into_iter()
+            .filter(|c| !dealt.contains(c))
+            .collect::<SmallVec<[SCard; 2]>>()
+            .into_iter();
+        for slot in dealt.iter_mut().filter(|c| !chosen.contains(c)) {
+            let card = incoming.next().expect("as many cards in as out");
+            let i = spare.iter().position(|c| *c == card).expect("a spare card");
+            std::mem::swap(slot, &mut spare[i]);
+        }
+        true
+    }
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseRangeError {
+    #[error("Bad hand class {0}")]
+    BadClass(String),
+
+    #[error("Bad weight {0}")]
+    BadWeight(String),
+}
+
+impl FromStr for HandClass {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseRangeError::BadClass(s.to_owned());
+        let mut chars = s.chars();
+        let mut rank = || chars.next().and_then(|c| Rank::try_from(c).ok());
+        let (a, b) = (rank().ok_or_else(err)?, rank().ok_or_else(err)?);
+        let suited = match &s[2..] {
+            "" => None,
+            "s" if a != b => Some(true),
+            "o" if a != b => Some(false),
+            _ => return Err(err()),
+        };
+
+        Ok(HandClass {
+            high: a.max(b),
+            low: a.min(b),
+            suited,
+        })
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    /// Classes are separated by commas or whitespace, each optionally
+    /// followed by `:weight`. The default weight is 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let classes = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|t| !t.is_empty())
+            .map(|token| {
+                let (class, weight) = token.split_once(':').unwrap_or((token, "1"));
+                let weight = weight
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|w| *w >= 0.0)
+                    .ok_or_else(|| ParseRangeError::BadWeight(weight.to_owned()))?;
+                Ok((class.parse()?, weight))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Range { classes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::Suit;
+
+    use super::*;
+
+    #[test]
+    fn parse_and_weigh() {
+        let range: Range = "AA, AKs KQo:0.5".parse().unwrap();
+        let c = SCard::new;
+
+        assert_eq!(range.classes.len(), 3);
+        assert_eq!(
+            range.weight(c(Rank::Ace, Suit::Spades), c(Rank::Ace, Suit::Hearts)),
+            1.0
+        );
+        assert_eq!(
+            range.weight(c(Rank::King, Suit::Clubs), c(Rank::Ace, Suit::Clubs)),
+            1.0
+        );
+        assert_eq!(
+            range.weight(c(Rank::King, Suit::Clubs), c(Rank::Ace, Suit::Hearts)),
+            0.0
+        );
+        assert_eq!(
+            range.weight(c(Rank::Queen, Suit::Clubs), c(Rank::King, Suit::Hearts)),
+            0.5
+        );
+        assert_eq!(
+            range.weight(c(Rank::Ace, Suit::Spades), SCard::JOKERS[0]),
+            0.0
+        );
+    }
+
+    #[test]
+    fn conditions_on_hands_left() {
+        use rand::{rngs::SmallRng, SeedableRng};
+
+        let kings: Range = "KK".parse().unwrap();
+        let c = SCard::new;
+        let mut rng = SmallRng::seed_from_u64(1);
+        let mut deck = SCard::deck()
+            .filter(|d| *d != c(Rank::King, Suit::Spades) && *d != c(Rank::King, Suit::Hearts))
+            .collect::<Vec<_>>();
+
+        let (dealt, spare) = deck.split_at_mut(2);
+        let mut dealt = [dealt[0], dealt[1]];
+        assert!(kings.condition(&mut dealt, spare, &mut rng));
+        dealt.sort();
+        assert_eq!(
+            dealt,
+            [c(Rank::King, Suit::Clubs), c(Rank::King, Suit::Diamonds)]
+        );
+        assert!(!spare.iter().any(|d| dealt.contains(d)));
+
+        let mut spare = spare
+            .iter()
+            .copied()
+            .filter(|d| d.rank() != Rank::King)
+            .collect::<Vec<_>>();
+        let mut dealt = [c(Rank::Two, Suit::Clubs), c(Rank::King, Suit::Clubs)];
+        assert!(!kings.condition(&mut dealt, &mut spare, &mut rng));
+    }
+
+    #[test]
+    fn bad_ranges() {
+        assert!("AAs".parse::<Range>().is_err());
+        assert!("A".parse::<Range>().is_err());
+        assert!("AKx".parse::<Range>().is_err());
+        assert!("AK:-1".parse::<Range>().is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! Sampling strategies that reduce the variance of Monte Carlo estimates
+//! compared to drawing each sample independently with [`SimParams::run_sample`].
+
+use compact_poker::SCard;
+use rand::{
+    seq::{index, SliceRandom},
+    Rng,
+};
+use rayon::iter::{repeatn, FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use super::{
+    accumulator::ScoreAccumulator,
+    model::HandVec,
+    montecarlo::{with_sample_rng, SimParams},
+    range::Range,
+    stats::ScoreSummary,
+};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Strategy {
+    /// Independent samples, as drawn by [`SimParams::run_sample`].
+    #[default]
+    Uniform,
+
+    /// Every card in the deck is dealt first equally often, and the rest are
+    /// drawn at random.
+    Stratified,
+
+    /// Samples come in pairs, the second taking the cards opposite the first
+    /// in deck order, so that low cards become high ones.
+    Antithetic,
+
+    /// Each draw is scored again with the suits of each rank rotated, once
+    /// per suit. Rotations only change scores through flushes, so this is
+    /// mostly useful when many cards are known.
+    SuitPermuted,
+
+    /// The first two undrawn cards are a hand from the range, drawn uniformly
+    /// from the hands it contains and weighted by the range. The rest are
+    /// drawn at random.
+    Importance(Range),
+}
+
+impl Strategy {
+    /// Number of scores produced per draw, which sample counts are rounded
+    /// up to a multiple of.
+    fn group_size(&self, deck_size: usize) -> u64 {
+        match self {
+            Strategy::Uniform | Strategy::Importance(_) => 1,
+            Strategy::Stratified => deck_size as u64,
+            Strategy::Antithetic => 2,
+            Strategy::SuitPermuted => 4,
+        }
+    }
+}
+
+impl SimParams<'_> {
+    fn score_sampled(&self, sampled: impl IntoIterator<Item = SCard>) -> u32 {
+        let mut cards = self.player.drawn.clone();
+        cards.extend(sampled);
+        self.deck.score_superhand_wild(&cards, self.wild).1
+    }
+
+    /// Weighted scores of at least `n` samples drawn with `strategy`, gathered
+    /// into any parallel collection. The count is rounded up to a whole
+    /// number of strata, pairs or rotations.
+    ///
+    /// Only [`Strategy::Uniform`] plays out [`SimParams::exchanges`]; the
+    /// other strategies score the hand as drawn.
+    ///
+    /// Panics for [`Strategy::Importance`] if fewer than two cards are
+    /// undrawn, or if the range contains no hand in the deck.
+    pub fn sample_with<C>(&self, strategy: &Strategy, n: u64) -> C
+    where
+        C: FromParallelIterator<(u32, f64)>,
+    {
+        let deck = self.sample_deck;
+        let k = self.player.undrawn as usize;
+        let exchanging = *strategy == Strategy::Uniform && self.exchanges.max_replacements() > 0;
+        if k == 0 && !exchanging {
+            return repeatn((self.score_sampled([]), 1.0), n as usize).collect();
+        }
+
+        let group_size = strategy.group_size(deck.len());
+        let groups = n.div_ceil(group_size);
+
+        match strategy {
+            Strategy::Uniform => (0..groups)
+                .into_par_iter()
+                .map(|i| {
+                    (
+                        with_sample_rng(self.seed, i, |rng| self.run_sample(rng)).score,
+                        1.0,
+                    )
+                })
+                .collect(),
+            Strategy::Stratified => (0..groups * group_size)
+                .into_par_iter()
+                .map(|i| {
+                    let first = (i % group_size) as usize;
+                    let rest = with_sample_rng(self.seed, i, |rng| {
+                        draw_excluding(rng, deck, &[first], k - 1)
+                    });
+                    (
+                        self.score_sampled(rest.into_iter().chain([deck[first]])),
+                        1.0,
+                    )
+                })
+                .collect(),
+            Strategy::Antithetic => {
+                let mirror = mirror_table(deck);
+                (0..groups)
+                    .into_par_iter()
+                    .flat_map_iter(|g| {
+                        let drawn = with_sample_rng(self.seed, g, |rng| draw(rng, deck, k));
+                        let mirrored = drawn.iter().map(|c| mirror[c.raw() as usize].unwrap());
+                        [
+                            (self.score_sampled(drawn.iter().copied()), 1.0),
+                            (self.score_sampled(mirrored), 1.0),
+                        ]
+                    })
+                    .collect()
+            }
+            Strategy::SuitPermuted => {
+                let rotations = rotation_tables(deck);
+                (0..groups)
+                    .into_par_iter()
+                    .flat_map_iter(|g| {
+                        let drawn = with_sample_rng(self.seed, g, |rng| draw(rng, deck, k));
+                        rotations
+                            .iter()
+                            .map(|table| {
+                                let rotated =
+                                    drawn.iter().map(|c| table[c.raw() as usize].unwrap());
+                                (self.score_sampled(rotated), 1.0)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            }
+            Strategy::Importance(range) => {
+                assert!(k >= 2, "importance sampling needs two undrawn cards");
+                let support = (0..deck.len())
+                    .flat_map(|a| (a + 1..deck.len()).map(move |b| (a, b)))
+                    .filter(|(a, b)| range.weight(deck[*a], deck[*b]) > 0.0)
+                    .collect::<Vec<_>>();
+                assert!(!support.is_empty(), "range contains no hand in the deck");
+
+                (0..groups)
+                    .into_par_iter()
+                    .map(|i| {
+                        let ((a, b), rest) = with_sample_rng(self.seed, i, |rng| {
+                            let (a, b) = support[rng.gen_range(0..support.len())];
+                            ((a, b), draw_excluding(rng, deck, &[a, b], k - 2))
+                        });
+                        (
+                            self.score_sampled(rest.into_iter().chain([deck[a], deck[b]])),
+                            range.weight(deck[a], deck[b]),
+                        )
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Summary of [`SimParams::sample_with`]. Standard errors assume
+    /// independent samples, so they overestimate the error of strategies
+    /// other than [`Strategy::Uniform`] and [`Strategy::Importance`].
+    pub fn summarize_with(&self, strategy: &Strategy, n: u64, n_bins: usize) -> ScoreSummary {
+        self.sample_with::<ScoreAccumulator>(strategy, n)
+            .summary(n_bins)
+    }
+}
+
+fn draw(rng: &mut impl Rng, deck: &[SCard], k: usize) -> HandVec {
+    deck.choose_multiple(rng, k).copied().collect()
+}
+
+/// Draw `k` cards from the deck, skipping the cards at the sorted indices
+/// `excluded`.
+pub(super) fn draw_excluding(
+    rng: &mut impl Rng,
+    deck: &[SCard],
+    excluded: &[usize],
+    k: usize,
+) -> HandVec {
+    index::sample(rng, deck.len() - excluded.len(), k)
+        .into_iter()
+        .map(|mut i| {
+            for e in excluded {
+                if i >= *e {
+                    i += 1;
+                }
+            }
+            deck[i]
+        })
+        .collect()
+}
+
+/// Maps each card in the deck to the card at the opposite position in the
+/// sorted deck, indexed by [`SCard::raw`].
+fn mirror_table(deck: &[SCard]) -> [Option<SCard>; 64] {
+    let mut sorted = deck.to_vec();
+    sorted.sort();
+    let mut table = [None; 64];
+    for (c, m) in sorted.iter().zip(sorted.iter().rev()) {
+        table[c.raw() as usize] = Some(*m);
+    }
+    table
+}
+
+/// For each of 4 rotations, maps each card in the deck to the card that many
+/// places along among the deck's cards of the same rank.
+fn rotation_tables(deck: &[SCard]) -> [[Option<SCard>; 64]; 4] {
+    let mut sorted = deck.to_vec();
+    sorted.sort();
+    let mut tables = [[None; 64]; 4];
+    // Group by raw value rather than rank so that jokers rotate among
+    // themselves.
+    let mut start = 0;
+    while start < sorted.len() {
+        let group = sorted[start].raw() >> 2;
+        let end = sorted[start..]
+            .iter()
+            .position(|c| c.raw() >> 2 != group)
+            .map_or(sorted.len(), |i| start + i);
+        let rank = &sorted[start..end];
+        for (k, table) in tables.iter_mut().enumerate() {
+            for (j, c) in rank.iter().enumerate() {
+                table[c.raw() as usize] = Some(rank[(j + k) % rank.len()]);
+            }
+        }
+        start = end;
+    }
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::{Rank, Suit};
+
+    use super::*;
+    use crate::prediction::{category::HandCategory, model::PartialHand};
+
+    fn params(deck: &[SCard]) -> SimParams<'_> {
+        SimParams {
+            player: PartialHand {
+                drawn: deck[..2].into(),
+                undrawn: 3,
+            },
+            sample_deck: &deck[2..],
+            exchanges: Default::default(),
+            seed: Some(3),
+            deck: Default::default(),
+            wild: Default::default(),
+        }
+    }
+
+    #[test]
+    fn strategies_agree_with_exhaustive_mean() {
+        let deck = SCard::deck().collect::<Vec<_>>();
+        let params = params(&deck);
+        let exact = params.summarize(u64::MAX, 10).mean;
+
+        for strategy in [
+            Strategy::Uniform,
+            Strategy::Stratified,
+            Strategy::Antithetic,
+            Strategy::SuitPermuted,
+        ] {
+            let summary = params.summarize_with(&strategy, 20000, 10);
+            assert!(summary.samples >= 20000);
+            assert!(
+                (summary.mean - exact).abs() < 0.01,
+                "{strategy:?}: {} vs {exact}",
+                summary.mean
+            );
+        }
+    }
+
+    #[test]
+    fn importance_sampling_only_draws_from_range() {
+        let deck = SCard::deck().collect::<Vec<_>>();
+        let params = params(&deck);
+        let range: Range = "AA".parse().unwrap();
+
+        let scores: Vec<_> = params.sample_with(&Strategy::Importance(range), 1000);
+
+        assert_eq!(scores.len(), 1000);
+        assert!(scores.iter().all(|(_, w)| *w == 1.0));
+        // Every sample deals a pair of aces.
+        assert!(scores
+            .iter()
+            .all(|(s, _)| HandCategory::from_score(*s) >= HandCategory::Pair));
+    }
+
+    #[test]
+    fn tables_are_bijections() {
+        let mut deck = SCard::deck().collect::<Vec<_>>();
+        deck.retain(|c| c.rank() != Rank::Ace || c.suit() == Suit::Spades);
+
+        let mirror = mirror_table(&deck);
+        let mut mirrored = deck
+            .iter()
+            .map(|c| mirror[c.raw() as usize].unwrap())
+            .collect::<Vec<_>>();
+        mirrored.sort();
+        let mut sorted = deck.clone();
+        sorted.sort();
+        assert_eq!(mirrored, sorted);
+
+        for table in rotation_tables(&deck) {
+            let mut rotated = deck
+                .iter()
+                .map(|c| table[c.raw() as usize].unwrap())
+                .collect::<Vec<_>>();
+            rotated.sort();
+            assert_eq!(rotated, sorted);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use poker_assistant_lookup::N_HANDS;
+use serde::{Deserialize, Serialize};
+use strum::EnumCount;
+
+use super::{accumulator::ScoreAccumulator, category::HandCategory, deck::relative_score};
+
+/// Number of standard errors either side of an estimate covered by a 95%
+/// confidence interval.
+pub const Z_95: f64 = 1.96;
+
+/// Summary of a distribution of absolute hand scores.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScoreSummary {
+    /// Number of scores summarized.
+    pub samples: usize,
+
+    /// Whether the scores are every possible outcome rather than a random
+    /// sample of them.
+    #[serde(default)]
+    pub exhaustive: bool,
+
+    /// Mean of the scores, as a quantile.
+    pub mean: f32,
+
+    /// Standard deviation of the scores, as a quantile.
+    pub stddev: f32,
+
+    /// Standard error of the mean. Zero if the summary is exhaustive.
+    #[serde(default)]
+    pub mean_stderr: f32,
+
+    /// Median of the scores, as a quantile.
+    pub p50: f32,
+
+    /// 95% confidence interval of the median.
+    #[serde(default)]
+    pub p50_interval: (f32, f32),
+
+    /// Every percentile of the scores from 0 to 100, as quantiles.
+    pub percentiles: Vec<f32>,
+
+    /// Number of scores falling into each evenly-sized quantile bin.
+    pub histogram: Vec<usize>,
+
+    /// Probability of ending up in each hand category, indexed by
+    /// [`HandCategory::index`].
+    pub categories: [f64; HandCategory::COUNT],
+}
+
+impl ScoreSummary {
+    /// Summarize a random sample of scores. Panics if `scores` is empty.
+    pub fn from_scores(scores: &[u32], n_bins: usize) -> Self {
+        scores
+            .iter()
+            .copied()
+            .collect::<ScoreAccumulator>()
+            .summary(n_bins)
+    }
+
+    /// Summarize a sample of weighted scores, such as from importance
+    /// sampling. See [`ScoreAccumulator::summary`]. Panics if `scores` is
+    /// empty or the weights sum to zero.
+    pub fn from_weighted_scores(scores: &[(u32, f64)], n_bins: usize) -> Self {
+        scores
+            .iter()
+            .copied()
+            .collect::<ScoreAccumulator>()
+            .summary(n_bins)
+    }
+
+    /// Summarize every possible score. Unlike a streamed summary, the median,
+    /// percentiles and histogram are exact. Panics if `scores` is empty.
+    pub fn from_every_score(scores: &mut [u32], n_bins: usize) -> Self {
+        let streamed = scores
+            .iter()
+            .copied()
+            .collect::<ScoreAccumulator>()
+            .summary(n_bins);
+
+        scores.sort_unstable();
+        let at = |i: usize| score_quantile(scores[i.min(scores.len() - 1)]);
+        Self {
+            p50: at(scores.len() / 2),
+            percentiles: (0..=100).map(|i| at(i * scores.len() / 100)).collect(),
+            histogram: collect_histogram(n_bins, scores.iter().map(|s| score_quantile(*s))),
+            ..streamed
+        }
+        .into_exhaustive()
+    }
+
+    /// Mark the summary as covering every possible outcome, so its statistics
+    /// are exact.
+    pub fn into_exhaustive(self) -> Self {
+        Self {
+            exhaustive: true,
+            mean_stderr: 0.0,
+            p50_interval: (self.p50, self.p50),
+            ..self
+        }
+    }
+
+    /// Half-width of the confidence interval of the mean at `z` standard
+    /// errors.
+    pub fn mean_margin(&self, z: f64) -> f32 {
+        z as f32 * self.mean_stderr
+    }
+}
+
+/// Where an absolute score lies among all 5-card hands of its deck, from 0
+/// to 1.
+#[inline]
+pub fn score_quantile(score: u32) -> f32 {
+    relative_score(score) as f32 / N_HANDS as f32
+}
+
+pub fn collect_histogram(n_bins: usize, values: impl IntoIterator<Item = f32>) -> Vec<usize> {
+    let mut bins = vec![0; n_bins];
+
+    for v in values {
+        let bin = (v * n_bins as f32) as usize;
+        bins[bin] += 1;
+    }
+
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_works() {
+        let n = N_HANDS as u32;
+        let scores = [n / 2, 0, n / 4, 3 * n / 4];
+
+        let summary = ScoreSummary::from_scores(&scores, 4);
+
+        assert_eq!(summary.samples, 4);
+        assert!((summary.mean - 0.375).abs() < 1e-4);
+        assert!((summary.p50 - 0.5).abs() < 1e-4);
+        assert_eq!(summary.percentiles.len(), 101);
+        assert_eq!(summary.percentiles[0], 0.0);
+        assert_eq!(summary.percentiles[50], summary.p50);
+        assert!((summary.percentiles[100] - 0.75).abs() < 1e-4);
+        assert_eq!(summary.histogram, vec![1, 1, 1, 1]);
+        assert_eq!(summary.categories[HandCategory::HighCard.index()], 0.75);
+        assert_eq!(summary.categories[HandCategory::Pair.index()], 0.25);
+    }
+
+    #[test]
+    fn intervals_shrink_with_samples() {
+        let n = N_HANDS as u32;
+        let few = (0..100).map(|i| i * (n / 100)).collect::<Vec<_>>();
+        let many = (0..10000).map(|i| i * (n / 10000)).collect::<Vec<_>>();
+
+        let few = ScoreSummary::from_scores(&few, 10);
+        let many = ScoreSummary::from_scores(&many, 10);
+
+        assert!((few.mean_stderr - few.stddev / 10.0).abs() < 1e-6);
+        assert!(many.mean_stderr < few.mean_stderr);
+        assert!(few.p50_interval.0 < few.p50 && few.p50 < few.p50_interval.1);
+        assert!(
+            many.p50_interval.1 - many.p50_interval.0 < few.p50_interval.1 - few.p50_interval.0
+        );
+
+        let exact = few.into_exhaustive();
+        assert_eq!(exact.mean_margin(Z_95), 0.0);
+        assert_eq!(exact.p50_interval, (exact.p50, exact.p50));
+    }
+
+    #[test]
+    fn every_score_gives_exact_order_statistics() {
+        // All of these fall in the lowest bucket of a streamed summary.
+        let mut scores = (1..=101).rev().collect::<Vec<_>>();
+
+        let streamed = ScoreSummary::from_scores(&scores, 10);
+        let exact = ScoreSummary::from_every_score(&mut scores, 10);
+
+        assert_eq!(streamed.p50, 0.0);
+        assert!(exact.exhaustive);
+        assert_eq!(exact.p50, score_quantile(51));
+        assert_eq!(exact.percentiles[0], score_quantile(1));
+        assert_eq!(exact.percentiles[100], score_quantile(101));
+        assert_eq!(exact.mean, streamed.mean);
+    }
+
+    #[test]
+    fn weighted_summary_matches_repeated_scores() {
+        let n = N_HANDS as u32;
+        let weighted = [(n / 2, 1.0), (0, 2.0), (3 * n / 4, 1.0)];
+        let repeated = [n / 2, 0, 0, 3 * n / 4];
+
+        let weighted = ScoreSummary::from_weighted_scores(&weighted, 4);
+        let repeated = ScoreSummary::from_scores(&repeated, 4);
+
+        assert_eq!(weighted.samples, 3);
+        assert!((weighted.mean - repeated.mean).abs() < 1e-6);
+        assert!((weighted.stddev - repeated.stddev).abs() < 1e-6);
+        assert_eq!(weighted.percentiles[25], 0.0);
+        assert!((weighted.percentiles[100] - 0.75).abs() < 1e-4);
+        assert_eq!(weighted.categories, repeated.categories);
+        assert_eq!(weighted.histogram, vec![2, 0, 1, 1]);
+    }
+}
@@ -0,0 +1,464 @@
+//! Hand strength and potential against a single opponent, as described by
+//! Billings et al. in "The challenge of poker".
+
+use std::cmp::Ordering;
+
+use compact_poker::SCard;
+use rand::{seq::SliceRandom, Rng};
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    combinations::{combinations, n_combinations, par_combinations},
+    model::HandVec,
+    montecarlo::{score_superhand, with_sample_rng},
+    range::Range,
+    sampling::draw_excluding,
+};
+
+pub struct StrengthParams<'a> {
+    /// Our hole cards.
+    pub hole: HandVec,
+
+    /// Community cards dealt so far. Together with the hole cards there must
+    /// be at least 5.
+    pub board: HandVec,
+
+    /// Number of community cards still to come.
+    pub board_undrawn: u8,
+
+    /// Deck the opponent's hole cards and the rest of the board are drawn
+    /// from.
+    pub sample_deck: &'a [SCard],
+
+    /// Weights of the opponent's possible hole cards. If not provided, every
+    /// pair is equally likely.
+    pub opponent: Option<&'a Range>,
+
+    /// Seed for reproducible results. See [`with_sample_rng`].
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct HandStrength {
+    /// Probability that our hand is currently the best, counting ties as
+    /// half.
+    pub hs: f64,
+
+    /// Probability of ending up ahead when currently behind, counting ties as
+    /// half.
+    pub ppot: f64,
+
+    /// Probability of ending up behind when currently ahead, counting ties as
+    /// half.
+    pub npot: f64,
+
+    /// Effective hand strength: the probability of being ahead now and
+    /// staying ahead, or being behind now and pulling ahead.
+    pub ehs: f64,
+
+    /// Whether every outcome was enumerated rather than sampled.
+    pub exhaustive: bool,
+}
+
+/// Distribution of our equity against the opponent over runouts of the rest
+/// of the board.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EquityDistribution {
+    /// Number of runouts the distribution is made of.
+    pub runouts: usize,
+
+    /// Whether every runout was enumerated rather than sampled.
+    pub exhaustive: bool,
+
+    /// Mean equity, weighting each runout by how likely it is given the
+    /// opponent's range.
+    pub mean: f64,
+
+    /// Number of runouts with an equity in each evenly-sized bin from 0 to 1,
+    /// weighted as for [`EquityDistribution::mean`] and scaled to about the
+    /// number of runouts.
+    pub histogram: Vec<usize>,
+}
+
+/// Weighted equities of runouts, binned.
+#[derive(Clone)]
+struct EquityBins {
+    bins: Vec<f64>,
+    runouts: usize,
+    weight: f64,
+    equity: f64,
+}
+
+impl EquityBins {
+    fn new(n_bins: usize) -> Self {
+        Self {
+            bins: vec![0.0; n_bins],
+            runouts: 0,
+            weight: 0.0,
+            equity: 0.0,
+        }
+    }
+
+    fn add(mut self, equity: f64, weight: f64) -> Self {
+        let n_bins = self.bins.len();
+        self.bins[((equity * n_bins as f64) as usize).min(n_bins - 1)] += weight;
+        self.runouts += 1;
+        self.weight += weight;
+        self.equity += equity * weight;
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (a, b) in self.bins.iter_mut().zip(&other.bins) {
+            *a += b;
+        }
+        self.runouts += other.runouts;
+        self.weight += other.weight;
+        self.equity += other.equity;
+        self
+    }
+}
+
+const AHEAD: usize = 0;
+const TIED: usize = 1;
+const BEHIND: usize = 2;
+
+fn outcome(ours: u32, theirs: u32) -> usize {
+    match ours.cmp(&theirs) {
+        Ordering::Greater => AHEAD,
+        Ordering::Equal => TIED,
+        Ordering::Less => BEHIND,
+    }
+}
+
+/// Weight of each pair of outcomes, now and at the showdown.
+#[derive(Clone, Copy, Default)]
+struct Transitions([[f64; 3]; 3]);
+
+impl Transitions {
+    fn add(mut self, now: usize, later: usize, weight: f64) -> Self {
+        self.0[now][later] += weight;
+        self
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        for (a, b) in self.0.iter_mut().flatten().zip(other.0.iter().flatten()) {
+            *a += b;
+        }
+        self
+    }
+
+    /// Positive and negative potential.
+    fn potentials(&self) -> (f64, f64) {
+        let hp = &self.0;
+        let total = |now: usize| hp[now].iter().sum::<f64>();
+        let ratio = |num: f64, den: f64| if den > 0.0 { num / den } else { 0.0 };
+
+        let ppot = ratio(
+            hp[BEHIND][AHEAD] + hp[BEHIND][TIED] / 2.0 + hp[TIED][AHEAD] / 2.0,
+            total(BEHIND) + total(TIED) / 2.0,
+        );
+        let npot = ratio(
+            hp[AHEAD][BEHIND] + hp[TIED][BEHIND] / 2.0 + hp[AHEAD][TIED] / 2.0,
+            total(AHEAD) + total(TIED) / 2.0,
+        );
+        (ppot, npot)
+    }
+}
+
+impl StrengthParams<'_> {
+    fn score(&self, hole: &[SCard], rest: &[SCard]) -> u32 {
+        let mut cards = HandVec::from(hole);
+        cards.extend(self.board.iter().chain(rest).copied());
+        score_superhand(&cards).1
+    }
+
+    /// Opponent hole cards with any weight, as indices into the deck, with
+    /// their weights and scores on the current board.
+    fn opponent_hands(&self) -> Vec<(usize, usize, f64, u32)> {
+        let deck = self.sample_deck;
+        (0..deck.len())
+            .flat_map(|a| (a + 1..deck.len()).map(move |b| (a, b)))
+            .filter_map(|(a, b)| {
+                let weight = self.opponent.map_or(1.0, |r| r.weight(deck[a], deck[b]));
+                (weight > 0.0).then(|| (a, b, weight, self.score(&[deck[a], deck[b]], &[])))
+            })
+            .collect()
+    }
+
+    /// Probability that our hand is currently the best, counting ties as
+    /// half. Panics if the opponent's range has no hand in the deck.
+    pub fn hand_strength(&self) -> f64 {
+        hand_strength(self.score(&self.hole, &[]), &self.opponent_hands())
+    }
+
+    /// Hand strength and potential over `n` samples of the opponent's hand
+    /// and the rest of the board, or every possibility if there are fewer
+    /// than `n` of them.
+    ///
+    /// Panics if there are fewer than 5 hole and community cards, or the
+    /// opponent's range has no hand in the deck.
+    pub fn evaluate(&self, n: u64) -> HandStrength {
+        let ours = self.score(&self.hole, &[]);
+        let opponents = self.opponent_hands();
+        let hs = hand_strength(ours, &opponents);
+
+        let deck = self.sample_deck;
+        let k = self.board_undrawn as usize;
+        let n_possibilities = opponents.len() as u64 * n_combinations(deck.len() - 2, k);
+        let exhaustive = n >= n_possibilities;
+
+        let later = |(a, b, weight, theirs): (usize, usize, f64, u32), rest: &[SCard]| {
+            let now = outcome(ours, theirs);
+            let later = outcome(
+                self.score(&self.hole, rest),
+                self.score(&[deck[a], deck[b]], rest),
+            );
+            (now, later, weight)
+        };
+
+        let transitions = match exhaustive {
+            true => opponents
+                .par_iter()
+                .map(|hand| {
+                    let remaining = deck
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != hand.0 && *i != hand.1)
+                        .map(|(_, c)| *c)
+                        .collect::<Vec<_>>();
+                    combinations(&remaining, k)
+                        .map(|rest| later(*hand, &rest))
+                        .fold(Transitions::default(), |t, (now, later, w)| {
+                            t.add(now, later, w)
+                        })
+                })
+                .reduce(Transitions::default, Transitions::merge),
+            false => (0..n)
+                .into_par_iter()
+                .map(|i| {
+                    let (hand, rest) = with_sample_rng(self.seed, i, |rng| {
+                        let hand = opponents[rng.gen_range(0..opponents.len())];
+                        (hand, draw_excluding(rng, deck, &[hand.0, hand.1], k))
+                    });
+                    later(hand, &rest)
+                })
+                .fold(Transitions::default, |t, (now, later, w)| {
+                    t.add(now, later, w)
+                })
+                .reduce(Transitions::default, Transitions::merge),
+        };
+
+        let (ppot, npot) = transitions.potentials();
+        HandStrength {
+            hs,
+            ppot,
+            npot,
+            ehs: hs * (1.0 - npot) + (1.0 - hs) * ppot,
+            exhaustive,
+        }
+    }
+
+    /// Our equity against the opponent, once the rest of the board is
+    /// dealt, and the total weight of the opponent's hands. `None` if the
+    /// runout leaves the opponent no hands.
+    fn runout_equity(
+        &self,
+        opponents: &[(usize, usize, f64, u32)],
+        runout: &[SCard],
+    ) -> Option<(f64, f64)> {
+        let deck = self.sample_deck;
+        let ours = self.score(&self.hole, runout);
+        let (mut share, mut total) = (0.0, 0.0);
+        for (a, b, weight, _) in opponents {
+            let hand = [deck[*a], deck[*b]];
+            if hand.iter().any(|c| runout.contains(c)) {
+                continue;
+            }
+            share += weight
+                * match outcome(ours, self.score(&hand, runout)) {
+                    AHEAD => 1.0,
+                    TIED => 0.5,
+                    _ => 0.0,
+                };
+            total += weight;
+        }
+        (total > 0.0).then(|| (share / total, total))
+    }
+
+    /// Distribution of our equity over `n` runouts of the rest of the board,
+    /// or every runout if there are fewer than `n`, with `n_bins` bins.
+    ///
+    /// Each runout is weighted by the total weight of the opponent's hands
+    /// it leaves possible, which makes it as likely as it is given the
+    /// opponent's range. Panics if no runout leaves the opponent a hand.
+    pub fn equity_distribution(&self, n: u64, n_bins: usize) -> EquityDistribution {
+        let deck = self.sample_deck;
+        let k = self.board_undrawn as usize;
+        let opponents = self.opponent_hands();
+        let exhaustive = n >= n_combinations(deck.len(), k);
+
+        let bin = |bins: EquityBins, runout: HandVec| match self.runout_equity(&opponents, &runout)
+        {
+            Some((equity, weight)) => bins.add(equity, weight),
+            None => bins,
+        };
+        let bins = match exhaustive {
+            true => par_combinations(deck, k)
+                .fold(|| EquityBins::new(n_bins), bin)
+                .reduce(|| EquityBins::new(n_bins), EquityBins::merge),
+            false => (0..n)
+                .into_par_iter()
+                .map(|i| {
+                    with_sample_rng(self.seed, i, |rng| {
+                        deck.choose_multiple(rng, k).copied().collect::<HandVec>()
+                    })
+                })
+                .fold(|| EquityBins::new(n_bins), bin)
+                .reduce(|| EquityBins::new(n_bins), EquityBins::merge),
+        };
+        assert!(bins.weight > 0.0, "opponent range has no hand in the deck");
+
+        EquityDistribution {
+            runouts: bins.runouts,
+            exhaustive,
+            mean: bins.equity / bins.weight,
+            histogram: bins
+                .bins
+                .iter()
+                .map(|b| (b * bins.runouts as f64 / bins.weight).round() as usize)
+                .collect(),
+        }
+    }
+}
+
+fn hand_strength(ours: u32, opponents: &[(usize, usize, f64, u32)]) -> f64 {
+    assert!(
+        !opponents.is_empty(),
+        "opponent range has no hand in the deck"
+    );
+    let mut counts = [0.0; 3];
+    for (_, _, weight, theirs) in opponents {
+        counts[outcome(ours, *theirs)] += weight;
+    }
+    (counts[AHEAD] + counts[TIED] / 2.0) / counts.iter().sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::Card;
+
+    use super::*;
+
+    fn cards(s: &str) -> HandVec {
+        s.split_whitespace()
+            .map(|c| c.parse::<Card>().unwrap().into())
+            .collect()
+    }
+
+    fn deck_without(known: &[SCard]) -> Vec<SCard> {
+        SCard::deck().filter(|c| !known.contains(c)).collect()
+    }
+
+    #[test]
+    fn nuts_on_the_river() {
+        let hole = cards("As Ks");
+        let board = cards("Qs Js Ts 2d 3c");
+        let deck = deck_without(&[hole.as_slice(), &board].concat());
+        let params = StrengthParams {
+            hole,
+            board,
+            board_undrawn: 0,
+            sample_deck: &deck,
+            opponent: None,
+            seed: None,
+        };
+
+        let strength = params.evaluate(1000);
+
+        assert_eq!(strength.hs, 1.0);
+        assert_eq!(strength.ehs, 1.0);
+        assert_eq!((strength.ppot, strength.npot), (0.0, 0.0));
+        assert!(strength.exhaustive);
+    }
+
+    #[test]
+    fn drawing_hand_has_potential() {
+        // A flush draw with no pair is usually behind, but often gets there.
+        let hole = cards("8h 9h");
+        let board = cards("2h 5h Kc 3d");
+        let deck = deck_without(&[hole.as_slice(), &board].concat());
+        let params = StrengthParams {
+            hole,
+            board,
+            board_undrawn: 1,
+            sample_deck: &deck,
+            opponent: None,
+            seed: None,
+        };
+
+        let strength = params.evaluate(u64::MAX);
+
+        assert!(strength.exhaustive);
+        assert!(strength.hs < 0.5, "{strength:?}");
+        assert!(strength.ppot > 0.15, "{strength:?}");
+        assert!(strength.ehs > strength.hs, "{strength:?}");
+    }
+
+    #[test]
+    fn ranges_change_strength() {
+        let hole = cards("Qd Qc");
+        let board = cards("7s 8d 2c");
+        let deck = deck_without(&[hole.as_slice(), &board].concat());
+        let strong: Range = "AA, KK".parse().unwrap();
+        let params = |opponent| StrengthParams {
+            hole: hole.clone(),
+            board: board.clone(),
+            board_undrawn: 2,
+            sample_deck: &deck,
+            opponent,
+            seed: Some(1),
+        };
+
+        let uniform = params(None).evaluate(20000);
+        let against_strong = params(Some(&strong)).evaluate(20000);
+
+        assert!(!uniform.exhaustive);
+        assert!(uniform.hs > 0.8, "{uniform:?}");
+        assert_eq!(against_strong.hs, 0.0);
+        assert_eq!(against_strong.npot, 0.0);
+        assert!(against_strong.ppot > 0.0 && against_strong.ppot < 0.2);
+        assert_eq!(params(None).evaluate(20000), uniform);
+    }
+
+    #[test]
+    fn equity_distribution_is_centred_on_equity() {
+        let hole = cards("Ah Kh");
+        let board = cards("Qh 7c 2h 9s");
+        let deck = deck_without(&[hole.as_slice(), &board].concat());
+        let range: Range = "AA, KK, QQ, 77, 22, AQ, KQ".parse().unwrap();
+        let params = StrengthParams {
+            hole,
+            board,
+            board_undrawn: 1,
+            sample_deck: &deck,
+            opponent: Some(&range),
+            seed: None,
+        };
+
+        let distribution = params.equity_distribution(1000, 10);
+        let equity = params.evaluate(u64::MAX);
+
+        assert!(distribution.exhaustive);
+        assert_eq!(distribution.runouts, 46);
+        // A flush draw against a strong range either gets there or doesn't.
+        let h = &distribution.histogram;
+        assert!(h[0] + h[9] > h[1..9].iter().sum::<usize>() * 2, "{h:?}");
+        // Averaging over runouts gives the equity on the turn.
+        assert!(
+            (distribution.mean - equity.ehs).abs() < 1e-9,
+            "{distribution:?} {equity:?}"
+        );
+    }
+}
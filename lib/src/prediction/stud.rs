@@ -0,0 +1,435 @@
+//! Stud rules and live cards.
+//!
+//! Seats are numbered with the player first, at seat 0, and each opponent
+//! after them in [`Game::opponents`] order, which is also the order of play.
+//!
+//! Live cards are those a hand wants which have not been seen in its own
+//! hand, other players' upcards or folded hands.
+
+use std::cmp::Reverse;
+
+use compact_poker::SCard;
+use poker::Suit;
+use serde::{Deserialize, Serialize};
+
+use super::{board::RANK_CHARS, model::Game};
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum StudError {
+    #[error("Seat {seat} shows {shown} upcards, but seat 0 shows {expected}")]
+    UnevenUpcards {
+        seat: usize,
+        shown: usize,
+        expected: usize,
+    },
+
+    #[error("No upcards have been dealt yet")]
+    NoUpcards,
+
+    #[error("Every player needs a card still to come to share the last card")]
+    NoLastCard,
+
+    #[error("Game needs {0} unknown cards, but only {1} are left in the deck")]
+    DeckExhausted(usize, usize),
+}
+
+/// Whether the best hand is the highest, or the lowest as in razz.
+///
+/// This decides who brings in and who acts first, so stud eight-or-better
+/// plays as [`Ranking::High`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Ranking {
+    /// The lowest upcard brings in, aces playing high, and the best hand
+    /// showing acts first.
+    #[default]
+    High,
+
+    /// The highest upcard brings in, aces playing low, and the lowest hand
+    /// showing acts first.
+    Low,
+}
+
+impl Ranking {
+    /// Value of a rank, from 0 for the lowest.
+    fn rank_value(&self, c: SCard) -> usize {
+        match self {
+            Ranking::High => c.rank() as usize,
+            Ranking::Low => (c.rank() as usize + 1) % 13,
+        }
+    }
+
+    /// Seat which must bring in on third street, judged by each seat's first
+    /// upcard. Suits break ties, from clubs, the lowest, to diamonds, hearts
+    /// and spades.
+    ///
+    /// Seats may show different numbers of upcards, as players who folded
+    /// on a later street still count.
+    pub fn bring_in(&self, game: &Game) -> Result<usize, StudError> {
+        let upcards = seat_upcards(game).collect::<Vec<_>>();
+        if upcards.iter().any(|u| u.is_empty()) {
+            return Err(StudError::NoUpcards);
+        }
+        let key = |seat: &usize| {
+            let c = upcards[*seat][0];
+            (self.rank_value(c), suit_value(c.suit()))
+        };
+        let seats = 0..upcards.len();
+        Ok(match self {
+            Ranking::High => seats.min_by_key(key),
+            Ranking::Low => seats.max_by_key(key),
+        }
+        .unwrap())
+    }
+
+    /// Seat which acts first after third street: the best hand showing, or
+    /// the lowest in razz. Only pairs, trips and quads count, not straights
+    /// or flushes, and the earliest seat acts first on ties.
+    pub fn first_to_act(&self, game: &Game) -> Result<usize, StudError> {
+        let upcards = upcards(game)?;
+        let showing = upcards.iter().map(|u| self.showing(u)).collect::<Vec<_>>();
+        let better = |a: &(Vec<u8>, Vec<usize>), b: &(Vec<u8>, Vec<usize>)| match self {
+            Ranking::High => a > b,
+            Ranking::Low => a < b,
+        };
+        let mut first = 0;
+        for (seat, s) in showing.iter().enumerate().skip(1) {
+            if better(s, &showing[first]) {
+                first = seat;
+            }
+        }
+        Ok(first)
+    }
+
+    /// Strength of the cards showing, which sorts from weakest to strongest
+    /// as a high hand: the sizes of groups of equal rank, largest first, then
+    /// the rank values of each group.
+    fn showing(&self, cards: &[SCard]) -> (Vec<u8>, Vec<usize>) {
+        let mut counts = [0u8; 13];
+        for c in cards {
+            counts[self.rank_value(*c)] += 1;
+        }
+        let mut groups = (0..13)
+            .rev()
+            .filter(|r| counts[*r] > 0)
+            .map(|r| (counts[r], r))
+            .collect::<Vec<_>>();
+        groups.sort_by_key(|(n, _)| Reverse(*n));
+        groups.into_iter().unzip()
+    }
+}
+
+/// Suits from lowest to highest, as used to break ties for the bring-in.
+fn suit_value(suit: Suit) -> u8 {
+    match suit {
+        Suit::Clubs => 0,
+        Suit::Diamonds => 1,
+        Suit::Hearts => 2,
+        Suit::Spades => 3,
+    }
+}
+
+/// Upcards of each seat.
+fn seat_upcards(game: &Game) -> impl Iterator<Item = &[SCard]> {
+    std::iter::once(game.player.stud.drawn.as_slice())
+        .chain(game.opponents.iter().map(|o| o.stud.drawn.as_slice()))
+}
+
+/// Upcards of each seat, checking that every seat shows as many.
+fn upcards(game: &Game) -> Result<Vec<&[SCard]>, StudError> {
+    let upcards = seat_upcards(game).collect::<Vec<_>>();
+    let expected = upcards[0].len();
+    if expected == 0 {
+        return Err(StudError::NoUpcards);
+    }
+    if let Some((seat, u)) = upcards
+        .iter()
+        .enumerate()
+        .find(|(_, u)| u.len() != expected)
+    {
+        return Err(StudError::UnevenUpcards {
+            seat,
+            shown: u.len(),
+            expected,
+        });
+    }
+    Ok(upcards)
+}
+
+/// Number of cards left to deal: the sample deck, less mucked cards. Burn
+/// cards are not counted.
+pub fn cards_left(game: &Game) -> usize {
+    game.sample_deck()
+        .len()
+        .saturating_sub(game.mucked as usize)
+}
+
+/// Number of unknown cards the deck must still provide: every card to come,
+/// and opponents' hole cards.
+pub fn cards_needed(game: &Game) -> usize {
+    let player = &game.player;
+    let opponents = game
+        .opponents
+        .iter()
+        .map(|o| (o.hole.total_cards() + o.stud.undrawn) as usize)
+        .sum::<usize>();
+    (player.hole.undrawn + player.stud.undrawn + game.community.undrawn) as usize + opponents
+}
+
+/// Deal the last card as one community card shared by every player if the
+/// deck cannot give each player their own, as in seven-card stud with eight
+/// players. Returns whether the card is shared.
+pub fn share_last_card(game: &mut Game) -> Result<bool, StudError> {
+    let left = cards_left(game);
+    let needed = cards_needed(game);
+    if needed <= left {
+        return Ok(false);
+    }
+
+    if game.player.hole.undrawn == 0 || game.opponents.iter().any(|o| o.hole.undrawn == 0) {
+        return Err(StudError::NoLastCard);
+    }
+    game.player.hole.undrawn -= 1;
+    for o in game.opponents.iter_mut() {
+        o.hole.undrawn -= 1;
+    }
+    game.community.undrawn += 1;
+
+    let needed = cards_needed(game);
+    if needed > left {
+        return Err(StudError::DeckExhausted(needed, left));
+    }
+    Ok(true)
+}
+
+/// Cards of one rank or suit that a hand holds and wants more of.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Draw {
+    /// Rank or suit character, e.g. `'K'` or `'h'`.
+    pub to: char,
+
+    /// Cards of it in the hand.
+    pub held: u8,
+
+    /// Cards of it still in the deck.
+    pub live: u8,
+}
+
+/// Cards in a stud hand's sample deck that would improve its draws.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LiveCards {
+    /// Each rank in the hand, most held first, then highest first.
+    pub ranks: Vec<Draw>,
+
+    /// The suit held most.
+    pub flush: Option<Draw>,
+
+    /// The five consecutive ranks with most held, highest first on ties.
+    /// `to` is the high card of the straight, `held` the ranks of it held
+    /// and `live` the cards of the missing ranks still in the deck.
+    pub straight: Option<Draw>,
+}
+
+impl LiveCards {
+    /// Live cards for `hand`, where anything not in `deck` is out of play.
+    pub fn new(hand: &[SCard], deck: &[SCard]) -> Self {
+        let mut held_ranks = [0u8; 13];
+        let mut held_suits = [0u8; 4];
+        for c in hand {
+            held_ranks[c.rank() as usize] += 1;
+            held_suits[c.suit() as usize] += 1;
+        }
+        let mut live_ranks = [0u8; 13];
+        let mut live_suits = [0u8; 4];
+        for c in deck {
+            live_ranks[c.rank() as usize] += 1;
+            live_suits[c.suit() as usize] += 1;
+        }
+
+        let mut ranks = (0..13)
+            .rev()
+            .filter(|r| held_ranks[*r] > 0)
+            .map(|r| Draw {
+                to: RANK_CHARS[r] as char,
+                held: held_ranks[r],
+                live: live_ranks[r],
+            })
+            .collect::<Vec<_>>();
+        ranks.sort_by_key(|d| Reverse(d.held));
+
+        let flush = hand
+            .iter()
+            .max_by_key(|c| held_suits[c.suit() as usize])
+            .map(|c| Draw {
+                to: c.suit().as_char(),
+                held: held_suits[c.suit() as usize],
+                live: live_suits[c.suit() as usize],
+            });
+
+        // Windows of ranks from the ace playing low to the ace playing high.
+        let straight = (4..=13)
+            .rev()
+            .map(|high| {
+                let window = (high - 4..=high).map(|i| (i + 12) % 13);
+                let (held, missing): (Vec<_>, Vec<_>) = window.partition(|r| held_ranks[*r] > 0);
+                Draw {
+                    to: RANK_CHARS[(high + 12) % 13] as char,
+                    held: held.len() as u8,
+                    live: missing.iter().map(|r| live_ranks[*r]).sum(),
+                }
+            })
+            .reduce(|best, d| if d.held > best.held { d } else { best })
+            .filter(|d| d.held > 0);
+
+        LiveCards {
+            ranks,
+            flush,
+            straight,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_repr::Deal;
+
+    /// A seven-card stud game where each seat shows the given upcards.
+    fn game(upcards: &[&str]) -> Game {
+        let deal = Deal {
+            hole: 3,
+            stud: 4,
+            ..Default::default()
+        };
+        let mut game = Game::from_deals(upcards.len() - 1, [deal]);
+        game.player.stud.add_cards(cards(upcards[0]));
+        for (o, up) in game.opponents.iter_mut().zip(&upcards[1..]) {
+            o.hole.add_cards(2);
+            o.stud.add_cards(cards(up));
+        }
+        game
+    }
+
+    fn cards(s: &str) -> Vec<SCard> {
+        s.split_whitespace()
+            .map(|c| c.parse::<poker::Card>().unwrap().into())
+            .collect()
+    }
+
+    #[test]
+    fn counts_live_cards() {
+        let hand = cards("7h 7c 8h 9h");
+        // Another player shows a seven and two hearts.
+        let seen = cards("7s Ah 2h Tc");
+        let deck = SCard::deck()
+            .filter(|c| !hand.contains(c) && !seen.contains(c))
+            .collect::<Vec<_>>();
+
+        let live = LiveCards::new(&hand, &deck);
+
+        assert_eq!(
+            live.ranks,
+            [
+                Draw {
+                    to: '7',
+                    held: 2,
+                    live: 1
+                },
+                Draw {
+                    to: '9',
+                    held: 1,
+                    live: 3
+                },
+                Draw {
+                    to: '8',
+                    held: 1,
+                    live: 3
+                },
+            ]
+        );
+        assert_eq!(
+            live.flush,
+            Some(Draw {
+                to: 'h',
+                held: 3,
+                live: 13 - 5
+            })
+        );
+        // The jack-high straight is the highest with three ranks held. It
+        // needs a ten, one of which is gone, and a jack.
+        assert_eq!(
+            live.straight,
+            Some(Draw {
+                to: 'J',
+                held: 3,
+                live: 3 + 4
+            })
+        );
+        assert_eq!(LiveCards::new(&[], &deck).flush, None);
+    }
+
+    #[test]
+    fn lowest_upcard_brings_in() {
+        let third_street = game(&["Kd", "2h", "Ac", "2c"]);
+
+        // The deuce of clubs is the lowest card.
+        assert_eq!(Ranking::High.bring_in(&third_street), Ok(3));
+        // In razz the king is the highest card, and aces play low.
+        assert_eq!(Ranking::Low.bring_in(&third_street), Ok(0));
+
+        assert_eq!(
+            Ranking::High.bring_in(&game(&["", ""])),
+            Err(StudError::NoUpcards)
+        );
+        assert_eq!(
+            Ranking::High.bring_in(&game(&["Kd", ""])),
+            Err(StudError::NoUpcards)
+        );
+        // Only the first upcard counts, for players who folded later too.
+        assert_eq!(Ranking::High.bring_in(&game(&["Kd 2c", "7h"])), Ok(1));
+        assert_eq!(
+            Ranking::High.first_to_act(&game(&["Kd", "2h 3h"])),
+            Err(StudError::UnevenUpcards {
+                seat: 1,
+                shown: 2,
+                expected: 1
+            })
+        );
+    }
+
+    #[test]
+    fn best_hand_showing_acts_first() {
+        let fourth_street = game(&["Ad Kd", "7h 7c", "2s 2d", "As Kc"]);
+
+        // Sevens beat deuces and ace-king showing.
+        assert_eq!(Ranking::High.first_to_act(&fourth_street), Ok(1));
+        // No pair is lowest for razz, and seat 0 shows the same as seat 3.
+        assert_eq!(Ranking::Low.first_to_act(&fourth_street), Ok(0));
+        // A low four showing beats king-ace in razz, and a four-flush does
+        // not count.
+        let razz = game(&["Ad Kd", "4h 3h", "2s 2d"]);
+        assert_eq!(Ranking::Low.first_to_act(&razz), Ok(1));
+        assert_eq!(Ranking::High.first_to_act(&razz), Ok(2));
+    }
+
+    #[test]
+    fn eight_players_share_the_last_card() {
+        let mut seven = game(&["2c", "3c", "4c", "5c", "6c", "7c", "8c"]);
+        assert_eq!(share_last_card(&mut seven), Ok(false));
+        assert_eq!(seven.community.undrawn, 0);
+
+        let mut eight = game(&["2c", "3c", "4c", "5c", "6c", "7c", "8c", "9c"]);
+        assert_eq!(cards_needed(&eight), 6 + 7 * 6);
+        assert_eq!(cards_left(&eight), 52 - 8);
+        assert_eq!(share_last_card(&mut eight), Ok(true));
+        assert_eq!(eight.community.undrawn, 1);
+        assert_eq!(eight.player.hole.undrawn, 2);
+        assert_eq!(cards_needed(&eight), 1 + 5 + 7 * 5);
+
+        // Once a player folds, their face-down cards are mucked, but there
+        // are enough cards left for everyone else.
+        let mut folded = game(&["2c", "3c", "4c", "5c", "6c", "7c", "8c", "9c"]);
+        folded.fold(6);
+        assert_eq!(cards_left(&folded), 52 - 8 - 2);
+        assert_eq!(share_last_card(&mut folded), Ok(false));
+    }
+}
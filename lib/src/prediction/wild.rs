@@ -0,0 +1,178 @@
+//! Scoring hands with wild cards, which stand for whichever card makes the
+//! best hand.
+//!
+//! A wild card may stand for any card of the deck not already in the hand.
+//! Five of a kind, which only wild cards can make, beats every other hand.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use compact_poker::{SCard, SHand, Wild};
+use poker::Rank;
+use poker_assistant_lookup::N_HANDS;
+
+use super::{combinations::combinations, deck::Deck, model::HandVec};
+
+/// Scores of five of a kind start here, above the scores of every deck.
+pub(super) const FIVE_OF_A_KIND: u32 = 2 * N_HANDS as u32;
+
+std::thread_local! {
+    /// Best substitution for each set of natural cards, as it does not depend
+    /// on which cards were wild.
+    static SUBSTITUTIONS: RefCell<HashMap<(Deck, u32), (SHand, u32)>> =
+        RefCell::new(HashMap::new());
+}
+
+/// The best 5-card hand within `hand`, and its score, where the cards `wild`
+/// are wild.
+pub fn score_wild(deck: Deck, wild: Wild, hand: &[SCard]) -> (SHand, u32) {
+    combinations(hand, 5)
+        .map(|h| score_five(deck, wild, &h))
+        .max_by_key(|(_h, s)| *s)
+        .unwrap()
+}
+
+fn score_five(deck: Deck, wild: Wild, hand: &[SCard]) -> (SHand, u32) {
+    let naturals = hand
+        .iter()
+        .copied()
+        .filter(|c| !wild.is_wild(*c))
+        .collect::<HandVec>();
+    let n_wild = hand.len() - naturals.len();
+    if n_wild == 0 {
+        let sh = SHand::from(hand);
+        return (sh, deck.score(sh));
+    }
+
+    let rank = naturals.first().map_or(Rank::Ace, |c| c.rank());
+    if naturals.iter().all(|c| c.rank() == rank) {
+        return (SHand::from(hand), FIVE_OF_A_KIND + rank as u32);
+    }
+
+    let key = naturals
+        .iter()
+        .fold(0, |key, c| key << 6 | (c.raw() as u32 + 1));
+    SUBSTITUTIONS.with_borrow_mut(|cache| {
+        *cache
+            .entry((deck, key))
+            .or_insert_with(|| best_substitution(deck, &naturals, n_wild))
+    })
+}
+
+/// Cards of `deck` worth substituting for wild cards in a hand of
+/// `naturals`: those of their suit, if they could make a flush, those of their
+/// ranks, and one card of each other rank for straights. Two wild cards of
+/// the same new rank are never better than two more of a rank already held.
+fn substitutes(deck: Deck, naturals: &[SCard]) -> Vec<SCard> {
+    let suit = naturals[0].suit();
+    let flush = naturals.iter().all(|c| c.suit() == suit);
+    let mut other_ranks = 0u16;
+    deck.cards()
+        .into_iter()
+        .filter(|c| !naturals.contains(c))
+        .filter(|c| {
+            if flush && c.suit() == suit || naturals.iter().any(|n| n.rank() == c.rank()) {
+                return true;
+            }
+            let bit = 1 << c.rank() as u16;
+            let first = other_ranks & bit == 0;
+            other_ranks |= bit;
+            first
+        })
+        .collect()
+}
+
+/// The best hand made by adding `n_wild` cards of `deck` to `naturals`.
+fn best_substitution(deck: Deck, naturals: &[SCard], n_wild: usize) -> (SHand, u32) {
+    combinations(&substitutes(deck, naturals), n_wild)
+        .map(|subs| {
+            let sh = naturals.iter().chain(&subs).copied().collect::<SHand>();
+            (sh, deck.score(sh))
+        })
+        .max_by_key(|(_h, s)| *s)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prediction::category::HandCategory;
+
+    fn hand(cards: &str) -> Vec<SCard> {
+        cards
+            .split_whitespace()
+            .map(|c| match c {
+                "Jk" => SCard::JOKERS[0],
+                c => c.parse::<poker::Card>().unwrap().into(),
+            })
+            .collect()
+    }
+
+    fn category(wild: Wild, cards: &str) -> HandCategory {
+        HandCategory::from_score(score_wild(Deck::Standard, wild, &hand(cards)).1)
+    }
+
+    #[test]
+    fn wild_cards_make_the_best_hand() {
+        let deuces = Wild::NONE.with_rank(Rank::Two);
+
+        assert_eq!(category(Wild::NONE, "As Ks 7d 2c 2h"), HandCategory::Pair);
+        assert_eq!(
+            category(deuces, "As Ks 7d 2c 2h"),
+            HandCategory::ThreeOfAKind
+        );
+        assert_eq!(
+            category(deuces, "9s Ts Js 2c 2h"),
+            HandCategory::StraightFlush
+        );
+        assert_eq!(
+            category(Wild::NONE, "Ah Kh Qh Jh Jk"),
+            HandCategory::StraightFlush
+        );
+        assert_eq!(
+            category(deuces, "7s 7d 7h 2c Jk"),
+            HandCategory::FiveOfAKind
+        );
+        assert_eq!(
+            category(deuces, "Ac Kd 9h 5s 3c 2h 2d"),
+            HandCategory::Straight
+        );
+    }
+
+    #[test]
+    fn substitutes_make_the_same_hands_as_every_card() {
+        for naturals in ["Ah Kd", "9s Js", "Kc Kd 4h", "6h 7h 9h", "Ac 2d 3h Qs"] {
+            let naturals = hand(naturals);
+            let n_wild = 5 - naturals.len();
+            let every_card = Deck::Standard
+                .cards()
+                .into_iter()
+                .filter(|c| !naturals.contains(c))
+                .collect::<Vec<_>>();
+            let best = combinations(&every_card, n_wild)
+                .map(|subs| {
+                    let sh = naturals.iter().chain(&subs).copied().collect::<SHand>();
+                    Deck::Standard.score(sh)
+                })
+                .max();
+
+            assert_eq!(
+                Some(best_substitution(Deck::Standard, &naturals, n_wild).1),
+                best
+            );
+        }
+    }
+
+    #[test]
+    fn higher_five_of_a_kind_wins() {
+        let deuces = Wild::NONE.with_rank(Rank::Two);
+        let sevens = score_wild(Deck::Standard, deuces, &hand("7s 7d 7h 2c Jk")).1;
+        let aces = score_wild(Deck::Standard, deuces, &hand("As Ad 2h 2c Jk")).1;
+        let royal = score_wild(Deck::Standard, Wild::NONE, &hand("As Ks Qs Js Ts")).1;
+
+        assert!(royal < sevens && sevens < aces);
+        assert_eq!(
+            score_wild(Deck::Short, Wild::NONE, &hand("Ah Kh Qh Jh Jk")).0,
+            hand("Ah Kh Qh Jh Th").iter().copied().collect::<SHand>()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Persistent log of played hands and the statistics derived from it.
+//!
+//! The log is stored as JSON lines, one [`HandRecord`] per line, so that it can
+//! be appended to after every hand and inspected with ordinary text tools.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+
+use crate::game_repr::Round;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HandRecord {
+    /// Seconds since the Unix epoch at which the hand was recorded.
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+
+    /// Round schedule of the game this hand was played in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rounds: Vec<Round>,
+
+    /// Amount we put into the pot.
+    pub stake: f64,
+
+    /// Net amount won. Negative if the hand was lost.
+    pub result: f64,
+
+    /// Our equity when the last decision was made, e.g. when all-in. Must be
+    /// between 0 and 1.
+    #[serde(default, deserialize_with = "deserialize_equity")]
+    pub equity: Option<f64>,
+
+    /// Total size of the pot the equity applies to.
+    #[serde(default)]
+    pub pot: Option<f64>,
+}
+
+impl HandRecord {
+    /// Winnings we were entitled to given our equity, or the actual result if
+    /// no equity was recorded.
+    pub fn luck_adjusted_result(&self) -> f64 {
+        match (self.equity, self.pot) {
+            (Some(equity), Some(pot)) => equity * pot - self.stake,
+            _ => self.result,
+        }
+    }
+}
+
+/// Checks that `equity` is a fraction of the pot.
+fn check_equity(equity: Option<f64>) -> Result<(), String> {
+    match equity {
+        Some(e) if !(0.0..=1.0).contains(&e) => Err(format!("Equity {e} is not between 0 and 1")),
+        _ => Ok(()),
+    }
+}
+
+fn deserialize_equity<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    let equity = Option::<f64>::deserialize(d)?;
+    check_equity(equity).map_err(D::Error::custom)?;
+    Ok(equity)
+}
+
+/// Append a single record to the log, creating it if it does not exist.
+///
+/// Fails without writing anything if the record's equity is out of range.
+pub fn append_record(path: impl AsRef<Path>, record: &HandRecord) -> std::io::Result<()> {
+    check_equity(record.equity)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(record)?;
+    writeln!(file, "{line}")
+}
+
+/// Read every record from the log. Blank lines are skipped.
+pub fn read_records(path: impl AsRef<Path>) -> std::io::Result<Vec<HandRecord>> {
+    let file = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+    for line in file.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SessionSummary {
+    /// Number of hands played.
+    pub hands: usize,
+
+    /// Sum of all results.
+    pub total: f64,
+
+    /// Sum of all luck-adjusted results.
+    pub luck_adjusted_total: f64,
+
+    /// Mean result per hand.
+    pub winrate: f64,
+
+    /// Mean luck-adjusted result per hand.
+    pub luck_adjusted_winrate: f64,
+
+    /// Sample variance of the per-hand result.
+    pub variance: f64,
+
+    /// Cumulative (actual, luck-adjusted) winnings after each hand.
+    pub running: Vec<(f64, f64)>,
+}
+
+impl SessionSummary {
+    pub fn from_records(records: &[HandRecord]) -> Self {
+        let hands = records.len();
+        if hands == 0 {
+            return Self::default();
+        }
+
+        let mut running = Vec::with_capacity(hands);
+        let (mut total, mut luck_adjusted_total) = (0.0, 0.0);
+        for r in records {
+            total += r.result;
+            luck_adjusted_total += r.luck_adjusted_result();
+            running.push((total, luck_adjusted_total));
+        }
+
+        let winrate = total / hands as f64;
+        let variance = match hands {
+            1 => 0.0,
+            n => {
+                records
+                    .iter()
+                    .map(|r| (r.result - winrate).powi(2))
+                    .sum::<f64>()
+                    / (n - 1) as f64
+            }
+        };
+
+        Self {
+            hands,
+            total,
+            luck_adjusted_total,
+            winrate,
+            luck_adjusted_winrate: luck_adjusted_total / hands as f64,
+            variance,
+            running,
+        }
+    }
+
+    pub fn stddev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_repr::holdem;
+
+    fn record(stake: f64, result: f64, equity: Option<f64>, pot: Option<f64>) -> HandRecord {
+        HandRecord {
+            timestamp: None,
+            rounds: vec![],
+            stake,
+            result,
+            equity,
+            pot,
+        }
+    }
+
+    #[test]
+    fn luck_adjusted_result_uses_equity() {
+        let r = record(50.0, -50.0, Some(0.8), Some(100.0));
+
+        assert_eq!(r.luck_adjusted_result(), 30.0);
+    }
+
+    #[test]
+    fn summary_works() {
+        let records = [
+            record(2.0, 4.0, None, None),
+            record(50.0, -50.0, Some(0.8), Some(100.0)),
+            record(2.0, -2.0, None, None),
+        ];
+
+        let summary = SessionSummary::from_records(&records);
+
+        assert_eq!(summary.hands, 3);
+        assert_eq!(summary.total, -48.0);
+        assert_eq!(summary.luck_adjusted_total, 32.0);
+        assert_eq!(summary.winrate, -16.0);
+        assert_eq!(summary.variance, 876.0);
+        assert_eq!(
+            summary.running,
+            vec![(4.0, 4.0), (-46.0, 34.0), (-48.0, 32.0)]
+        );
+    }
+
+    #[test]
+    fn record_roundtrips_through_json() {
+        let r = HandRecord {
+            timestamp: Some(1700000000),
+            rounds: holdem(),
+            ..record(2.0, 4.0, Some(0.5), Some(8.0))
+        };
+
+        let json = serde_json::to_string(&r).unwrap();
+
+        assert_eq!(serde_json::from_str::<HandRecord>(&json).unwrap(), r);
+    }
+
+    #[test]
+    fn rejects_equity_out_of_range() {
+        let json = r#"{"stake": 50.0, "result": -50.0, "equity": 1.5, "pot": 100.0}"#;
+        assert!(serde_json::from_str::<HandRecord>(json).is_err());
+
+        let path = std::env::temp_dir().join("poker-assistant-bad-equity.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let bad = record(50.0, -50.0, Some(-0.1), Some(100.0));
+        assert!(append_record(&path, &bad).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -6,18 +6,23 @@ use std::{
 
 use compact_poker::SHand;
 use itertools::Itertools;
-use poker::{Card, Evaluator};
+use poker::{Card, Eval, Evaluator, Rank};
 use rayon::slice::ParallelSliceMut;
 
 fn main() {
     eprintln!("generating all hands");
 
     write_ordered_hands().unwrap();
+    write_short_deck_hands().unwrap();
 }
 
 fn all_hands() -> Vec<Vec<Card>> {
-    let deck = Card::generate_deck().collect_vec();
-    deck.into_iter()
+    hands_from(Card::generate_deck())
+}
+
+fn hands_from(deck: impl Iterator<Item = Card>) -> Vec<Vec<Card>> {
+    deck.collect_vec()
+        .into_iter()
         .combinations(5)
         .map(|mut h| {
             h.sort();
@@ -51,10 +56,63 @@ fn write_ordered_hands() -> std::io::Result<()> {
     });
     eprintln!("writing hands in order to {}", path.to_string_lossy());
 
+    write_hands(&path, &hands)
+}
+
+fn write_hands(path: &Path, hands: &[Vec<Card>]) -> std::io::Result<()> {
     let mut file = BufWriter::new(File::create(path)?);
     for h in hands {
         let sh = SHand::from(&h[..]);
-        file.write(sh.raw().to_be_bytes().as_slice())?;
+        file.write_all(sh.raw().to_be_bytes().as_slice())?;
     }
     Ok(())
 }
+
+/// Whether a short-deck hand is A-6-7-8-9, the lowest straight.
+fn is_low_straight(hand: &[Card]) -> bool {
+    hand.iter().map(|c| c.rank()).sorted().collect_vec()
+        == [Rank::Six, Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ace]
+}
+
+/// Category of a hand in short-deck hold'em, where a flush beats a full
+/// house, from worst to best.
+fn short_deck_class(hand: &[Card], eval: Eval) -> u8 {
+    let low_straight = is_low_straight(hand);
+    match () {
+        _ if eval.is_straight_flush() || (low_straight && eval.is_flush()) => 8,
+        _ if eval.is_four_of_a_kind() => 7,
+        _ if eval.is_flush() => 6,
+        _ if eval.is_full_house() => 5,
+        _ if eval.is_straight() || low_straight => 4,
+        _ if eval.is_three_of_a_kind() => 3,
+        _ if eval.is_two_pair() => 2,
+        _ if eval.is_pair() => 1,
+        _ => 0,
+    }
+}
+
+fn write_short_deck_hands() -> std::io::Result<()> {
+    let path = Path::new(&std::env::var_os("OUT_DIR").unwrap()).join("short_deck_hands.bin");
+
+    if path.exists() {
+        eprintln!(
+            "skipping making short-deck hands, file exists: {}",
+            path.to_string_lossy()
+        );
+        return Ok(());
+    }
+
+    let mut hands = hands_from(Card::generate_deck().filter(|c| c.rank() >= Rank::Six));
+
+    // Within a category, hands rank as they do with a full deck, except that
+    // A-6-7-8-9 ranks below every other straight.
+    let evaluator = Evaluator::new();
+    eprintln!("sorting short-deck hands");
+    hands.par_sort_by_cached_key(|h| {
+        let eval = evaluator.evaluate(h).unwrap();
+        (short_deck_class(h, eval), !is_low_straight(h), eval)
+    });
+    eprintln!("writing short-deck hands to {}", path.to_string_lossy());
+
+    write_hands(&path, &hands)
+}
@@ -7,8 +7,18 @@ const ORDERED_HANDS_RAW: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/order
 
 pub const N_HANDS: usize = ORDERED_HANDS_RAW.len() / 4;
 
+const SHORT_DECK_HANDS_RAW: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/short_deck_hands.bin"));
+
+/// Number of hands in the 36-card short deck, without twos to fives.
+pub const N_SHORT_DECK_HANDS: usize = SHORT_DECK_HANDS_RAW.len() / 4;
+
 lazy_static! {
     pub static ref LOOKUP: HandLookup = HandLookup::new();
+
+    /// Ranks of short-deck hands, where a flush beats a full house and
+    /// A-6-7-8-9 is the lowest straight.
+    pub static ref SHORT_DECK_LOOKUP: HandLookup = HandLookup::from_raw(SHORT_DECK_HANDS_RAW);
 }
 
 pub struct HandLookup {
@@ -17,17 +27,32 @@ pub struct HandLookup {
 
 impl HandLookup {
     pub fn new() -> Self {
+        Self::from_raw(ORDERED_HANDS_RAW)
+    }
+
+    /// Lookup from hands written by the build script, in order of strength.
+    ///
+    /// Hands of equal strength, which differ only in suits, are next to each
+    /// other and all get the rank of the first of them, so that they tie.
+    fn from_raw(raw: &[u8]) -> Self {
+        let n_hands = raw.len() / 4;
         let mut map = HashMap::with_hasher(fasthash::t1ha::t1ha0::Hash64);
-        map.reserve(N_HANDS);
-        for i in 0..N_HANDS {
+        map.reserve(n_hands);
+        let mut last = None;
+        let mut score = 0;
+        for i in 0..n_hands {
             let offset = i * 4;
-            let bytes = &ORDERED_HANDS_RAW[offset..offset + 4];
+            let bytes = &raw[offset..offset + 4];
             let hand = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
 
-            // This is safe because it is guaranteed by ORDERED_HANDS_RAW.
-            unsafe {
-                map.insert(SHand::unsafe_from_raw(hand), i as u32);
+            // This is safe because it is guaranteed by the build script.
+            let hand = unsafe { SHand::unsafe_from_raw(hand) };
+            let key = strength_key(hand);
+            if last != Some(key) {
+                last = Some(key);
+                score = i as u32;
             }
+            map.insert(hand, score);
         }
         Self { map }
     }
@@ -37,6 +62,23 @@ impl HandLookup {
     }
 }
 
+/// What the strength of a hand depends on: its ranks, and whether it is a
+/// flush. Cards are sorted by rank in a packed hand.
+fn strength_key(hand: SHand) -> u32 {
+    let cards = hand.members();
+    let ranks = cards
+        .iter()
+        .fold(0, |key, c| key << 4 | (c.raw() >> 2) as u32);
+    let flush = cards.iter().all(|c| c.raw() & 0x3 == cards[0].raw() & 0x3);
+    ranks << 1 | flush as u32
+}
+
+impl Default for HandLookup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<I: Into<SHand>> Index<I> for HandLookup {
     type Output = u32;
 
@@ -77,7 +119,75 @@ mod tests {
             SCard::new(Rank::Ten, Suit::Spades),
         ];
 
-        assert!(LOOKUP[hand] > 2598960 - 4);
+        assert_eq!(LOOKUP[hand], 2598960 - 4);
+    }
+
+    #[test]
+    fn equal_hands_tie() {
+        let hand = |suits: [Suit; 2]| {
+            LOOKUP[&[
+                SCard::new(Rank::King, suits[0]),
+                SCard::new(Rank::King, suits[1]),
+                SCard::new(Rank::Seven, Suit::Clubs),
+                SCard::new(Rank::Four, Suit::Diamonds),
+                SCard::new(Rank::Two, Suit::Spades),
+            ][..]]
+        };
+
+        assert_eq!(
+            hand([Suit::Spades, Suit::Hearts]),
+            hand([Suit::Clubs, Suit::Diamonds])
+        );
+    }
+
+    #[test]
+    fn short_deck_ranks() {
+        use Rank::*;
+        use Suit::*;
+
+        let hand =
+            |cards: [(Rank, Suit); 5]| SHORT_DECK_LOOKUP[&cards.map(|(r, s)| SCard::new(r, s))[..]];
+        let low_straight = hand([
+            (Ace, Spades),
+            (Six, Hearts),
+            (Seven, Spades),
+            (Eight, Clubs),
+            (Nine, Diamonds),
+        ]);
+        let straight = hand([
+            (Six, Hearts),
+            (Seven, Spades),
+            (Eight, Clubs),
+            (Nine, Diamonds),
+            (Ten, Spades),
+        ]);
+        let trips = hand([
+            (Ace, Spades),
+            (Ace, Hearts),
+            (Ace, Clubs),
+            (King, Diamonds),
+            (Queen, Spades),
+        ]);
+        let full_house = hand([
+            (Ace, Spades),
+            (Ace, Hearts),
+            (Ace, Clubs),
+            (King, Diamonds),
+            (King, Spades),
+        ]);
+        let flush = hand([
+            (Six, Hearts),
+            (Seven, Hearts),
+            (Eight, Hearts),
+            (Nine, Hearts),
+            (Jack, Hearts),
+        ]);
+
+        assert_eq!(N_SHORT_DECK_HANDS, 376992);
+        assert!(trips < low_straight);
+        assert!(low_straight < straight);
+        assert!(straight < full_house);
+        assert!(full_house < flush);
     }
 
     #[test]
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail};
+use compact_poker::SCard;
+use poker_assistant::prediction::{board::BoardTexture, deck::Deck};
+
+use crate::{
+    cards::{cards_string, check_distinct, parse_cards},
+    dsl::{evaluate_directives, Evaluation},
+    scenario::read_program,
+};
+
+#[derive(clap::Args, Clone)]
+pub struct BoardArgs {
+    /// Community cards, e.g. "Qs Jd 2c".
+    #[clap(required_unless_present = "file", conflicts_with = "file")]
+    pub cards: Option<String>,
+
+    /// Take the community cards from a hand in this scenario file instead.
+    #[clap(short, long)]
+    pub file: Option<PathBuf>,
+
+    /// Hand holding the community cards in the scenario file.
+    #[clap(long, default_value = "community", requires = "file")]
+    pub hand: String,
+
+    /// Total number of community cards once all are dealt.
+    #[clap(long, default_value = "5")]
+    pub board_size: u8,
+
+    /// Number of the strongest possible hands to list.
+    #[clap(short, long, default_value = "5")]
+    pub top: usize,
+
+    /// Print the analysis as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Analyse the community cards of the hand named `name`. Its holes are the
+/// cards still to come, and hole cards are drawn from the sample deck.
+pub fn texture_from_evaluation(
+    eval: &Evaluation,
+    name: &str,
+    top_n: usize,
+) -> anyhow::Result<BoardTexture> {
+    let hand = eval
+        .hands
+        .get(name)
+        .ok_or_else(|| anyhow!("No hand named {name}"))?;
+    if eval.has_wild_cards() {
+        bail!("Board analysis does not support wild cards");
+    }
+    let mut board = hand.known_cards.iter().copied().collect::<Vec<_>>();
+    board.sort_unstable_by_key(|c| std::cmp::Reverse(c.raw()));
+    Ok(BoardTexture::new(
+        &board,
+        hand.n_holes() as u8,
+        eval.deck,
+        &eval.sample_deck(),
+        top_n,
+    ))
+}
+
+pub fn analyse(args: &BoardArgs) -> anyhow::Result<BoardTexture> {
+    if let Some(path) = &args.file {
+        let eval = evaluate_directives(&read_program(path)?)?;
+        return texture_from_evaluation(&eval, &args.hand, args.top);
+    }
+
+    let board = parse_cards(args.cards.as_deref().unwrap_or_default())?;
+    check_distinct(&board)?;
+    let Some(to_come) = args.board_size.checked_sub(board.len() as u8) else {
+        bail!("The board has more than {} cards", args.board_size);
+    };
+
+    let deck = SCard::deck()
+        .filter(|c| !board.contains(c))
+        .collect::<Vec<_>>();
+    Ok(BoardTexture::new(
+        &board,
+        to_come,
+        Deck::Standard,
+        &deck,
+        args.top,
+    ))
+}
+
+fn yes_no(b: bool) -> &'static str {
+    if b {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+pub fn board(args: BoardArgs) -> anyhow::Result<()> {
+    let t = analyse(&args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&t)?);
+        return Ok(());
+    }
+
+    println!(
+        "Board:         {} ({} to come)",
+        cards_string(&t.cards),
+        t.to_come
+    );
+    println!("Pairing:       {:?}", t.pairing);
+    println!(
+        "Suits:         {:?} (at most {} of a suit)",
+        t.suitedness, t.max_suited
+    );
+    println!(
+        "Flush:         possible {}, draw {}",
+        yes_no(t.flush_possible),
+        yes_no(t.flush_draw)
+    );
+    println!(
+        "Straights:     {} (connectedness {}), draw {}",
+        match t.straights.is_empty() {
+            true => "none".to_owned(),
+            false => t
+                .straights
+                .iter()
+                .map(|c| format!("{c}-high"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        },
+        t.connectedness,
+        yes_no(t.straight_draw)
+    );
+
+    if t.top_hands.is_empty() {
+        return Ok(());
+    }
+    println!("\nStrongest hands:");
+    for (i, hand) in t.top_hands.iter().enumerate() {
+        let holes = hand
+            .holes
+            .iter()
+            .map(|h| cards_string(h))
+            .collect::<Vec<_>>();
+        let shown = holes.iter().take(4).cloned().collect::<Vec<_>>().join(", ");
+        let more = match holes.len() {
+            0..=4 => String::new(),
+            n => format!(" and {} more", n - 4),
+        };
+        println!(
+            "  {}. {:<15} {}  with {shown}{more}",
+            i + 1,
+            hand.category.to_string(),
+            cards_string(&hand.cards)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use poker_assistant::prediction::{board::Pairing, category::HandCategory};
+
+    use super::*;
+    use crate::{dsl::parse_program_from_str, parse_args};
+
+    #[test]
+    fn analyses_cards() {
+        let analyse = |cards: &str| analyse(&parse_args(&[cards, "-t", "3"]));
+
+        let t = analyse("Kd Kh 7s").unwrap();
+        assert_eq!(t.to_come, 2);
+        assert_eq!(t.pairing, Pairing::Paired);
+        assert_eq!(t.top_hands.len(), 3);
+
+        assert!(analyse("Kd Kd 7s").is_err());
+        assert!(analyse("Kd Kh 7s 2c 3c 4c").is_err());
+        assert!(analyse("Kd Xx").is_err());
+    }
+
+    #[test]
+    fn analyses_evaluation() {
+        let program = r#"
+            (define-cards community "Kd Kh 7s ? ?")
+            (plot-cards self "Ks Kc $community")
+        "#;
+        let eval = evaluate_directives(&parse_program_from_str(program).unwrap()).unwrap();
+
+        let t = texture_from_evaluation(&eval, "community", 3).unwrap();
+
+        assert_eq!(t.to_come, 2);
+        // Our kings are known, so the best an opponent can make is a full
+        // house.
+        assert_eq!(t.nuts().unwrap().category, HandCategory::FullHouse);
+        assert!(texture_from_evaluation(&eval, "villain", 3).is_err());
+    }
+}
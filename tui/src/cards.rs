@@ -0,0 +1,58 @@
+//! Reading and writing cards given on the command line.
+
+use anyhow::{anyhow, bail};
+use compact_poker::SCard;
+use poker::Card;
+use poker_assistant::prediction::model::HandVec;
+
+/// Cards separated by whitespace, e.g. "As Kd 7c".
+pub fn parse_cards(s: &str) -> anyhow::Result<HandVec> {
+    s.split_whitespace()
+        .map(|c| {
+            c.parse::<Card>()
+                .map(SCard::from)
+                .map_err(|e| anyhow!("Could not parse card {c}: {e}"))
+        })
+        .collect()
+}
+
+/// Cards separated by spaces, as read by [`parse_cards`].
+pub fn cards_string(cards: &[SCard]) -> String {
+    cards
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Fails on the first card which appears more than once.
+pub fn check_distinct<'a>(cards: impl IntoIterator<Item = &'a SCard>) -> anyhow::Result<()> {
+    let mut seen = vec![];
+    for c in cards {
+        if seen.contains(c) {
+            bail!("{c} appears more than once");
+        }
+        seen.push(*c);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_finds_duplicates() {
+        let cards = parse_cards(" As  Kd 7c ").unwrap();
+
+        assert_eq!(cards_string(&cards), "As Kd 7c");
+        assert!(parse_cards("As Zz").is_err());
+        assert!(check_distinct(&cards).is_ok());
+        assert_eq!(
+            check_distinct(cards.iter().chain(&cards[1..2]))
+                .unwrap_err()
+                .to_string(),
+            "Kd appears more than once"
+        );
+    }
+}
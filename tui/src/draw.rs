@@ -0,0 +1,174 @@
+use anyhow::bail;
+use compact_poker::SCard;
+use poker_assistant::prediction::{
+    category::HandCategory,
+    draw::{DrawOption, DrawParams, DrawPolicy, PastDraw},
+    stats::Z_95,
+};
+use strum::IntoEnumIterator;
+
+use crate::cards::{cards_string, check_distinct, parse_cards};
+
+#[derive(clap::Args, Clone)]
+pub struct DrawArgs {
+    /// Our hand before the exchange, e.g. "As Ah Kd 7c 2s".
+    pub hand: String,
+
+    /// Most cards that may be exchanged.
+    #[clap(short, long, default_value = "3")]
+    pub max: u8,
+
+    /// Number of cards an opponent exchanged in the same round. Repeat for
+    /// each opponent. Their hands are dealt consistently with the exchange.
+    #[clap(short = 'p', long = "opponent")]
+    pub opponents: Vec<u8>,
+
+    /// Number of samples to simulate for each option
+    #[clap(short = 'n', default_value = "20000")]
+    pub samples: u64,
+
+    /// Seed for reproducible results.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Number of the best options to show.
+    #[clap(short, long, default_value = "5")]
+    pub top: usize,
+
+    /// Print every option as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Every option for the hand, best first.
+pub fn advise(args: &DrawArgs) -> anyhow::Result<Vec<DrawOption>> {
+    let hand = parse_cards(&args.hand)?;
+    if hand.len() != 5 {
+        bail!("Expected 5 cards, got {}", hand.len());
+    }
+    check_distinct(&hand)?;
+    if let Some(d) = args.opponents.iter().find(|d| **d > args.max.min(5)) {
+        bail!("An opponent cannot exchange {d} cards");
+    }
+
+    let deck = SCard::deck()
+        .filter(|c| !hand.contains(c))
+        .collect::<Vec<_>>();
+    let params = DrawParams {
+        hand,
+        max_exchange: args.max,
+        sample_deck: &deck,
+        opponents: args
+            .opponents
+            .iter()
+            .map(|n| PastDraw {
+                n: *n,
+                max: args.max,
+                policy: DrawPolicy::Heuristic,
+            })
+            .collect(),
+        seed: args.seed,
+    };
+    if params.cards_needed() > deck.len() {
+        bail!(
+            "Dealing every opponent and the replacements needs {} cards, but only {} are left",
+            params.cards_needed(),
+            deck.len()
+        );
+    }
+    Ok(params.advise(args.samples, 10))
+}
+
+pub fn draw(args: DrawArgs) -> anyhow::Result<()> {
+    let options = advise(&args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&options)?);
+        return Ok(());
+    }
+
+    for (i, o) in options.iter().take(args.top).enumerate() {
+        let discard = match o.discard.is_empty() {
+            true => "nothing".to_owned(),
+            false => cards_string(&o.discard),
+        };
+        print!(
+            "{:>2}. discard {:<11} u={:.04}",
+            i + 1,
+            discard,
+            o.summary.mean
+        );
+        if let Some(e) = o.equity {
+            print!(
+                "  equity {:.2}%±{:.2}",
+                e.equity * 100.0,
+                e.margin(Z_95) * 100.0
+            );
+        }
+        println!();
+
+        let categories = HandCategory::iter()
+            .rev()
+            .map(|c| (c, o.summary.categories[c.index()]))
+            .filter(|(_, p)| *p >= 0.005)
+            .map(|(c, p)| format!("{c} {:.1}%", p * 100.0))
+            .collect::<Vec<_>>();
+        println!("    {}", categories.join(", "));
+    }
+
+    let unconditioned = options
+        .iter()
+        .filter_map(|o| o.equity)
+        .map(|e| e.unconditioned)
+        .fold(0.0, f64::max);
+    if unconditioned > 0.0 {
+        eprintln!(
+            "Warning: opponents could not be dealt hands matching their exchanges in up to {:.1}% of samples",
+            unconditioned * 100.0
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    #[test]
+    fn rejects_bad_hands() {
+        let crowded = DrawArgs {
+            opponents: vec![3; 6],
+            ..parse_args(&["As Ah Kd 7c 2s"])
+        };
+        assert!(advise(&crowded).is_err());
+
+        let advise = |argv: &[&str]| advise(&parse_args(argv));
+
+        assert!(advise(&["As Ah Kd 7c"]).is_err());
+        assert!(advise(&["As Ah Kd 7c 7c"]).is_err());
+        assert!(advise(&["As Ah Kd 7c 2s", "-p", "6"]).is_err());
+        assert!(advise(&["As Ah Kd 7c 2s", "-p", "4"]).is_err());
+    }
+
+    #[test]
+    fn keeps_the_pair() {
+        let pair = DrawArgs {
+            samples: 2000,
+            seed: Some(1),
+            ..parse_args(&["As Ah Kd 7c 2s", "-p", "1"])
+        };
+
+        let options = advise(&pair).unwrap();
+
+        assert_eq!(options.len(), 26);
+        assert!(
+            options[0]
+                .hold
+                .iter()
+                .filter(|c| c.rank() == poker::Rank::Ace)
+                .count()
+                == 2
+        );
+        assert!(options[0].equity.is_some());
+    }
+}
@@ -1,9 +1,14 @@
-use indexmap::IndexMap;
-use std::collections::HashSet;
+use indexmap::{IndexMap, IndexSet};
+use std::{collections::HashSet, fmt};
 
-use compact_poker::SCard;
+use compact_poker::{SCard, Wild};
 use lexpr::Value;
-use poker::{Card, ParseCardError};
+use poker::{Card, ParseCardError, Rank};
+use poker_assistant::prediction::{
+    deck::Deck,
+    equity::{Contender, Showdown},
+    low::Pot,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Directive {
@@ -19,6 +24,27 @@ pub enum Directive {
 
     /// Cards to be discarded from the deck.
     Discard(Vec<CardsExp>),
+
+    /// A hand which has folded, e.g. a stud player whose upcards are dead.
+    ///
+    /// Its known cards stay out of the deck, but it is no longer plotted and
+    /// cannot take part in a showdown.
+    Fold(String),
+
+    /// Deck the cards are dealt from, which also decides how hands are
+    /// ranked. The standard deck is used if this is not given.
+    Deck(Deck),
+
+    /// Make every card of a rank wild, as well as any jokers.
+    Wild(Rank),
+
+    /// Add this many jokers to the deck. Jokers are written `Jk`.
+    Jokers(u8),
+
+    /// How the pot of a showdown is won, e.g. `(pot low)` for razz. The best
+    /// hand wins if this is not given. Plotted scores are always of the
+    /// best high hand.
+    Pot(Pot),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, derive_more::From)]
@@ -30,6 +56,9 @@ pub enum CardsExp {
     /// An unknown card.
     Hole,
 
+    /// A joker, which is always wild.
+    Joker,
+
     /// Substitute another hand in for this card.
     #[from(ignore)]
     Subs(String),
@@ -61,6 +90,21 @@ pub enum ParseError {
     #[error("Bad Discard expression {0}")]
     BadDiscardExpression(Value),
 
+    #[error("Bad Fold expression {0}")]
+    BadFoldExpression(Value),
+
+    #[error("Bad Deck expression {0}")]
+    BadDeckExpression(Value),
+
+    #[error("Bad Wild expression {0}")]
+    BadWildExpression(Value),
+
+    #[error("Bad Jokers expression {0}")]
+    BadJokersExpression(Value),
+
+    #[error("Bad Pot expression {0}")]
+    BadPotExpression(Value),
+
     #[error("Error parsing S-expression: {0}")]
     LexprError(#[from] lexpr::parse::Error),
 }
@@ -72,6 +116,57 @@ pub enum EvaluationError {
 
     #[error("Could not find hand with name {0}")]
     UnknownHand(String),
+
+    #[error("Hand {0} has folded")]
+    FoldedHand(String),
+
+    #[error("Card {0} is not in the {1} deck")]
+    NotInDeck(String, Deck),
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Directive::DefineCards(dh) => {
+                write!(f, "(define-cards {} \"{}\")", dh.name, CardsList(&dh.cards))
+            }
+            Directive::PlotCards(dh) => {
+                write!(f, "(plot-cards {} \"{}\")", dh.name, CardsList(&dh.cards))
+            }
+            Directive::Discard(cards) => write!(f, "(discard \"{}\")", CardsList(cards)),
+            Directive::Fold(name) => write!(f, "(fold {name})"),
+            Directive::Deck(deck) => write!(f, "(deck {deck})"),
+            Directive::Wild(rank) => write!(f, "(wild \"{}\")", rank.as_char()),
+            Directive::Jokers(n) => write!(f, "(jokers {n})"),
+            Directive::Pot(pot) => write!(f, "(pot {pot})"),
+        }
+    }
+}
+
+impl fmt::Display for CardsExp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CardsExp::Lit(c) => write!(f, "{}", c.rank_suit_string()),
+            CardsExp::Hole => write!(f, "?"),
+            CardsExp::Joker => write!(f, "Jk"),
+            CardsExp::Subs(name) => write!(f, "${name}"),
+        }
+    }
+}
+
+/// Space-separated list of card expressions, as written inside a string.
+struct CardsList<'a>(&'a [CardsExp]);
+
+impl fmt::Display for CardsList<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, c) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -83,6 +178,87 @@ pub struct Evaluation {
 
     /// Hands in the evaluation.
     pub hands: IndexMap<String, ConcreteHand>,
+
+    /// Deck the cards are dealt from.
+    pub deck: Deck,
+
+    /// Cards which are wild, besides jokers.
+    pub wild: Wild,
+
+    /// Number of jokers added to the deck.
+    pub jokers: u8,
+
+    /// How the pot of a showdown is won.
+    pub pot: Pot,
+}
+
+/// Identifies an unknown card by the hand it was written in, and its position
+/// among that hand's unknown cards.
+///
+/// Hands substituting another hand share its holes, so they are dealt the same
+/// card.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HoleId {
+    pub hand: String,
+    pub index: usize,
+}
+
+impl Evaluation {
+    /// The full deck and its jokers, without any discarded cards.
+    pub fn sample_deck(&self) -> Vec<SCard> {
+        let mut deck = self.deck.cards();
+        deck.extend(&SCard::JOKERS[..self.jokers as usize]);
+        deck.retain(|c| !self.discarded.contains(c));
+        deck
+    }
+
+    /// Whether any card is wild.
+    pub fn has_wild_cards(&self) -> bool {
+        self.jokers > 0 || self.wild != Wild::NONE
+    }
+
+    /// A showdown between the named hands, where holes shared between hands
+    /// are dealt the same card.
+    pub fn showdown<'a>(
+        &self,
+        names: &[&str],
+        sample_deck: &'a [SCard],
+    ) -> Result<Showdown<'a>, EvaluationError> {
+        let mut pool = IndexSet::new();
+        let contenders = names
+            .iter()
+            .map(|name| {
+                let hand = self
+                    .hands
+                    .get(*name)
+                    .ok_or_else(|| EvaluationError::UnknownHand(name.to_string()))?;
+                if hand.folded {
+                    return Err(EvaluationError::FoldedHand(name.to_string()));
+                }
+                Ok(Contender {
+                    known: hand.known_cards.iter().copied().collect(),
+                    sampled: hand
+                        .holes
+                        .iter()
+                        .map(|h| pool.insert_full(h.clone()).0)
+                        .collect(),
+                    drew: None,
+                    exchanges: Default::default(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Showdown {
+            contenders,
+            n_sampled: pool.len(),
+            sample_deck,
+            seed: None,
+            deck: self.deck,
+            wild: self.wild,
+            pot: self.pot,
+            opponent_range: None,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -96,8 +272,18 @@ pub struct ConcreteHand {
     /// Cards known in this hand.
     pub known_cards: HashSet<SCard>,
 
+    /// Holes in the hand for the simulator to fill.
+    pub holes: Vec<HoleId>,
+
+    /// Whether this hand has folded.
+    pub folded: bool,
+}
+
+impl ConcreteHand {
     /// Number of holes in the hand for the simulator to fill.
-    pub n_holes: usize,
+    pub fn n_holes(&self) -> usize {
+        self.holes.len()
+    }
 }
 
 pub fn evaluate_directives<'a>(
@@ -110,71 +296,133 @@ pub fn evaluate_directives<'a>(
     Ok(ctx)
 }
 
-fn evaluate_directive(ctx: &mut Evaluation, directive: &Directive) -> Result<(), EvaluationError> {
+/// Apply a single directive. On error, the evaluation is left unchanged.
+pub fn evaluate_directive(
+    ctx: &mut Evaluation,
+    directive: &Directive,
+) -> Result<(), EvaluationError> {
     match directive {
         Directive::DefineCards(dh) => {
             let ch = evaluate_define_hand(ctx, dh, false)?;
+            check_in_deck(ctx, &ch.known_cards)?;
             ctx.discarded.extend(&ch.known_cards);
             ctx.hands.insert(ch.name.clone(), ch);
         }
         Directive::PlotCards(dh) => {
             let ch = evaluate_define_hand(ctx, dh, true)?;
+            check_in_deck(ctx, &ch.known_cards)?;
             ctx.discarded.extend(&ch.known_cards);
             ctx.hands.insert(ch.name.clone(), ch);
         }
         Directive::Discard(cards) => {
-            let (known_cards, _n_holes) = evaluate_card_exprs(ctx, cards)?;
+            let (known_cards, _holes) = evaluate_card_exprs(ctx, "", cards)?;
+            check_in_deck(ctx, &known_cards)?;
             ctx.discarded.extend(known_cards);
         }
+        Directive::Deck(deck) => {
+            let with_deck = Evaluation {
+                deck: *deck,
+                ..ctx.clone()
+            };
+            check_in_deck(&with_deck, &ctx.discarded)?;
+            ctx.deck = *deck;
+        }
+        Directive::Wild(rank) => ctx.wild = ctx.wild.with_rank(*rank),
+        Directive::Jokers(n) => {
+            let with_jokers = Evaluation {
+                jokers: *n,
+                ..ctx.clone()
+            };
+            check_in_deck(&with_jokers, &ctx.discarded)?;
+            ctx.jokers = *n;
+        }
+        Directive::Pot(pot) => ctx.pot = *pot,
+        Directive::Fold(name) => {
+            let hand = ctx
+                .hands
+                .get_mut(name)
+                .ok_or_else(|| EvaluationError::UnknownHand(name.clone()))?;
+            hand.should_plot = false;
+            hand.folded = true;
+        }
     }
 
     Ok(())
 }
 
+/// Fails on the first of `cards` which is neither in the deck nor one of its
+/// jokers.
+fn check_in_deck(ctx: &Evaluation, cards: &HashSet<SCard>) -> Result<(), EvaluationError> {
+    let jokers = &SCard::JOKERS[..ctx.jokers as usize];
+    match cards
+        .iter()
+        .find(|c| !ctx.deck.contains(**c) && !jokers.contains(c))
+    {
+        Some(c) => Err(EvaluationError::NotInDeck(c.to_string(), ctx.deck)),
+        None => Ok(()),
+    }
+}
+
 fn evaluate_define_hand(
     ctx: &Evaluation,
     dh: &DefineHand,
     should_plot: bool,
 ) -> Result<ConcreteHand, EvaluationError> {
-    if let Some(_) = ctx.hands.get(&dh.name) {
+    if ctx.hands.contains_key(&dh.name) {
         return Err(EvaluationError::HandAlreadyExists(dh.name.clone()));
     }
 
-    let (known_cards, n_holes) = evaluate_card_exprs(ctx, dh.cards.iter())?;
+    let (known_cards, holes) = evaluate_card_exprs(ctx, &dh.name, dh.cards.iter())?;
 
     Ok(ConcreteHand {
         should_plot,
         name: dh.name.clone(),
         known_cards,
-        n_holes,
+        holes,
+        folded: false,
     })
 }
 
+/// Returns the known cards and holes of the expressions, with new holes
+/// attributed to the hand named `name`.
 fn evaluate_card_exprs<'a>(
     ctx: &Evaluation,
+    name: &str,
     card_exprs: impl IntoIterator<Item = &'a CardsExp>,
-) -> Result<(HashSet<SCard>, usize), EvaluationError> {
+) -> Result<(HashSet<SCard>, Vec<HoleId>), EvaluationError> {
     let mut known_cards = HashSet::new();
-    let mut n_holes = 0;
+    let mut holes = vec![];
+    let mut n_own_holes = 0;
     for cexpr in card_exprs {
         match cexpr {
             CardsExp::Lit(c) => {
                 known_cards.insert((*c).into());
             }
+            CardsExp::Joker => {
+                let joker = SCard::JOKERS
+                    .into_iter()
+                    .find(|j| !ctx.discarded.contains(j) && !known_cards.contains(j))
+                    .ok_or_else(|| EvaluationError::NotInDeck("Jk".into(), ctx.deck))?;
+                known_cards.insert(joker);
+            }
             CardsExp::Hole => {
-                n_holes += 1;
+                holes.push(HoleId {
+                    hand: name.to_owned(),
+                    index: n_own_holes,
+                });
+                n_own_holes += 1;
             }
             CardsExp::Subs(ref_name) => match ctx.hands.get(ref_name) {
                 Some(hand) => {
                     known_cards.extend(&hand.known_cards);
-                    n_holes += hand.n_holes;
+                    holes.extend(hand.holes.iter().cloned());
                 }
                 None => Err(EvaluationError::UnknownHand(ref_name.clone()))?,
             },
         }
     }
 
-    Ok((known_cards, n_holes))
+    Ok((known_cards, holes))
 }
 
 pub fn parse_program_from_str(s: &str) -> Result<Vec<Directive>, ParseError> {
@@ -196,6 +444,11 @@ pub fn parse_directive(exp: &Value) -> Result<Directive, ParseError> {
                 "define-cards" => Ok(Directive::DefineCards(parse_define_hand(c.cdr())?)),
                 "plot-cards" => Ok(Directive::PlotCards(parse_define_hand(c.cdr())?)),
                 "discard" => Ok(Directive::Discard(parse_discard(c.cdr())?)),
+                "fold" => Ok(Directive::Fold(parse_fold(c.cdr())?)),
+                "deck" => Ok(Directive::Deck(parse_deck(c.cdr())?)),
+                "wild" => Ok(Directive::Wild(parse_wild(c.cdr())?)),
+                "jokers" => Ok(Directive::Jokers(parse_jokers(c.cdr())?)),
+                "pot" => Ok(Directive::Pot(parse_pot(c.cdr())?)),
                 name => Err(UnknownDirective {
                     name: name.to_owned(),
                 }),
@@ -236,7 +489,71 @@ fn parse_discard(cdr: &Value) -> Result<Vec<CardsExp>, ParseError> {
     }
 }
 
-fn parse_cards_list(cards_list: impl AsRef<str>) -> Result<Vec<CardsExp>, ParseError> {
+fn parse_fold(cdr: &Value) -> Result<String, ParseError> {
+    let args = cdr
+        .to_vec()
+        .ok_or_else(|| ParseError::BadFoldExpression(cdr.clone()))?;
+    match &args.as_slice() {
+        &[Value::Symbol(name)] => Ok(name.to_string()),
+        _ => Err(ParseError::BadFoldExpression(cdr.clone())),
+    }
+}
+
+fn parse_deck(cdr: &Value) -> Result<Deck, ParseError> {
+    let args = cdr
+        .to_vec()
+        .ok_or_else(|| ParseError::BadDeckExpression(cdr.clone()))?;
+    match &args.as_slice() {
+        &[Value::Symbol(name)] => name
+            .parse()
+            .map_err(|_| ParseError::BadDeckExpression(cdr.clone())),
+        _ => Err(ParseError::BadDeckExpression(cdr.clone())),
+    }
+}
+
+fn parse_pot(cdr: &Value) -> Result<Pot, ParseError> {
+    let args = cdr
+        .to_vec()
+        .ok_or_else(|| ParseError::BadPotExpression(cdr.clone()))?;
+    match &args.as_slice() {
+        &[Value::Symbol(name)] => name
+            .parse()
+            .map_err(|_| ParseError::BadPotExpression(cdr.clone())),
+        _ => Err(ParseError::BadPotExpression(cdr.clone())),
+    }
+}
+
+fn parse_wild(cdr: &Value) -> Result<Rank, ParseError> {
+    let args = cdr
+        .to_vec()
+        .ok_or_else(|| ParseError::BadWildExpression(cdr.clone()))?;
+    let mut chars = match &args.as_slice() {
+        &[Value::String(s)] => s.chars(),
+        _ => return Err(ParseError::BadWildExpression(cdr.clone())),
+    };
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => {
+            Rank::try_from(c).map_err(|_| ParseError::BadWildExpression(cdr.clone()))
+        }
+        _ => Err(ParseError::BadWildExpression(cdr.clone())),
+    }
+}
+
+fn parse_jokers(cdr: &Value) -> Result<u8, ParseError> {
+    let args = cdr
+        .to_vec()
+        .ok_or_else(|| ParseError::BadJokersExpression(cdr.clone()))?;
+    match &args.as_slice() {
+        &[n] => n
+            .as_u64()
+            .filter(|n| *n as usize <= SCard::JOKERS.len())
+            .map(|n| n as u8)
+            .ok_or_else(|| ParseError::BadJokersExpression(cdr.clone())),
+        _ => Err(ParseError::BadJokersExpression(cdr.clone())),
+    }
+}
+
+pub fn parse_cards_list(cards_list: impl AsRef<str>) -> Result<Vec<CardsExp>, ParseError> {
     cards_list
         .as_ref()
         .split_whitespace()
@@ -247,13 +564,14 @@ fn parse_cards_list(cards_list: impl AsRef<str>) -> Result<Vec<CardsExp>, ParseE
 fn parse_card_exp(exp: impl AsRef<str>) -> Result<CardsExp, ParseError> {
     let exp = exp.as_ref();
 
-    if exp.len() == 0 {
+    if exp.is_empty() {
         panic!("exp.len() == 0, this should never happen")
     }
 
     match exp.split_at(1) {
         ("$", rest) => Ok(CardsExp::Subs(rest.to_owned())),
         ("?", "") => Ok(CardsExp::Hole),
+        _ if exp == "Jk" => Ok(CardsExp::Joker),
         _ => Ok(CardsExp::Lit(
             exp.parse::<Card>().map_err(ParseError::CouldNotParseCard)?,
         )),
@@ -342,6 +660,10 @@ mod tests {
         let c_As = SCard::new(Ace, Spades);
         let c_Kh = SCard::new(King, Hearts);
         let c_2s = SCard::new(Two, Spades);
+        let hole = |hand: &str, index| HoleId {
+            hand: hand.into(),
+            index,
+        };
 
         use Rank::*;
         use Suit::*;
@@ -355,7 +677,8 @@ mod tests {
                         should_plot: false,
                         name: "community".into(),
                         known_cards: [c_3c, c_Td, c_2s].into(),
-                        n_holes: 2,
+                        holes: vec![hole("community", 0), hole("community", 1)],
+                        folded: false,
                     },
                 ),
                 (
@@ -364,7 +687,8 @@ mod tests {
                         should_plot: true,
                         name: "self".into(),
                         known_cards: [c_3c, c_Td, c_2s, c_As, c_Kh].into(),
-                        n_holes: 2,
+                        holes: vec![hole("community", 0), hole("community", 1)],
+                        folded: false,
                     },
                 ),
                 (
@@ -373,13 +697,169 @@ mod tests {
                         should_plot: true,
                         name: "opponents".into(),
                         known_cards: [c_3c, c_Td, c_2s].into(),
-                        n_holes: 4,
+                        holes: vec![
+                            hole("opponents", 0),
+                            hole("opponents", 1),
+                            hole("community", 0),
+                            hole("community", 1),
+                        ],
+                        folded: false,
                     },
                 ),
             ]
             .into(),
+            deck: Deck::Standard,
+            wild: Wild::NONE,
+            jokers: 0,
+            pot: Pot::High,
         };
 
         assert_eq!(eval, expected)
     }
+
+    #[test]
+    fn directives_roundtrip_through_display() {
+        let program = r#"
+            (define-cards community "3c Td 2s ? ?")
+            (plot-cards self "As Kh $community")
+            (discard "7h ?")
+            (fold self)
+            (deck short)
+            (wild "T")
+            (jokers 2)
+            (pot high-low)
+        "#;
+
+        let directives = parse_program_from_str(program).unwrap();
+        let printed = directives
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert_eq!(
+            printed,
+            "(define-cards community \"3c Td 2s ? ?\")\n\
+             (plot-cards self \"As Kh $community\")\n\
+             (discard \"7h ?\")\n\
+             (fold self)\n\
+             (deck short)\n\
+             (wild \"T\")\n\
+             (jokers 2)\n\
+             (pot high-low)"
+        );
+        assert_eq!(parse_program_from_str(&printed).unwrap(), directives);
+    }
+
+    #[test]
+    fn low_pots_go_to_the_lowest_hand() {
+        let program = r#"
+            (pot low)
+            (plot-cards self "As 2s 3s 4s 6d ? ?")
+            (plot-cards opp "Ks Kd Kh Kc Qs ? ?")
+        "#;
+        let eval = evaluate_directives(&parse_program_from_str(program).unwrap()).unwrap();
+        assert_eq!(eval.pot, Pot::Low);
+        let deck = eval.sample_deck();
+        let equity = eval
+            .showdown(&["self", "opp"], &deck)
+            .unwrap()
+            .with_seed(Some(1))
+            .equity(200);
+        assert_eq!(equity[0].win, 1.0);
+
+        assert!(parse_program_from_str("(pot lowball)").is_err());
+    }
+
+    #[test]
+    fn showdown_shares_substituted_holes() {
+        let program = r#"
+            (define-cards community "3c Td 2s ? ?")
+            (plot-cards self "As Kh $community")
+            (plot-cards opponents "? ? $community")
+        "#;
+        let eval = evaluate_directives(parse_program_from_str(program).unwrap().iter()).unwrap();
+        let deck = eval.sample_deck();
+
+        let showdown = eval.showdown(&["self", "opponents"], &deck).unwrap();
+
+        assert_eq!(deck.len(), 47);
+        assert_eq!(showdown.n_sampled, 4);
+        assert_eq!(showdown.contenders[0].sampled.as_slice(), &[0, 1]);
+        assert_eq!(showdown.contenders[1].sampled.as_slice(), &[2, 3, 0, 1]);
+        assert_eq!(
+            eval.showdown(&["nobody"], &deck).err(),
+            Some(EvaluationError::UnknownHand("nobody".into()))
+        );
+    }
+
+    #[test]
+    fn folded_hands_stay_out_of_the_deck() {
+        let program = r#"
+            (plot-cards self "As Kh ? ? ? ? ?")
+            (plot-cards opp1 "? ? Th ? ? ? ?")
+            (plot-cards opp2 "? ? Jh ? ? ? ?")
+            (fold opp2)
+        "#;
+        let eval = evaluate_directives(&parse_program_from_str(program).unwrap()).unwrap();
+
+        let opp2 = &eval.hands["opp2"];
+        assert!(opp2.folded && !opp2.should_plot);
+        assert_eq!(eval.sample_deck().len(), 48);
+        assert_eq!(
+            eval.showdown(&["self", "opp2"], &eval.sample_deck()).err(),
+            Some(EvaluationError::FoldedHand("opp2".into()))
+        );
+        assert!(evaluate_directives(&parse_program_from_str("(fold nobody)").unwrap()).is_err());
+        assert!(parse_program_from_str(r#"(fold "opp1")"#).is_err());
+    }
+
+    #[test]
+    fn short_deck_leaves_out_low_cards() {
+        let program = r#"
+            (deck short)
+            (plot-cards self "As Kh ? ? ? ? ?")
+        "#;
+        let eval = evaluate_directives(&parse_program_from_str(program).unwrap()).unwrap();
+        assert_eq!(eval.deck, Deck::Short);
+        assert_eq!(eval.sample_deck().len(), 34);
+
+        let low_card = parse_program_from_str(r#"(plot-cards self "5s ?") (deck short)"#).unwrap();
+        assert_eq!(
+            evaluate_directives(&low_card).err(),
+            Some(EvaluationError::NotInDeck("5s".into(), Deck::Short))
+        );
+        assert!(parse_program_from_str("(deck pinochle)").is_err());
+    }
+
+    #[test]
+    fn jokers_are_dealt_and_wild() {
+        let program = r#"
+            (wild "2")
+            (jokers 2)
+            (plot-cards self "Jk 2h As ? ?")
+            (plot-cards other "Jk ? ? ? ?")
+        "#;
+        let eval = evaluate_directives(&parse_program_from_str(program).unwrap()).unwrap();
+        assert!(eval.has_wild_cards());
+        assert_eq!(eval.wild, Wild::NONE.with_rank(Rank::Two));
+        assert!(eval.hands["self"].known_cards.contains(&SCard::JOKERS[0]));
+        assert!(eval.hands["other"].known_cards.contains(&SCard::JOKERS[1]));
+        assert_eq!(eval.sample_deck().len(), 52 + 2 - 4);
+
+        let deck = eval.sample_deck();
+        let equity = eval
+            .showdown(&["self", "other"], &deck)
+            .unwrap()
+            .equity(2000);
+        assert!(equity[0].equity > 0.8, "{equity:?}");
+
+        let too_many = parse_program_from_str(r#"(jokers 1) (plot-cards self "Jk Jk")"#).unwrap();
+        assert_eq!(
+            evaluate_directives(&too_many).err(),
+            Some(EvaluationError::NotInDeck("Jk".into(), Deck::Standard))
+        );
+        assert!(parse_program_from_str(r#"(wild "22")"#).is_err());
+        assert!(parse_program_from_str("(jokers 3)").is_err());
+    }
 }
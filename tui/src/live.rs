@@ -0,0 +1,811 @@
+use std::{collections::VecDeque, io::stdout};
+
+use compact_poker::SCard;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use poker::{Card, ParseCardError};
+use poker_assistant::{
+    game_repr::Round,
+    prediction::{
+        category::{outs, HandCategory},
+        discard::DiscardParams,
+        draw::{DrawPolicy, Exchanges},
+        equity::{Equity, Showdown},
+        low::Pot,
+        model::{DiscardError, Game, HandVec, PartialHand},
+        montecarlo::SimParams,
+        stats::{ScoreSummary, Z_95},
+    },
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    textplot::{bar, sparkline},
+    BuiltinTemplates,
+};
+
+#[derive(clap::Args, Clone)]
+pub struct LiveArgs {
+    /// Game to play.
+    #[clap(short = 't')]
+    pub template: BuiltinTemplates,
+
+    /// Number of opponents.
+    #[clap(short, long, default_value = "1")]
+    pub opponents: usize,
+
+    /// Number of samples to simulate after each input
+    #[clap(short = 'n', default_value = "20000")]
+    pub samples: u64,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum InputError {
+    #[error("Could not parse card {0}: {1}")]
+    CouldNotParseCard(String, ParseCardError),
+
+    #[error("{0} has already been seen")]
+    CardNotInDeck(String),
+
+    #[error("Expected at most {expected} cards, got {got}")]
+    TooManyCards { expected: u8, got: usize },
+
+    #[error("{0} is not in your hand")]
+    NotInHand(String),
+
+    #[error("Expected {expected} cards, got {got}")]
+    WrongCardCount { expected: u8, got: usize },
+
+    #[error("Expected a number of cards from 0 to {max}, got {got}")]
+    BadDrawCount { max: u8, got: String },
+
+    #[error("The hand is over")]
+    HandOver,
+
+    #[error(transparent)]
+    Discard(#[from] DiscardError),
+}
+
+/// A piece of input we are waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Our own hole cards.
+    Hole(u8),
+
+    /// Our own face-up cards.
+    Stud(u8),
+
+    /// Community cards.
+    Community(u8),
+
+    /// An opponent's face-up cards.
+    OpponentStud { opponent: usize, n: u8 },
+
+    /// Cards we throw away in an exchange.
+    Discard { max: u8 },
+
+    /// How many cards an opponent exchanged.
+    OpponentDraw { opponent: usize, max: u8 },
+
+    /// Hole cards we throw away without replacement. Every opponent throws
+    /// away as many.
+    DiscardHole(u8),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    /// Our equity against all opponents jointly.
+    pub equity: Option<Equity>,
+
+    /// Distribution of our final hand.
+    pub summary: Option<ScoreSummary>,
+
+    /// Cards improving the category of our hand if we get them next.
+    pub outs: Vec<SCard>,
+
+    /// Cards best thrown away in the next exchange or discard round, if our
+    /// hand is complete.
+    pub discard: Option<HandVec>,
+}
+
+pub struct LiveState {
+    pub rounds: Vec<Round>,
+
+    /// Index of the round currently being dealt.
+    pub round: usize,
+
+    /// Input still expected for the current round.
+    pub steps: VecDeque<Step>,
+
+    pub game: Game,
+
+    /// How the pot is won at showdown.
+    pub pot: Pot,
+
+    /// Number of samples to use per simulation.
+    pub samples: u64,
+
+    pub analysis: Analysis,
+}
+
+impl LiveState {
+    pub fn new(rounds: Vec<Round>, n_opponents: usize, samples: u64) -> Self {
+        let game = Game::from_rounds(n_opponents, &rounds);
+        let mut state = Self {
+            rounds,
+            round: 0,
+            steps: VecDeque::new(),
+            game,
+            pot: Pot::High,
+            samples,
+            analysis: Analysis::default(),
+        };
+        state.start_round();
+        state
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Queue up input for the current round, skipping rounds needing none.
+    fn start_round(&mut self) {
+        while self.steps.is_empty() && self.round < self.rounds.len() {
+            match self.rounds[self.round] {
+                Round::Deal { counts, .. } => {
+                    for o in self.game.opponents.iter_mut() {
+                        o.hole.add_cards(counts.hole);
+                    }
+                    if counts.hole > 0 {
+                        self.steps.push_back(Step::Hole(counts.hole));
+                    }
+                    if counts.stud > 0 {
+                        self.steps.push_back(Step::Stud(counts.stud));
+                        for opponent in 0..self.game.opponents.len() {
+                            self.steps.push_back(Step::OpponentStud {
+                                opponent,
+                                n: counts.stud,
+                            });
+                        }
+                    }
+                    if counts.community > 0 {
+                        self.steps.push_back(Step::Community(counts.community));
+                    }
+                }
+                Round::Exchange { max, .. } => {
+                    self.steps.push_back(Step::Discard { max });
+                    for opponent in 0..self.game.opponents.len() {
+                        self.steps.push_back(Step::OpponentDraw { opponent, max });
+                    }
+                }
+                Round::Discard { n, .. } => self.steps.push_back(Step::DiscardHole(n)),
+            }
+
+            if self.steps.is_empty() {
+                self.round += 1;
+            }
+        }
+    }
+
+    pub fn prompt(&self) -> String {
+        let round = self
+            .rounds
+            .get(self.round)
+            .and_then(|r| r.name())
+            .unwrap_or("Showdown");
+        let step = match self.steps.front() {
+            Some(Step::Hole(n)) => format!("your hole cards ({n} left)"),
+            Some(Step::Stud(n)) => format!("your face-up cards ({n} left)"),
+            Some(Step::Community(n)) => format!("community cards ({n} left)"),
+            Some(Step::OpponentStud { opponent, n }) => {
+                format!("opponent {}'s face-up cards ({n} left)", opponent + 1)
+            }
+            Some(Step::Discard { max }) => format!("cards you throw away (up to {max})"),
+            Some(Step::OpponentDraw { opponent, max }) => {
+                format!("number of cards opponent {} drew (0-{max})", opponent + 1)
+            }
+            Some(Step::DiscardHole(n)) => format!("hole cards you throw away ({n})"),
+            None => return "The hand is over. Press Esc to quit.".to_owned(),
+        };
+        format!("{round}: {step}")
+    }
+
+    /// Apply a line of input to the current step.
+    pub fn submit(&mut self, line: &str) -> Result<(), InputError> {
+        let step = *self.steps.front().ok_or(InputError::HandOver)?;
+
+        match step {
+            Step::Hole(n) | Step::Stud(n) | Step::Community(n) | Step::OpponentStud { n, .. } => {
+                let cards = self.parse_new_cards(line, n)?;
+                let left = n - cards.len() as u8;
+                match step {
+                    Step::Hole(_) => self.game.player.hole.add_cards(cards),
+                    Step::Stud(_) => self.game.player.stud.add_cards(cards),
+                    Step::Community(_) => self.game.community.add_cards(cards),
+                    Step::OpponentStud { opponent, .. } => {
+                        self.game.opponents[opponent].stud.add_cards(cards)
+                    }
+                    _ => unreachable!(),
+                }
+                self.steps.pop_front();
+                if left > 0 {
+                    self.steps.push_front(match step {
+                        Step::Hole(_) => Step::Hole(left),
+                        Step::Stud(_) => Step::Stud(left),
+                        Step::Community(_) => Step::Community(left),
+                        Step::OpponentStud { opponent, .. } => {
+                            Step::OpponentStud { opponent, n: left }
+                        }
+                        _ => unreachable!(),
+                    });
+                }
+            }
+            Step::Discard { max } => {
+                let cards = parse_cards(line)?;
+                if cards.len() > max as usize {
+                    return Err(InputError::TooManyCards {
+                        expected: max,
+                        got: cards.len(),
+                    });
+                }
+                let hole = &mut self.game.player.hole;
+                if let Some(c) = cards.iter().find(|c| !hole.drawn.contains(c)) {
+                    return Err(InputError::NotInHand(card_str(*c)));
+                }
+
+                hole.drawn.retain(|c| !cards.contains(c));
+                hole.undrawn += cards.len() as u8;
+                self.game.player.exchanged.extend(cards.iter().copied());
+                self.game.last_exchange = Some(max);
+                if !self.game.exchanges.is_empty() {
+                    self.game.exchanges.remove(0);
+                }
+                self.steps.pop_front();
+                if !cards.is_empty() {
+                    self.steps.push_front(Step::Hole(cards.len() as u8));
+                }
+            }
+            Step::OpponentDraw { opponent, max } => {
+                let n = line
+                    .trim()
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|n| *n <= max)
+                    .ok_or_else(|| InputError::BadDrawCount {
+                        max,
+                        got: line.trim().to_owned(),
+                    })?;
+                self.game.opponents[opponent].exchanged = n as usize;
+                self.steps.pop_front();
+            }
+            Step::DiscardHole(n) => {
+                let cards = parse_cards(line)?;
+                if cards.len() != n as usize {
+                    return Err(InputError::WrongCardCount {
+                        expected: n,
+                        got: cards.len(),
+                    });
+                }
+                let hole = &self.game.player.hole;
+                if let Some(c) = cards.iter().find(|c| !hole.drawn.contains(c)) {
+                    return Err(InputError::NotInHand(card_str(*c)));
+                }
+
+                self.game.discard(&cards)?;
+                self.steps.pop_front();
+            }
+        }
+
+        if self.steps.is_empty() {
+            self.round += 1;
+            self.start_round();
+        }
+        Ok(())
+    }
+
+    /// Parse up to `max` cards which have not been seen yet.
+    fn parse_new_cards(&self, line: &str, max: u8) -> Result<HandVec, InputError> {
+        let cards = parse_cards(line)?;
+        if cards.len() > max as usize {
+            return Err(InputError::TooManyCards {
+                expected: max,
+                got: cards.len(),
+            });
+        }
+
+        let deck = self.game.sample_deck();
+        for (i, c) in cards.iter().enumerate() {
+            if !deck.contains(c) || cards[..i].contains(c) {
+                return Err(InputError::CardNotInDeck(card_str(*c)));
+            }
+        }
+        Ok(cards)
+    }
+
+    /// Cards we know will be part of our final hand.
+    pub fn known_cards(&self) -> HandVec {
+        let player = &self.game.player;
+        player
+            .hole
+            .drawn
+            .iter()
+            .chain(player.stud.drawn.iter())
+            .chain(self.game.community.drawn.iter())
+            .copied()
+            .collect()
+    }
+
+    /// Number of cards still to come in our final hand.
+    pub fn undrawn_cards(&self) -> u8 {
+        let player = &self.game.player;
+        player.hole.undrawn + player.stud.undrawn + self.game.community.undrawn
+    }
+
+    /// Recompute [`LiveState::analysis`] for the current state of the game.
+    pub fn analyze(&mut self) {
+        let deck = self.game.sample_deck();
+        let mut known = self.known_cards();
+        let mut undrawn = self.undrawn_cards();
+
+        // Before a discard round, once our hole cards are dealt, we plan on
+        // keeping the best of them. Until then, only the cards we will keep
+        // are dealt.
+        let hole = &self.game.player.hole;
+        let pending = self.game.pending_discards();
+        let planned = match hole.undrawn {
+            0 if pending > 0 && !self.game.opponents.is_empty() => {
+                let params = DiscardParams {
+                    game: &self.game,
+                    opponent: None,
+                    seed: None,
+                };
+                params
+                    .advise(self.samples)
+                    .ok()
+                    .and_then(|options| options.into_iter().next())
+            }
+            _ => None,
+        };
+        match &planned {
+            Some(option) => known.retain(|c| !option.discard.contains(c)),
+            None => undrawn -= pending.min(hole.undrawn),
+        }
+
+        // Once our hand is complete, we make the best exchange for it.
+        let hole = &self.game.player.hole;
+        let policy = match self.game.exchanges.first() {
+            Some(max) if hole.undrawn == 0 => {
+                DrawPolicy::best_mean(&hole.drawn, *max, &deck, self.samples)
+            }
+            _ => DrawPolicy::Heuristic,
+        };
+        let exchanges = Exchanges::new(self.game.exchanges.iter().copied(), policy);
+
+        let equity = match (&planned, self.game.opponents.is_empty()) {
+            (Some(option), _) => Some(option.equity),
+            (None, true) => None,
+            (None, false) => {
+                let mut showdown = Showdown::from_game(&self.game, &deck).with_pot(self.pot);
+                showdown.contenders[0].exchanges = exchanges.clone();
+                // Opponents who have yet to say how many cards they drew
+                // still have this round's exchange ahead of them.
+                for step in &self.steps {
+                    if let Step::OpponentDraw { opponent, max } = *step {
+                        let contender = &mut showdown.contenders[opponent + 1];
+                        contender.drew = None;
+                        contender.exchanges = Exchanges::new(
+                            std::iter::once(max).chain(self.game.exchanges.iter().copied()),
+                            DrawPolicy::Heuristic,
+                        );
+                    }
+                }
+                Some(showdown.equity(self.samples)[0])
+            }
+        };
+
+        let summary = (known.len() + undrawn as usize >= 5).then(|| {
+            let params = SimParams {
+                player: PartialHand {
+                    drawn: known.clone(),
+                    undrawn,
+                },
+                sample_deck: &deck,
+                exchanges: exchanges.clone(),
+                seed: None,
+                deck: self.game.deck,
+                wild: Default::default(),
+            };
+            params.summarize(self.samples, 40)
+        });
+
+        let outs = match undrawn {
+            0 => vec![],
+            _ => outs(&known, self.game.deck, &deck),
+        };
+
+        let discard = match &exchanges.policy {
+            _ if planned.is_some() => planned.map(|o| o.discard),
+            DrawPolicy::Keep(hold) => Some(
+                hole.drawn
+                    .iter()
+                    .filter(|c| !hold.contains(c))
+                    .copied()
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        self.analysis = Analysis {
+            equity,
+            summary,
+            outs,
+            discard,
+        };
+    }
+}
+
+fn parse_cards(line: &str) -> Result<HandVec, InputError> {
+    line.split_whitespace()
+        .map(|s| {
+            s.parse::<Card>()
+                .map(SCard::from)
+                .map_err(|e| InputError::CouldNotParseCard(s.to_owned(), e))
+        })
+        .collect()
+}
+
+fn card_str(c: SCard) -> String {
+    Card::from(c).rank_suit_string()
+}
+
+fn cards_str(known: &[SCard], unknown: usize) -> String {
+    known
+        .iter()
+        .map(|c| card_str(*c))
+        .chain(std::iter::repeat("??".to_owned()).take(unknown))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn live(args: LiveArgs) -> anyhow::Result<()> {
+    let mut state = LiveState::new(args.template.rounds(), args.opponents, args.samples);
+    state.game.deck = args.template.deck();
+    state.pot = args.template.pot();
+
+    eprintln!("Building lookup table");
+    state.analyze();
+
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+    let result = run(&mut terminal, &mut state);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    result
+}
+
+fn run<B: Backend>(terminal: &mut Terminal<B>, state: &mut LiveState) -> anyhow::Result<()> {
+    let mut input = String::new();
+    let mut message = None;
+
+    loop {
+        terminal.draw(|f| render(f, state, &input, message.as_deref()))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => match state.submit(&input) {
+                Ok(()) => {
+                    input.clear();
+                    terminal.draw(|f| render(f, state, &input, Some("Simulating...")))?;
+                    state.analyze();
+                    message = None;
+                }
+                Err(e) => message = Some(e.to_string()),
+            },
+            _ => (),
+        }
+    }
+}
+
+fn render(f: &mut Frame, state: &LiveState, input: &str, message: Option<&str>) {
+    let game = &state.game;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(game.opponents.len() as u16 + 5),
+            Constraint::Min(14),
+            Constraint::Length(4),
+        ])
+        .split(f.size());
+
+    // Table: everything dealt so far.
+    let schedule = state
+        .rounds
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let name = r.name().unwrap_or("?").to_owned();
+            match i == state.round {
+                true => Span::styled(name, Style::default().add_modifier(Modifier::REVERSED)),
+                false => Span::raw(name),
+            }
+        })
+        .flat_map(|s| [s, Span::raw("  ")])
+        .collect::<Vec<_>>();
+
+    let mut table = vec![
+        Line::from(schedule),
+        Line::from(format!(
+            "You:        {}",
+            cards_str(
+                &game
+                    .player
+                    .hole
+                    .drawn
+                    .iter()
+                    .chain(game.player.stud.drawn.iter())
+                    .copied()
+                    .collect::<Vec<_>>(),
+                (game.player.hole.undrawn + game.player.stud.undrawn) as usize
+            )
+        )),
+        Line::from(format!(
+            "Community:  {}",
+            cards_str(&game.community.drawn, game.community.undrawn as usize)
+        )),
+    ];
+    for (i, o) in game.opponents.iter().enumerate() {
+        let mut line = format!(
+            "Opponent {}: {}",
+            i + 1,
+            cards_str(&[], o.hole.total_cards() as usize)
+        );
+        if !o.stud.drawn.is_empty() || o.stud.undrawn > 0 {
+            line += &format!(" | {}", cards_str(&o.stud.drawn, o.stud.undrawn as usize));
+        }
+        if o.exchanged > 0 {
+            line += &format!(" (drew {})", o.exchanged);
+        }
+        table.push(Line::from(line));
+    }
+    f.render_widget(
+        Paragraph::new(table).block(Block::default().borders(Borders::ALL).title("Table")),
+        chunks[0],
+    );
+
+    // Analysis: equity, outs and hand distribution.
+    let analysis = &state.analysis;
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    let mut odds = vec![];
+    if let Some(e) = analysis.equity {
+        odds.push(Line::from(format!(
+            "Equity {:>6.2}% ±{:.2}%  (win {:.2}%, tie {:.2}%)",
+            e.equity * 100.0,
+            e.margin(Z_95) * 100.0,
+            e.win * 100.0,
+            e.tie * 100.0
+        )));
+        odds.push(Line::from(bar(e.equity, 30)));
+        if e.unconditioned > 0.0 {
+            odds.push(Line::from(format!(
+                "Hands could not match their exchanges in {:.1}% of samples",
+                e.unconditioned * 100.0
+            )));
+        }
+    }
+    odds.push(Line::from(format!(
+        "Outs ({}): {}",
+        analysis.outs.len(),
+        cards_str(&analysis.outs, 0)
+    )));
+    if let Some(discard) = &analysis.discard {
+        let what = match game.exchanges.is_empty() {
+            true => "discard",
+            false => "exchange",
+        };
+        odds.push(Line::from(match discard.is_empty() {
+            true => format!("Best {what}: stand pat"),
+            false => format!("Best {what}: throw {}", cards_str(discard, 0)),
+        }));
+    }
+    f.render_widget(
+        Paragraph::new(odds)
+            .wrap(ratatui::widgets::Wrap { trim: true })
+            .block(Block::default().borders(Borders::ALL).title("Equity")),
+        columns[0],
+    );
+
+    let mut distribution = vec![];
+    if let Some(summary) = &analysis.summary {
+        for category in HandCategory::iter().rev() {
+            let p = summary.categories[category.index()];
+            distribution.push(Line::from(format!(
+                "{:<16}{:>6.2}% {}",
+                category.to_string(),
+                p * 100.0,
+                bar(p, 20)
+            )));
+        }
+        distribution.push(Line::from(""));
+        distribution.push(Line::from(format!(
+            "Quantile u={:.3} s={:.3} p50={:.3}",
+            summary.mean, summary.stddev, summary.p50
+        )));
+        distribution.push(Line::from(format!("|{}|", sparkline(&summary.histogram))));
+    }
+    f.render_widget(
+        Paragraph::new(distribution)
+            .block(Block::default().borders(Borders::ALL).title("Final hand")),
+        columns[1],
+    );
+
+    // Input line.
+    let mut prompt = vec![Line::from(state.prompt()), Line::from(format!("> {input}"))];
+    if let Some(m) = message {
+        prompt[0] = Line::from(format!("{}  [{m}]", state.prompt()));
+    }
+    f.render_widget(
+        Paragraph::new(prompt).block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use poker::{Rank, Suit};
+    use poker_assistant::game_repr::{crazy_pineapple, five_card_draw, holdem};
+
+    use super::*;
+
+    #[test]
+    fn holdem_flow() {
+        let mut state = LiveState::new(holdem(), 2, 100);
+
+        assert_eq!(state.steps, [Step::Hole(2)]);
+        state.submit("As").unwrap();
+        state.submit("Kh").unwrap();
+        assert_eq!(state.round, 1);
+        assert_eq!(state.steps, [Step::Community(3)]);
+        assert_eq!(
+            state.submit("Kh"),
+            Err(InputError::CardNotInDeck("Kh".into()))
+        );
+        state.submit("2c 3c 4c").unwrap();
+        state.submit("5c").unwrap();
+        state.submit("6c").unwrap();
+
+        assert!(state.is_over());
+        assert_eq!(state.known_cards().len(), 7);
+        assert_eq!(state.game.opponents[1].hole.total_cards(), 2);
+    }
+
+    #[test]
+    fn draw_flow() {
+        let mut state = LiveState::new(five_card_draw(), 1, 100);
+
+        state.submit("As Ah 2c 7d 9s").unwrap();
+        assert_eq!(
+            state.steps,
+            [
+                Step::Discard { max: 3 },
+                Step::OpponentDraw {
+                    opponent: 0,
+                    max: 3
+                }
+            ]
+        );
+        assert_eq!(state.submit("Kh"), Err(InputError::NotInHand("Kh".into())));
+        state.submit("2c 7d").unwrap();
+        assert_eq!(state.steps.front(), Some(&Step::Hole(2)));
+        state.submit("Ad Ac").unwrap();
+        state.submit("3").unwrap();
+
+        assert!(state.is_over());
+        assert_eq!(state.game.opponents[0].exchanged, 3);
+        assert!(state
+            .game
+            .player
+            .exchanged
+            .contains(&SCard::new(Rank::Two, Suit::Clubs)));
+    }
+
+    #[test]
+    fn analysis_works() {
+        let mut state = LiveState::new(holdem(), 1, 1000);
+        state.submit("As Ah").unwrap();
+        state.submit("Ad Kc 2s").unwrap();
+
+        state.analyze();
+
+        let equity = state.analysis.equity.unwrap();
+        assert!(equity.equity > 0.8);
+        let summary = state.analysis.summary.unwrap();
+        assert_eq!(summary.categories[HandCategory::HighCard.index()], 0.0);
+        assert!(!state.analysis.outs.is_empty());
+    }
+
+    #[test]
+    fn draw_analysis_plans_the_exchange() {
+        let mut state = LiveState::new(five_card_draw(), 1, 1000);
+        state.submit("As Ah Ad 7c 2s").unwrap();
+
+        state.analyze();
+
+        let discard = state.analysis.discard.clone().unwrap();
+        assert_eq!(discard.len(), 2);
+        // Drawing two to trips makes a full house or quads a tenth of the
+        // time.
+        let summary = state.analysis.summary.as_ref().unwrap();
+        let improved = summary.categories[HandCategory::FullHouse.index()]
+            + summary.categories[HandCategory::FourOfAKind.index()];
+        assert!(improved > 0.06, "{improved}");
+        assert!(!summary.exhaustive);
+
+        state.submit("7c 2s").unwrap();
+        assert!(state.game.exchanges.is_empty());
+        state.analyze();
+        assert_eq!(state.analysis.discard, None);
+    }
+
+    #[test]
+    fn crazy_pineapple_flow() {
+        let mut state = LiveState::new(crazy_pineapple(), 2, 1000);
+
+        state.submit("As Ah 7c").unwrap();
+        state.submit("Ad Kd 2s").unwrap();
+        assert_eq!(state.steps, [Step::DiscardHole(1)]);
+
+        state.analyze();
+        let best = SCard::new(Rank::Seven, Suit::Clubs);
+        assert_eq!(state.analysis.discard.as_deref(), Some(&[best][..]));
+        let summary = state.analysis.summary.as_ref().unwrap();
+        assert_eq!(summary.categories[HandCategory::Pair.index()], 0.0);
+
+        assert_eq!(
+            state.submit("As 7c"),
+            Err(InputError::WrongCardCount {
+                expected: 1,
+                got: 2
+            })
+        );
+        assert_eq!(state.submit("Kh"), Err(InputError::NotInHand("Kh".into())));
+        state.submit("7c").unwrap();
+        assert_eq!(state.steps, [Step::Community(1)]);
+        assert_eq!(state.game.opponents[0].hole.total_cards(), 2);
+        assert!(state.game.dead.contains(&best));
+        state.submit("3h").unwrap();
+        state.submit("4h").unwrap();
+
+        assert!(state.is_over());
+        assert_eq!(state.known_cards().len(), 7);
+    }
+}
@@ -1,27 +1,39 @@
-use std::{
-    cell::RefCell,
-    fs::{read_to_string, File},
-    io::Write,
-    path::PathBuf,
-};
+use std::{fs::File, io::Write, path::PathBuf};
 
+use board::{board, BoardArgs};
 use clap::Parser;
-use compact_poker::SCard;
-use dsl::{evaluate_directives, parse_program_from_str};
-use plotters::{
-    backend::BitMapBackend,
-    chart::ChartBuilder,
-    coord::{combinators::IntoLinspace, ranged1d::IntoSegmentedCoord},
-    drawing::IntoDrawingArea,
-    series::Histogram,
-    style::{Color, RED, WHITE},
+use draw::{draw, DrawArgs};
+use live::{live, LiveArgs};
+use ofc::{ofc, OfcArgs};
+use pineapple::{pineapple, PineappleArgs};
+use poker_assistant::{
+    game_repr::{self, Round},
+    prediction::{deck::Deck, low::Pot},
 };
-use poker_assistant::prediction::{model::PartialHand, montecarlo::SimParams};
-use poker_assistant_lookup::N_HANDS;
-use rand::{rngs::SmallRng, SeedableRng};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-
+use repl::{repl, ReplArgs};
+use scenario::{convert, ConvertArgs};
+use serve::{serve, ServeArgs};
+use session::{session, SessionArgs};
+use sim::{simulate, SimulateArgs};
+use strength::{strength, StrengthArgs};
+use stud::{stud, StudArgs};
+
+pub mod board;
+pub mod cards;
+pub mod draw;
 pub mod dsl;
+pub mod live;
+pub mod ofc;
+pub mod pineapple;
+pub mod repl;
+pub mod scenario;
+pub mod serve;
+pub mod session;
+pub mod sim;
+pub mod strength;
+pub mod stud;
+pub mod template;
+pub mod textplot;
 
 /// Poker assistant TUI.
 #[derive(Parser, Clone)]
@@ -39,20 +51,40 @@ pub enum Subcommand {
 
     /// Generate a template file.
     Template(TemplateArgs),
-}
 
-#[derive(clap::Args, Clone)]
-pub struct SimulateArgs {
-    /// File to simulate with
-    pub file: PathBuf,
+    /// Record played hands and report results.
+    Session(SessionArgs),
 
-    /// Output file
-    #[clap(short, long)]
-    pub out: PathBuf,
+    /// Play a hand interactively, entering cards as they are dealt.
+    Live(LiveArgs),
+
+    /// Apply directives interactively and query equities.
+    Repl(ReplArgs),
+
+    /// Serve simulations over a local HTTP JSON API.
+    Serve(ServeArgs),
+
+    /// Convert a scenario between S-expressions, JSON, YAML and TOML.
+    Convert(ConvertArgs),
 
-    /// Number of samples to simulate
-    #[clap(short = 'n', default_value = "100000")]
-    pub samples: u64,
+    /// Report hand strength, potential and effective hand strength.
+    Strength(StrengthArgs),
+
+    /// Describe the texture of a board and the strongest hands on it.
+    Board(BoardArgs),
+
+    /// Rank the ways of exchanging cards in a five-card draw hand.
+    Draw(DrawArgs),
+
+    /// Report equity and live cards in a stud hand from the upcards showing.
+    Stud(StudArgs),
+
+    /// Rank which hole card to throw away in Pineapple or Crazy Pineapple.
+    Pineapple(PineappleArgs),
+
+    /// Score an open-face Chinese poker hand, or rank where to place the
+    /// next cards.
+    Ofc(OfcArgs),
 }
 
 #[derive(clap::Args, Clone)]
@@ -66,7 +98,7 @@ pub struct TemplateArgs {
     pub template: BuiltinTemplates,
 }
 
-#[derive(clap::ValueEnum, Clone)]
+#[derive(clap::ValueEnum, Clone, Copy)]
 pub enum BuiltinTemplates {
     #[clap(name = "5-card-draw")]
     FiveCardDraw,
@@ -77,27 +109,84 @@ pub enum BuiltinTemplates {
     #[clap(name = "7-card-stud")]
     SevenCardStud,
 
+    #[clap(name = "razz")]
+    Razz,
+
+    #[clap(name = "stud-8")]
+    StudEight,
+
     #[clap(name = "texas-holdem")]
     TexasHoldem,
+
+    #[clap(name = "short-deck-holdem")]
+    ShortDeckHoldem,
+
+    #[clap(name = "pineapple")]
+    Pineapple,
+
+    #[clap(name = "crazy-pineapple")]
+    CrazyPineapple,
 }
 
 impl BuiltinTemplates {
-    pub fn file_contents(&self) -> &'static str {
+    /// Scenario for this game, generated from its round schedule.
+    pub fn file_contents(&self) -> String {
+        template::from_rounds(&self.rounds(), self.deck(), self.pot())
+    }
+
+    /// Round schedule of this game.
+    pub fn rounds(&self) -> Vec<Round> {
+        match self {
+            BuiltinTemplates::FiveCardDraw => game_repr::five_card_draw(),
+            BuiltinTemplates::FiveCardStud => game_repr::five_card_stud(),
+            BuiltinTemplates::SevenCardStud => game_repr::seven_card_stud(),
+            BuiltinTemplates::Razz => game_repr::razz(),
+            BuiltinTemplates::StudEight => game_repr::stud_eight(),
+            BuiltinTemplates::TexasHoldem => game_repr::holdem(),
+            BuiltinTemplates::ShortDeckHoldem => game_repr::short_deck_holdem(),
+            BuiltinTemplates::Pineapple => game_repr::pineapple(),
+            BuiltinTemplates::CrazyPineapple => game_repr::crazy_pineapple(),
+        }
+    }
+
+    /// Deck this game is dealt from.
+    pub fn deck(&self) -> Deck {
         match self {
-            BuiltinTemplates::FiveCardDraw => include_str!("templates/5-card-draw.sexp"),
-            BuiltinTemplates::FiveCardStud => include_str!("templates/5-card-stud.sexp"),
-            BuiltinTemplates::SevenCardStud => include_str!("templates/7-card-stud.sexp"),
-            BuiltinTemplates::TexasHoldem => include_str!("templates/texas-holdem.sexp"),
+            BuiltinTemplates::ShortDeckHoldem => Deck::Short,
+            _ => Deck::Standard,
+        }
+    }
+
+    /// How the pot of this game is won.
+    pub fn pot(&self) -> Pot {
+        match self {
+            BuiltinTemplates::Razz => Pot::Low,
+            BuiltinTemplates::StudEight => Pot::HighLow,
+            _ => Pot::High,
         }
     }
 }
 
+/// Arguments of a subcommand parsed from `argv` as on the command line, so
+/// tests start from the defaults the CLI declares.
+#[cfg(test)]
+pub fn parse_args<A: clap::Args>(argv: &[&str]) -> A {
+    let command = A::augment_args(clap::Command::new("test"));
+    let matches = command
+        .try_get_matches_from(std::iter::once("test").chain(argv.iter().copied()))
+        .unwrap();
+    A::from_arg_matches(&matches).unwrap()
+}
+
 fn main() {
     let args = Args::parse();
 
     match args.subcommand {
         Subcommand::Simulate(args) => {
-            simulate(args).expect("Failed to run simulation");
+            if let Err(e) = simulate(args) {
+                eprintln!("Failed to run simulation: {e}");
+                std::process::exit(1);
+            }
         }
         Subcommand::Template(args) => {
             if let Some(p) = args.out {
@@ -109,134 +198,71 @@ fn main() {
                 println!("{}", args.template.file_contents());
             }
         }
-    }
-}
-
-fn simulate(args: SimulateArgs) -> anyhow::Result<()> {
-    std::thread_local! {
-        static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
-    }
-
-    let src = read_to_string(args.file)?;
-    let program = parse_program_from_str(&src)?;
-    let eval = evaluate_directives(&program)?;
-
-    let mut deck = SCard::deck().collect::<Vec<_>>();
-    deck.retain(|c| !eval.discarded.contains(c));
-
-    let sims = eval
-        .hands
-        .into_values()
-        .filter(|p| p.should_plot)
-        .map(|p| {
-            (
-                p.name,
-                SimParams {
-                    player: PartialHand {
-                        drawn: p.known_cards.into_iter().collect(),
-                        undrawn: p.n_holes as u8,
-                    },
-                    sample_deck: &deck,
-                },
-            )
-        })
-        .collect::<Vec<_>>();
-
-    let root = BitMapBackend::new(&args.out, (640, 240 * sims.len() as u32)).into_drawing_area();
-    root.fill(&WHITE)?;
-
-    let regions = root.split_evenly((sims.len(), 1));
-
-    for (region, (name, sim_params)) in regions.iter().zip(&sims) {
-        let n_samples = sim_params.n_possibilities().min(args.samples);
-        let mut raw_results = match args.samples {
-            n if n > sim_params.n_possibilities() => {
-                eprintln!(
-                    "Simulating {} (all {} possibilities)",
-                    name,
-                    sim_params.n_possibilities()
-                );
-
-                sim_params
-                    .run_exhaustive()
-                    .into_par_iter()
-                    .map(|result| result.score)
-                    .collect::<Vec<_>>()
+        Subcommand::Session(args) => {
+            if let Err(e) = session(args) {
+                eprintln!("Failed to run session command: {e:#}");
+                std::process::exit(1);
             }
-            n => {
-                eprintln!(
-                    "Simulating {} ({}/{} possibilities)",
-                    name,
-                    n,
-                    sim_params.n_possibilities()
-                );
-
-                (0..n)
-                    .into_par_iter()
-                    .map(|_| RNG.with_borrow_mut(|mut rng| sim_params.run_sample(&mut rng)))
-                    .map(|result| result.score)
-                    .collect::<Vec<_>>()
+        }
+        Subcommand::Live(args) => {
+            if let Err(e) = live(args) {
+                eprintln!("Failed to run live session: {e:#}");
+                std::process::exit(1);
             }
-        };
-        raw_results.sort();
-
-        let results = raw_results
-            .iter()
-            .map(|sr| *sr as f32 / N_HANDS as f32)
-            .collect::<Vec<_>>();
-
-        let histogram = collect_histogram(100, results.iter().copied());
-        let max = *histogram.iter().max().unwrap() as f32 / n_samples as f32;
-        let mean = results.iter().copied().sum::<f32>() / results.len() as f32;
-        let var = results.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / results.len() as f32;
-        let p50 = results[results.len() / 2];
-
-        let mut chart = ChartBuilder::on(&region)
-            .x_label_area_size(35)
-            .y_label_area_size(40)
-            .margin(10)
-            .caption(
-                format!(
-                    "{} (u={:.04}, s={:.04}, p50={:.04})",
-                    name,
-                    mean,
-                    var.sqrt(),
-                    p50
-                ),
-                ("sans-serif", 25.0),
-            )
-            .build_cartesian_2d(
-                (0f32..1f32).step(0.01).use_round().into_segmented(),
-                0f32..max,
-            )?;
-
-        chart
-            .configure_mesh()
-            .disable_x_mesh()
-            .bold_line_style(WHITE.mix(0.3))
-            .y_desc("Density")
-            .x_desc("Quantile")
-            .axis_desc_style(("sans-serif", 15))
-            .draw()?;
-
-        let histogram = Histogram::vertical(&chart)
-            .style(RED.mix(0.8).filled())
-            .margin(0)
-            .data(results.iter().map(|s| (*s, 1.0 / n_samples as f32)));
-
-        chart.draw_series(histogram)?;
-    }
-
-    Ok(())
-}
-
-pub fn collect_histogram(n_bins: usize, values: impl IntoIterator<Item = f32>) -> Vec<usize> {
-    let mut bins = vec![0; n_bins];
-
-    for v in values {
-        let bin = (v * n_bins as f32) as usize;
-        bins[bin] += 1;
+        }
+        Subcommand::Repl(args) => {
+            if let Err(e) = repl(args) {
+                eprintln!("Failed to run REPL: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Serve(args) => {
+            if let Err(e) = serve(args) {
+                eprintln!("Failed to run server: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Convert(args) => {
+            if let Err(e) = convert(args) {
+                eprintln!("Failed to convert scenario: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Strength(args) => {
+            if let Err(e) = strength(args) {
+                eprintln!("Failed to evaluate hand strength: {e}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Board(args) => {
+            if let Err(e) = board(args) {
+                eprintln!("Failed to analyse board: {e}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Draw(args) => {
+            if let Err(e) = draw(args) {
+                eprintln!("Failed to advise on the draw: {e}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Stud(args) => {
+            if let Err(e) = stud(args) {
+                eprintln!("Failed to evaluate the stud hand: {e}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Pineapple(args) => {
+            if let Err(e) = pineapple(args) {
+                eprintln!("Failed to advise on the discard: {e}");
+                std::process::exit(1);
+            }
+        }
+        Subcommand::Ofc(args) => {
+            if let Err(e) = ofc(args) {
+                eprintln!("Failed to evaluate the OFC hand: {e}");
+                std::process::exit(1);
+            }
+        }
     }
-
-    bins
 }
@@ -0,0 +1,243 @@
+use anyhow::bail;
+use compact_poker::SCard;
+use poker_assistant::prediction::{
+    model::{Game, HandVec},
+    ofc::{Arrangement, Placement, PlacementParams, Row},
+    stats::Z_95,
+};
+use serde::Serialize;
+
+use crate::cards::{check_distinct, parse_cards};
+
+#[derive(clap::Args, Clone)]
+pub struct OfcArgs {
+    /// Our rows from the top down, separated by slashes, e.g.
+    /// "Ah Ad / Ks Kd Kc 6c / Qs Qd Qc 8h 8d".
+    #[clap(default_value = "/ /")]
+    pub arrangement: String,
+
+    /// Cards to place now, e.g. "Ac". If not provided, the arrangement must
+    /// be complete and is scored.
+    #[clap(short = 'c', long, default_value = "")]
+    pub place: String,
+
+    /// Rows of an opponent, written like ours. Repeat for each opponent.
+    #[clap(short = 'p', long = "opponent")]
+    pub opponents: Vec<String>,
+
+    /// Any other cards seen and out of play.
+    #[clap(short, long, default_value = "")]
+    pub dead: String,
+
+    /// Number of samples to simulate for each placement
+    #[clap(short = 'n', default_value = "2000")]
+    pub samples: u64,
+
+    /// Seed for reproducible results.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Number of the best placements to show.
+    #[clap(short, long, default_value = "5")]
+    pub top: usize,
+
+    /// Print the result as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Scores of a complete arrangement.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OfcReport {
+    /// Why the arrangement is invalid, if it is.
+    pub foul: Option<String>,
+
+    /// Royalty of each row from the top down, before fouling.
+    pub royalties: [u32; 3],
+
+    /// Points against each opponent, if they are complete too.
+    pub points: Vec<Option<i32>>,
+}
+
+struct Table {
+    ours: Arrangement,
+    place: HandVec,
+    opponents: Vec<Arrangement>,
+    dead: HandVec,
+}
+
+impl Table {
+    fn parse(args: &OfcArgs) -> anyhow::Result<Self> {
+        let table = Table {
+            ours: args.arrangement.parse()?,
+            place: parse_cards(&args.place)?,
+            opponents: args
+                .opponents
+                .iter()
+                .map(|o| o.parse())
+                .collect::<Result<_, _>>()?,
+            dead: parse_cards(&args.dead)?,
+        };
+        let seen = table.seen().collect::<Vec<_>>();
+        check_distinct(&seen)?;
+        Ok(table)
+    }
+
+    fn seen(&self) -> impl Iterator<Item = SCard> + '_ {
+        self.ours
+            .cards()
+            .chain(self.place.iter().copied())
+            .chain(self.opponents.iter().flat_map(|o| o.cards()))
+            .chain(self.dead.iter().copied())
+    }
+}
+
+/// Scores of our complete arrangement.
+pub fn report(args: &OfcArgs) -> anyhow::Result<OfcReport> {
+    let table = Table::parse(args)?;
+    if !table.ours.is_complete() {
+        bail!("The arrangement is not complete. Give the cards to place with --place");
+    }
+
+    Ok(OfcReport {
+        foul: table.ours.validate().err().map(|e| e.to_string()),
+        royalties: Row::ALL.map(|r| table.ours.royalty(r)),
+        points: table
+            .opponents
+            .iter()
+            .map(|o| o.is_complete().then(|| table.ours.points(o)))
+            .collect(),
+    })
+}
+
+/// Every placement of the cards, best first.
+pub fn advise(args: &OfcArgs) -> anyhow::Result<Vec<Placement>> {
+    let table = Table::parse(args)?;
+    let space = table.ours.space().iter().sum::<usize>();
+    if table.place.len() > space {
+        bail!(
+            "There is room for {space} more cards, but {} were given",
+            table.place.len()
+        );
+    }
+
+    let mut game = Game::default();
+    game.dead.extend(table.seen());
+    let deck = game.sample_deck();
+    let needed = space - table.place.len()
+        + table
+            .opponents
+            .iter()
+            .map(|o| o.space().iter().sum::<usize>())
+            .sum::<usize>();
+    if needed > deck.len() {
+        bail!(
+            "Filling every row needs {needed} cards, but only {} are left",
+            deck.len()
+        );
+    }
+
+    let params = PlacementParams {
+        arrangement: table.ours,
+        cards: table.place,
+        opponents: table.opponents,
+        sample_deck: &deck,
+        seed: args.seed,
+    };
+    Ok(params.advise(args.samples))
+}
+
+pub fn ofc(args: OfcArgs) -> anyhow::Result<()> {
+    if args.place.trim().is_empty() {
+        let report = report(&args)?;
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+
+        if let Some(foul) = &report.foul {
+            println!("Fouled: {foul}");
+        }
+        for (row, royalty) in Row::ALL.iter().zip(report.royalties) {
+            println!("{:<8}royalty {royalty}", row.to_string());
+        }
+        for (i, points) in report.points.iter().enumerate() {
+            match points {
+                Some(p) => println!("Opponent {}: {p:+} points", i + 1),
+                None => println!("Opponent {}: not complete", i + 1),
+            }
+        }
+        return Ok(());
+    }
+
+    let options = advise(&args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&options)?);
+        return Ok(());
+    }
+
+    for (i, o) in options.iter().take(args.top).enumerate() {
+        println!(
+            "{:>2}. {}  value {:+.2}±{:.2}  foul {:.1}%  royalties {:.2}",
+            i + 1,
+            o.arrangement,
+            o.value,
+            o.stderr * Z_95,
+            o.foul * 100.0,
+            o.royalties
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(report(&parse_args(&["Ah / /"])).is_err());
+
+        let advise = |argv: &[&str]| advise(&parse_args(argv));
+        let all_clubs = "2c 3c 4c 5c 6c 7c 8c 9c Tc Jc Qc Kc";
+        assert!(advise(&["Ah / /", "-c", "Ah"]).is_err());
+        assert!(advise(&["Ah Kh / /", "-c", all_clubs]).is_err());
+        assert!(advise(&["Ah Kh Qh Jh / /", "-c", "2c"]).is_err());
+        assert!(advise(&["Ah / /", "-c", "2c", "-d", "2c"]).is_err());
+    }
+
+    #[test]
+    fn scores_complete_arrangements() {
+        let scoop = parse_args(&[
+            "Qh Qd 2c / 8s 8c 8h 3d 4d / Ks Qs Js Ts 9s",
+            "-p",
+            "2h 3h 5c / 6s 7c 8d Td Jh / Ac Kc Qc Jc 9c",
+            "-p",
+            "/ /",
+        ]);
+
+        let report = report(&scoop).unwrap();
+
+        assert_eq!(report.foul, None);
+        assert_eq!(report.royalties, [7, 2, 15]);
+        assert_eq!(report.points, [Some(6 + 24 - 4), None]);
+    }
+
+    #[test]
+    fn places_the_card() {
+        let args = OfcArgs {
+            samples: 200,
+            seed: Some(1),
+            ..parse_args(&["Ah Ad / Ks Kd Kc 6c / Qs Qd Qc 8h 8d", "-c", "Ac"])
+        };
+
+        let options = advise(&args).unwrap();
+
+        assert_eq!(options.len(), 2);
+        assert_eq!(
+            options[0].arrangement.to_string(),
+            "Ah Ad / Ks Kd Kc 6c Ac / Qs Qd Qc 8h 8d"
+        );
+    }
+}
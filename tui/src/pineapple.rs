@@ -0,0 +1,149 @@
+use anyhow::bail;
+use poker_assistant::{
+    game_repr,
+    prediction::{
+        discard::{DiscardOption, DiscardParams},
+        model::Game,
+        range::Range,
+        stats::Z_95,
+    },
+};
+
+use crate::cards::{cards_string, check_distinct, parse_cards};
+
+#[derive(clap::Args, Clone)]
+pub struct PineappleArgs {
+    /// Our three hole cards, e.g. "As Ah 7c".
+    pub hole: String,
+
+    /// The flop, in Crazy Pineapple, where the discard comes after it.
+    #[clap(short, long, default_value = "")]
+    pub flop: String,
+
+    /// Number of opponents.
+    #[clap(short, long, default_value = "1")]
+    pub opponents: usize,
+
+    /// Range of each opponent's hole cards once they have discarded, e.g.
+    /// "AA, KK, AKs, AQo:0.5". If not provided, every hand is equally likely.
+    /// Only mean equities are reported; for the distribution of equity over
+    /// runouts against one range, see `strength --runouts` or
+    /// `sim --equity-against`.
+    #[clap(short, long)]
+    pub range: Option<Range>,
+
+    /// Number of samples to simulate for each option
+    #[clap(short = 'n', default_value = "20000")]
+    pub samples: u64,
+
+    /// Seed for reproducible results.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Print every option as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+/// Every option for the hole cards, best first.
+pub fn advise(args: &PineappleArgs) -> anyhow::Result<Vec<DiscardOption>> {
+    let hole = parse_cards(&args.hole)?;
+    let flop = parse_cards(&args.flop)?;
+    if hole.len() != 3 {
+        bail!("Expected 3 hole cards, got {}", hole.len());
+    }
+    let rounds = match flop.len() {
+        0 => game_repr::pineapple(),
+        3 => game_repr::crazy_pineapple(),
+        n => bail!("Expected a flop of 3 cards, got {n}"),
+    };
+    let known = hole.iter().chain(&flop).collect::<Vec<_>>();
+    check_distinct(known.iter().copied())?;
+    if args.opponents == 0 {
+        bail!("Expected at least one opponent");
+    }
+
+    let mut game = Game::from_rounds(args.opponents, &rounds);
+    game.player.hole.add_cards(hole);
+    game.community.add_cards(flop);
+    for o in game.opponents.iter_mut() {
+        o.hole.add_cards(3);
+    }
+    let deck = game.sample_deck();
+    if args.range.as_ref().is_some_and(|r| {
+        (0..deck.len()).all(|a| (a + 1..deck.len()).all(|b| r.weight(deck[a], deck[b]) == 0.0))
+    }) {
+        bail!("The range has no hands left in the deck");
+    }
+
+    let params = DiscardParams {
+        game: &game,
+        opponent: args.range.as_ref(),
+        seed: args.seed,
+    };
+    let options = params.advise(args.samples)?;
+    if let Some(o) = options.iter().find(|o| o.equity.unconditioned > 0.0) {
+        bail!(
+            "The range ran out of hands for some opponent in {:.1}% of samples keeping {}",
+            o.equity.unconditioned * 100.0,
+            cards_string(&o.hold)
+        );
+    }
+    Ok(options)
+}
+
+pub fn pineapple(args: PineappleArgs) -> anyhow::Result<()> {
+    let options = advise(&args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&options)?);
+        return Ok(());
+    }
+
+    for (i, o) in options.iter().enumerate() {
+        println!(
+            "{}. discard {}  keep {}  equity {:.2}%±{:.2}",
+            i + 1,
+            cards_string(&o.discard),
+            cards_string(&o.hold),
+            o.equity.equity * 100.0,
+            o.equity.margin(Z_95) * 100.0
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    #[test]
+    fn rejects_bad_input() {
+        let advise = |argv: &[&str]| advise(&parse_args(argv));
+
+        assert!(advise(&["As Ah"]).is_err());
+        assert!(advise(&["As Ah 7c", "-f", "Kd 2s"]).is_err());
+        assert!(advise(&["As Ah 7c", "-f", "As Kd 2s"]).is_err());
+        assert!(advise(&["As Ah 7c", "-o", "0"]).is_err());
+        assert!(advise(&["As Ah 7c", "-f", "Ad Kd 2s", "-r", "AA"]).is_err());
+    }
+
+    #[test]
+    fn throws_away_the_odd_card() {
+        let advise = |argv: &[&str]| {
+            advise(&PineappleArgs {
+                samples: 2000,
+                seed: Some(1),
+                ..parse_args(argv)
+            })
+        };
+
+        let options = advise(&["Ks Kh 2c"]).unwrap();
+
+        assert_eq!(options.len(), 3);
+        assert_eq!(cards_string(&options[0].discard), "2c");
+
+        let options = advise(&["Qs Js 8d", "-f", "Ts 9s 2h", "-o", "2"]).unwrap();
+        assert_eq!(cards_string(&options[0].discard), "8d");
+    }
+}
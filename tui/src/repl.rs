@@ -0,0 +1,254 @@
+use std::{
+    fs::File,
+    io::{stdin, stdout, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use lexpr::Value;
+use poker_assistant::prediction::{equity::Equity, stats::Z_95};
+
+use crate::{
+    dsl::{evaluate_directive, evaluate_directives, parse_directive, Directive, Evaluation},
+    scenario::{read_program, write_program, Format},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct ReplArgs {
+    /// File to load directives from before starting.
+    pub file: Option<PathBuf>,
+
+    /// Number of samples to simulate for equity queries
+    #[clap(short = 'n', default_value = "100000")]
+    pub samples: u64,
+}
+
+const HELP: &str = "\
+Enter directives such as (plot-cards self \"As Kh ? ? ?\"), or one of:
+  :show                   show all hands and discarded cards
+  :undo                   undo the last directive
+  :equity HAND [OTHERS]   equity of HAND against OTHERS, or every other plotted hand
+  :save FILE              save all directives to FILE, in the format of its extension
+  :help                   show this message
+  :quit                   exit";
+
+/// A session of directives applied one at a time.
+#[derive(Default)]
+pub struct Repl {
+    pub directives: Vec<Directive>,
+    pub eval: Evaluation,
+}
+
+impl Repl {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let directives = read_program(path)?;
+        let eval = evaluate_directives(&directives)?;
+        Ok(Self { directives, eval })
+    }
+
+    /// Parse and apply a single directive.
+    pub fn apply(&mut self, exp: &Value) -> anyhow::Result<()> {
+        let directive = parse_directive(exp)?;
+        evaluate_directive(&mut self.eval, &directive)?;
+        self.directives.push(directive);
+        Ok(())
+    }
+
+    /// Remove the last directive, returning it.
+    pub fn undo(&mut self) -> Option<Directive> {
+        let directive = self.directives.pop()?;
+        self.eval = evaluate_directives(&self.directives)
+            .expect("a prefix of valid directives must be valid");
+        Some(directive)
+    }
+
+    /// Equity of each of the named hands against each other. If only one hand
+    /// is named, it plays against every other plotted hand.
+    pub fn equity(&self, names: &[&str], samples: u64) -> anyhow::Result<Vec<(String, Equity)>> {
+        let mut names = names.to_vec();
+        if let [name] = *names.as_slice() {
+            names.extend(
+                self.eval
+                    .hands
+                    .values()
+                    .filter(|h| h.should_plot && h.name != name)
+                    .map(|h| h.name.as_str()),
+            );
+        }
+
+        let deck = self.eval.sample_deck();
+        let showdown = self.eval.showdown(&names, &deck)?;
+        Ok(names
+            .iter()
+            .map(|n| n.to_string())
+            .zip(showdown.equity(samples))
+            .collect())
+    }
+
+    /// Save in the format matching the file extension, or as S-expressions.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let format = Format::from_path(path).unwrap_or(Format::Sexp);
+        let output = write_program(&self.directives, format)?;
+        File::create(path)?.write_all(output.as_bytes())?;
+        Ok(())
+    }
+
+    fn show(&self) {
+        for hand in self.eval.hands.values() {
+            let mut cards = hand
+                .known_cards
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>();
+            cards.sort();
+            cards.extend(std::iter::repeat("?".to_owned()).take(hand.n_holes()));
+            let status = match (hand.should_plot, hand.folded) {
+                (_, true) => " (folded)",
+                (true, false) => " (plotted)",
+                (false, false) => "",
+            };
+            println!("{}{status}: {}", hand.name, cards.join(" "));
+        }
+        println!("{} cards removed from the deck", self.eval.discarded.len());
+    }
+
+    /// Run a `:command`. Returns false if the REPL should exit.
+    fn command(&mut self, line: &str, samples: u64) -> anyhow::Result<bool> {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.collect::<Vec<_>>().as_slice()) {
+            (Some(":show"), []) => self.show(),
+            (Some(":undo"), []) => match self.undo() {
+                Some(d) => println!("Undid {d}"),
+                None => println!("Nothing to undo"),
+            },
+            (Some(":equity"), names) if !names.is_empty() => {
+                for (name, e) in self.equity(names, samples)? {
+                    println!(
+                        "{name}: {:.2}% ±{:.2}% (win {:.2}%, tie {:.2}%)",
+                        e.equity * 100.0,
+                        e.margin(Z_95) * 100.0,
+                        e.win * 100.0,
+                        e.tie * 100.0
+                    );
+                }
+            }
+            (Some(":save"), [file]) => {
+                self.save(Path::new(file))?;
+                println!("Saved {} directives to {file}", self.directives.len());
+            }
+            (Some(":help"), []) => println!("{HELP}"),
+            (Some(":quit"), []) => return Ok(false),
+            _ => println!("Unknown command. Type :help for help."),
+        }
+        Ok(true)
+    }
+}
+
+/// Whether all parentheses opened in `s` outside of strings have been closed.
+fn is_balanced(s: &str) -> bool {
+    let mut depth = 0;
+    let mut in_string = false;
+    for c in s.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            _ => (),
+        }
+    }
+    depth <= 0 && !in_string
+}
+
+pub fn repl(args: ReplArgs) -> anyhow::Result<()> {
+    let mut repl = match &args.file {
+        Some(f) => Repl::load(f)?,
+        None => Repl::default(),
+    };
+    println!("{HELP}");
+
+    let mut buffer = String::new();
+    let mut lines = stdin().lock().lines();
+    loop {
+        print!("{}", if buffer.is_empty() { "> " } else { ". " });
+        stdout().flush()?;
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let line = line?;
+
+        if buffer.is_empty() && line.trim_start().starts_with(':') {
+            match repl.command(line.trim(), args.samples) {
+                Ok(true) => (),
+                Ok(false) => return Ok(()),
+                Err(e) => println!("Error: {e}"),
+            }
+            continue;
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+        if !is_balanced(&buffer) {
+            continue;
+        }
+
+        let src = std::mem::take(&mut buffer);
+        let result = lexpr::from_str(&format!("({src})"))
+            .map_err(anyhow::Error::from)
+            .and_then(|v| {
+                v.list_iter()
+                    .into_iter()
+                    .flatten()
+                    .try_for_each(|exp| repl.apply(exp))
+            });
+        if let Err(e) = result {
+            println!("Error: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(repl: &mut Repl, src: &str) -> anyhow::Result<()> {
+        repl.apply(&lexpr::from_str(src).unwrap())
+    }
+
+    #[test]
+    fn apply_and_undo() {
+        let mut repl = Repl::default();
+
+        apply(&mut repl, r#"(define-cards community "3c Td 2s ? ?")"#).unwrap();
+        apply(&mut repl, r#"(plot-cards self "As Kh $community")"#).unwrap();
+        assert!(apply(&mut repl, r#"(plot-cards self "2h")"#).is_err());
+        assert!(apply(&mut repl, r#"(plot-cards other "$nothing")"#).is_err());
+        assert_eq!(repl.directives.len(), 2);
+        assert_eq!(repl.eval.discarded.len(), 5);
+
+        repl.undo().unwrap();
+
+        assert_eq!(repl.directives.len(), 1);
+        assert_eq!(repl.eval.discarded.len(), 3);
+        assert!(!repl.eval.hands.contains_key("self"));
+    }
+
+    #[test]
+    fn equity_against_other_plotted_hands() {
+        let mut repl = Repl::default();
+        apply(&mut repl, r#"(define-cards community "Ac Ad 7s 2c 9h")"#).unwrap();
+        apply(&mut repl, r#"(plot-cards self "Ah As $community")"#).unwrap();
+        apply(&mut repl, r#"(plot-cards opponents "? ? $community")"#).unwrap();
+
+        let equity = repl.equity(&["self"], 1000).unwrap();
+
+        assert_eq!(equity[0].0, "self");
+        assert_eq!(equity[1].0, "opponents");
+        assert_eq!(equity[0].1.equity, 1.0);
+    }
+
+    #[test]
+    fn balanced_parens() {
+        assert!(is_balanced(r#"(plot-cards self "As (")"#));
+        assert!(!is_balanced(r#"(plot-cards self"#));
+        assert!(!is_balanced(r#"(plot-cards self ")"#));
+    }
+}
@@ -0,0 +1,377 @@
+//! Scenarios written as JSON, YAML or TOML instead of S-expressions.
+//!
+//! A scenario lists its steps in the order they apply, one per directive:
+//!
+//! ```yaml
+//! steps:
+//!   - name: community
+//!     cards: 3c Td 2s ? ?
+//!   - name: self
+//!     cards: [As, Kh, $community]
+//!     plot: true
+//!   - name: villain
+//!     cards: ? Qd $community
+//!   - fold: villain
+//!   - discard: 2h 3h
+//! ```
+//!
+//! Cards are written the same way as in the DSL, either as one
+//! space-separated string or as a list. Games dealt from a short deck add
+//! `deck: short`, games with wild cards add e.g. `wild: "2"` or `jokers: 1`,
+//! and low games add `pot: low` or `pot: high-low`.
+
+use std::{
+    fs::{read_to_string, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use poker::Rank;
+use poker_assistant::prediction::{deck::Deck, low::Pot};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dsl::{parse_cards_list, parse_program_from_str, CardsExp, DefineHand, Directive};
+
+#[derive(clap::Args, Clone)]
+pub struct ConvertArgs {
+    /// File to convert.
+    pub file: PathBuf,
+
+    /// Output file. If not provided, writes to stdout.
+    #[clap(short, long)]
+    pub out: Option<PathBuf>,
+
+    /// Format to convert to. Defaults to the format of the output file, or
+    /// JSON when converting from S-expressions and S-expressions otherwise.
+    #[clap(short, long)]
+    pub to: Option<Format>,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Sexp,
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Guess the format from a file extension, if it is known.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "sexp" => Some(Format::Sexp),
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Scenario {
+    /// Steps, in the order they apply.
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+/// A single directive of a scenario. Each kind of step is told apart by its
+/// keys.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Step {
+    Hand(HandSpec),
+
+    /// Cards to remove from the deck.
+    Discard {
+        #[serde(with = "cards")]
+        discard: Vec<CardsExp>,
+    },
+
+    /// Name of a hand which has folded.
+    Fold {
+        fold: String,
+    },
+
+    /// Deck the cards are dealt from.
+    Deck {
+        deck: Deck,
+    },
+
+    /// Rank which is wild.
+    Wild {
+        #[serde(with = "rank")]
+        wild: Rank,
+    },
+
+    /// Number of jokers in the deck.
+    Jokers {
+        jokers: u8,
+    },
+
+    /// How the pot of a showdown is won.
+    Pot {
+        pot: Pot,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HandSpec {
+    pub name: String,
+
+    #[serde(with = "cards")]
+    pub cards: Vec<CardsExp>,
+
+    /// Whether to plot this hand.
+    #[serde(default)]
+    pub plot: bool,
+}
+
+impl Scenario {
+    /// Fails if a hand is folded before it is defined.
+    pub fn from_directives(directives: &[Directive]) -> anyhow::Result<Self> {
+        let mut steps = Vec::with_capacity(directives.len());
+        for d in directives {
+            steps.push(match d {
+                Directive::DefineCards(dh) | Directive::PlotCards(dh) => Step::Hand(HandSpec {
+                    name: dh.name.clone(),
+                    cards: dh.cards.clone(),
+                    plot: matches!(d, Directive::PlotCards(_)),
+                }),
+                Directive::Discard(cards) => Step::Discard {
+                    discard: cards.clone(),
+                },
+                Directive::Fold(name) => {
+                    let defined = steps
+                        .iter()
+                        .any(|s| matches!(s, Step::Hand(h) if h.name == *name));
+                    if !defined {
+                        return Err(anyhow!("Cannot fold unknown hand {name}"));
+                    }
+                    Step::Fold { fold: name.clone() }
+                }
+                Directive::Deck(deck) => Step::Deck { deck: *deck },
+                Directive::Wild(rank) => Step::Wild { wild: *rank },
+                Directive::Jokers(n) => Step::Jokers { jokers: *n },
+                Directive::Pot(pot) => Step::Pot { pot: *pot },
+            });
+        }
+        Ok(Scenario { steps })
+    }
+
+    pub fn to_directives(&self) -> Vec<Directive> {
+        self.steps
+            .iter()
+            .map(|s| match s {
+                Step::Hand(h) => {
+                    let dh = DefineHand {
+                        name: h.name.clone(),
+                        cards: h.cards.clone(),
+                    };
+                    match h.plot {
+                        true => Directive::PlotCards(dh),
+                        false => Directive::DefineCards(dh),
+                    }
+                }
+                Step::Discard { discard } => Directive::Discard(discard.clone()),
+                Step::Fold { fold } => Directive::Fold(fold.clone()),
+                Step::Deck { deck } => Directive::Deck(*deck),
+                Step::Wild { wild } => Directive::Wild(*wild),
+                Step::Jokers { jokers } => Directive::Jokers(*jokers),
+                Step::Pot { pot } => Directive::Pot(*pot),
+            })
+            .collect()
+    }
+}
+
+/// Serialize card expressions as a DSL string, and deserialize them from
+/// either a string or a list of strings.
+mod cards {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum CardsRepr {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    pub fn serialize<S: Serializer>(cards: &[CardsExp], serializer: S) -> Result<S::Ok, S::Error> {
+        let cards = cards.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        cards.join(" ").serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<CardsExp>, D::Error> {
+        let src = match CardsRepr::deserialize(deserializer)? {
+            CardsRepr::One(s) => s,
+            CardsRepr::Many(v) => v.join(" "),
+        };
+        parse_cards_list(src).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A rank written as its character, e.g. `"J"`.
+mod rank {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(rank: &Rank, serializer: S) -> Result<S::Ok, S::Error> {
+        rank.as_char().to_string().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rank, D::Error> {
+        let r = String::deserialize(deserializer)?;
+        match r.chars().collect::<Vec<_>>()[..] {
+            [c] => Rank::try_from(c).map_err(serde::de::Error::custom),
+            _ => Err(serde::de::Error::custom(format!("Bad rank {r}"))),
+        }
+    }
+}
+
+/// Parse a program written in any format.
+pub fn parse_program(src: &str, format: Format) -> anyhow::Result<Vec<Directive>> {
+    let scenario: Scenario = match format {
+        Format::Sexp => return Ok(parse_program_from_str(src)?),
+        Format::Json => serde_json::from_str(src)?,
+        Format::Yaml => serde_yaml::from_str(src)?,
+        Format::Toml => toml::from_str(src)?,
+    };
+    Ok(scenario.to_directives())
+}
+
+pub fn write_program(directives: &[Directive], format: Format) -> anyhow::Result<String> {
+    let scenario = Scenario::from_directives(directives)?;
+    Ok(match format {
+        Format::Sexp => directives.iter().map(|d| format!("{d}\n")).collect(),
+        Format::Json => serde_json::to_string_pretty(&scenario)? + "\n",
+        Format::Yaml => serde_yaml::to_string(&scenario)?,
+        Format::Toml => toml::to_string(&scenario)?,
+    })
+}
+
+/// Read a program, guessing its format from the file extension. Files with
+/// unknown extensions are read as S-expressions.
+pub fn read_program(path: &Path) -> anyhow::Result<Vec<Directive>> {
+    let format = Format::from_path(path).unwrap_or(Format::Sexp);
+    parse_program(&read_to_string(path)?, format)
+}
+
+pub fn convert(args: ConvertArgs) -> anyhow::Result<()> {
+    let from = Format::from_path(&args.file).unwrap_or(Format::Sexp);
+    let to = args
+        .to
+        .or_else(|| args.out.as_deref().and_then(Format::from_path))
+        .unwrap_or(match from {
+            Format::Sexp => Format::Json,
+            _ => Format::Sexp,
+        });
+
+    let directives = read_program(&args.file)?;
+    let output = write_program(&directives, to)?;
+
+    match args.out {
+        Some(p) => File::create(p)?.write_all(output.as_bytes())?,
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dsl::evaluate_directives;
+
+    use super::*;
+
+    const PROGRAM: &str = r#"
+        (define-cards community "3c Td 2s ? ?")
+        (plot-cards self "As Kh $community")
+        (plot-cards opponents "? ? $community")
+        (discard "2h 3h")
+        (fold opponents)
+    "#;
+
+    #[test]
+    fn roundtrips_through_every_format() {
+        let directives = parse_program_from_str(PROGRAM).unwrap();
+
+        for format in [Format::Sexp, Format::Json, Format::Yaml, Format::Toml] {
+            let written = write_program(&directives, format).unwrap();
+            let parsed = parse_program(&written, format).unwrap();
+            assert_eq!(parsed, directives, "{format:?}:\n{written}");
+        }
+    }
+
+    #[test]
+    fn accepts_card_lists() {
+        let yaml = r#"
+steps:
+  - name: community
+    cards: 3c Td 2s ? ?
+  - name: self
+    cards: [As, Kh, $community]
+    plot: true
+  - discard: 2h 3h
+"#;
+        let from_yaml = evaluate_directives(&parse_program(yaml, Format::Yaml).unwrap()).unwrap();
+        let from_sexp = evaluate_directives(&parse_program_from_str(PROGRAM).unwrap()).unwrap();
+
+        assert_eq!(from_yaml.hands["self"], from_sexp.hands["self"]);
+        assert!(from_yaml.discarded.is_subset(&from_sexp.discarded));
+    }
+
+    #[test]
+    fn keeps_the_deck() {
+        let directives =
+            parse_program_from_str(r#"(plot-cards self "As ?") (deck short)"#).unwrap();
+        let yaml = write_program(&directives, Format::Yaml).unwrap();
+        assert!(yaml.contains("deck: short"), "{yaml}");
+
+        let parsed = parse_program(&yaml, Format::Yaml).unwrap();
+        assert_eq!(parsed, directives);
+        assert_eq!(evaluate_directives(&parsed).unwrap().deck, Deck::Short);
+
+        let razz = parse_program_from_str(r#"(pot low) (plot-cards self "As ?")"#).unwrap();
+        let toml = write_program(&razz, Format::Toml).unwrap();
+        assert!(toml.contains("pot = \"low\""), "{toml}");
+        assert_eq!(parse_program(&toml, Format::Toml).unwrap(), razz);
+    }
+
+    #[test]
+    fn keeps_wild_cards() {
+        let program = r#"(wild "2") (wild "J") (jokers 1) (plot-cards self "Jk 2h ? ? ?")"#;
+        let directives = parse_program_from_str(program).unwrap();
+        for format in [Format::Json, Format::Yaml, Format::Toml] {
+            let written = write_program(&directives, format).unwrap();
+            let parsed = parse_program(&written, format).unwrap();
+            assert_eq!(parsed, directives, "{format:?}:\n{written}");
+        }
+    }
+
+    #[test]
+    fn keeps_the_order() {
+        // A joker cannot be dealt before jokers are added to the deck.
+        let program = r#"(plot-cards self "Jk ?") (jokers 1) (discard "2h")"#;
+        let directives = parse_program_from_str(program).unwrap();
+        assert!(evaluate_directives(&directives).is_err());
+
+        let json = write_program(&directives, Format::Json).unwrap();
+        let parsed = parse_program(&json, Format::Json).unwrap();
+        assert_eq!(parsed, directives);
+        assert!(evaluate_directives(&parsed).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_cards() {
+        let json = r#"{"steps": [{"name": "self", "cards": "Xx"}]}"#;
+        assert!(parse_program(json, Format::Json).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_folds() {
+        let directives =
+            parse_program_from_str(r#"(fold villain) (plot-cards self "As ?")"#).unwrap();
+        assert!(write_program(&directives, Format::Json).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::Read;
+
+use anyhow::anyhow;
+use poker_assistant::api::{
+    simulate_game, ErrorResponse, GameDescription, HandReport, SimulationRequest,
+    SimulationResponse,
+};
+use poker_assistant_lookup::LOOKUP;
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    dsl::{evaluate_directives, parse_program_from_str},
+    sim::{run_simulations, SimCache, SimOptions},
+};
+
+/// Largest request body accepted, in bytes.
+const MAX_BODY_BYTES: u64 = 1 << 20;
+
+/// A request body was larger than [`MAX_BODY_BYTES`].
+#[derive(thiserror::Error, Debug)]
+#[error("Request body is larger than {MAX_BODY_BYTES} bytes")]
+struct BodyTooLarge;
+
+#[derive(clap::Args, Clone)]
+pub struct ServeArgs {
+    /// Address to listen on.
+    #[clap(short, long, default_value = "127.0.0.1:8080")]
+    pub address: String,
+
+    /// Number of samples to simulate when a request does not say.
+    #[clap(short = 'n', default_value = "100000")]
+    pub samples: u64,
+
+    /// Largest number of samples a request may ask for.
+    #[clap(long, default_value = "10000000")]
+    pub max_samples: u64,
+}
+
+/// Serve simulations over HTTP. Requests are handled one at a time, each
+/// using every core. Nothing is cached between requests, so unseeded
+/// requests are simulated afresh each time.
+pub fn serve(args: ServeArgs) -> anyhow::Result<()> {
+    eprintln!("Loaded {} hands", LOOKUP.map.len());
+
+    let server = Server::http(&args.address).map_err(|e| anyhow!(e))?;
+    eprintln!("Listening on http://{}", args.address);
+
+    for mut request in server.incoming_requests() {
+        let response = match (request.method(), request.url()) {
+            (Method::Post, "/simulate") => {
+                match read_request(&mut request).and_then(|r| handle(&args, &r)) {
+                    Ok(response) => json_response(200, &response),
+                    Err(e) => json_response(
+                        if e.is::<BodyTooLarge>() { 413 } else { 400 },
+                        &ErrorResponse {
+                            error: e.to_string(),
+                        },
+                    ),
+                }
+            }
+            (_, "/simulate") => json_response(
+                405,
+                &ErrorResponse {
+                    error: "Use POST".into(),
+                },
+            ),
+            _ => json_response(
+                404,
+                &ErrorResponse {
+                    error: "Not found".into(),
+                },
+            ),
+        };
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to respond: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Parses the body of `request`, refusing bodies over [`MAX_BODY_BYTES`].
+fn read_request(request: &mut Request) -> anyhow::Result<SimulationRequest> {
+    if request
+        .body_length()
+        .is_some_and(|n| n as u64 > MAX_BODY_BYTES)
+    {
+        return Err(BodyTooLarge.into());
+    }
+    // The body may be chunked, with no length given up front.
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(BodyTooLarge.into());
+    }
+    Ok(serde_json::from_str(&body)?)
+}
+
+fn json_response(status: u16, body: &impl Serialize) -> Response<std::io::Cursor<Vec<u8>>> {
+    let json = serde_json::to_vec(body).expect("responses are always serializable");
+    Response::from_data(json)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+pub fn handle(args: &ServeArgs, request: &SimulationRequest) -> anyhow::Result<SimulationResponse> {
+    let samples = request.samples.unwrap_or(args.samples);
+    if samples == 0 || samples > args.max_samples {
+        return Err(anyhow!(
+            "samples must be between 1 and {}",
+            args.max_samples
+        ));
+    }
+    if request.bins == 0 {
+        return Err(anyhow!("bins must be at least 1"));
+    }
+
+    match &request.game {
+        GameDescription::Game(game) => Ok(simulate_game(game, samples, request.bins)?),
+        GameDescription::Dsl(src) => {
+            let program = parse_program_from_str(src)?;
+            let eval = evaluate_directives(&program)?;
+            for hand in eval.hands.values().filter(|h| h.should_plot) {
+                let size = hand.known_cards.len() + hand.n_holes();
+                if !(5..=7).contains(&size) {
+                    return Err(anyhow!(
+                        "Hand {} has {size} cards, but must have between 5 and 7",
+                        hand.name
+                    ));
+                }
+            }
+            let options = SimOptions {
+                samples,
+                n_bins: request.bins,
+                ..Default::default()
+            };
+            let results = run_simulations(&eval, &options, &mut SimCache::default());
+
+            let equities = match results.len() {
+                0 | 1 => vec![None; results.len()],
+                _ => {
+                    let names = results.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+                    let deck = eval.sample_deck();
+                    let showdown = eval.showdown(&names, &deck)?;
+                    showdown.equity(samples).into_iter().map(Some).collect()
+                }
+            };
+
+            let hands = results
+                .into_iter()
+                .zip(equities)
+                .map(|((name, summary), equity)| HandReport {
+                    name,
+                    equity,
+                    summary,
+                })
+                .collect();
+            Ok(SimulationResponse { hands })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    #[test]
+    fn handles_dsl_requests() {
+        let request = serde_json::from_str(
+            r#"{
+                "dsl": "(define-cards community \"Ac Ad 7s 2c 9h\")\n(plot-cards self \"Ah As $community\")\n(plot-cards other \"? ? $community\")",
+                "bins": 10
+            }"#,
+        )
+        .unwrap();
+
+        let response = handle(&parse_args(&["-n", "1000"]), &request).unwrap();
+
+        assert_eq!(response.hands.len(), 2);
+        assert_eq!(response.hands[0].name, "self");
+        assert_eq!(response.hands[0].equity.unwrap().equity, 1.0);
+        assert_eq!(response.hands[1].summary.histogram.len(), 10);
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        let too_many = serde_json::from_str(r#"{"dsl": "", "samples": 100000}"#).unwrap();
+        let bad_dsl = serde_json::from_str(r#"{"dsl": "(plot-cards self \"$x\")"}"#).unwrap();
+
+        let args = parse_args(&["--max-samples", "10000"]);
+        assert!(handle(&args, &too_many).is_err());
+        assert!(handle(&args, &bad_dsl).is_err());
+    }
+
+    #[test]
+    fn rejects_large_bodies() {
+        let small = r#"{"dsl": ""}"#;
+        let large = Box::leak(" ".repeat(MAX_BODY_BYTES as usize + 1).into_boxed_str());
+
+        let read = |body: &'static str| {
+            read_request(&mut tiny_http::TestRequest::new().with_body(body).into())
+        };
+
+        assert!(read(small).is_ok());
+        assert!(read(large).unwrap_err().is::<BodyTooLarge>());
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use plotters::{
+    backend::BitMapBackend,
+    chart::ChartBuilder,
+    drawing::IntoDrawingArea,
+    element::PathElement,
+    series::LineSeries,
+    style::{Color, BLACK, BLUE, RED, WHITE},
+};
+use poker_assistant::session::{append_record, read_records, HandRecord, SessionSummary};
+
+use crate::BuiltinTemplates;
+
+#[derive(clap::Args, Clone)]
+pub struct SessionArgs {
+    /// Results log to use.
+    #[clap(short, long, default_value = "poker-session.jsonl")]
+    pub log: PathBuf,
+
+    #[clap(subcommand)]
+    pub subcommand: SessionSubcommand,
+}
+
+#[derive(clap::Subcommand, Clone)]
+pub enum SessionSubcommand {
+    /// Record a played hand.
+    Add(AddArgs),
+
+    /// Report statistics over all recorded hands.
+    Report(ReportArgs),
+}
+
+#[derive(clap::Args, Clone)]
+pub struct AddArgs {
+    /// Amount we put into the pot.
+    #[clap(short, long)]
+    pub stake: f64,
+
+    /// Net amount won. Negative if the hand was lost.
+    #[clap(short, long, allow_hyphen_values = true)]
+    pub result: f64,
+
+    /// Our equity when the last decision was made. Needs the pot it applies
+    /// to.
+    #[clap(short, long, requires = "pot")]
+    pub equity: Option<f64>,
+
+    /// Total size of the pot the equity applies to.
+    #[clap(short, long, requires = "equity")]
+    pub pot: Option<f64>,
+
+    /// Game the hand was played in.
+    #[clap(short = 't')]
+    pub template: Option<BuiltinTemplates>,
+}
+
+#[derive(clap::Args, Clone)]
+pub struct ReportArgs {
+    /// Write a graph of running winnings to this file.
+    #[clap(short, long)]
+    pub out: Option<PathBuf>,
+}
+
+pub fn session(args: SessionArgs) -> anyhow::Result<()> {
+    match args.subcommand {
+        SessionSubcommand::Add(add) => {
+            let record = HandRecord {
+                timestamp: Some(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()),
+                rounds: add.template.map(|t| t.rounds()).unwrap_or_default(),
+                stake: add.stake,
+                result: add.result,
+                equity: add.equity,
+                pot: add.pot,
+            };
+            append_record(&args.log, &record)
+                .with_context(|| format!("Failed to append to {}", args.log.to_string_lossy()))?;
+        }
+        SessionSubcommand::Report(report) => {
+            let records = read_records(&args.log)
+                .with_context(|| format!("Failed to read {}", args.log.to_string_lossy()))?;
+            let summary = SessionSummary::from_records(&records);
+
+            println!("Hands:                 {}", summary.hands);
+            println!("Total:                 {:.2}", summary.total);
+            println!("Luck-adjusted total:   {:.2}", summary.luck_adjusted_total);
+            println!("Winrate / hand:        {:.4}", summary.winrate);
+            println!("Winrate / 100 hands:   {:.2}", summary.winrate * 100.0);
+            println!(
+                "Luck-adjusted / 100:   {:.2}",
+                summary.luck_adjusted_winrate * 100.0
+            );
+            println!("Std. dev. / hand:      {:.4}", summary.stddev());
+            println!(
+                "Std. dev. / 100 hands: {:.2}",
+                summary.stddev() * 100f64.sqrt()
+            );
+
+            if let Some(out) = report.out {
+                plot_running(&out, &summary)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn plot_running(out: &Path, summary: &SessionSummary) -> anyhow::Result<()> {
+    let (lo, hi) = summary
+        .running
+        .iter()
+        .flat_map(|(a, b)| [*a, *b])
+        .fold((0f64, 0f64), |(lo, hi), x| (lo.min(x), hi.max(x)));
+    // Avoid a degenerate range when nothing has been won or lost yet.
+    let hi = if hi > lo { hi } else { lo + 1.0 };
+
+    let root = BitMapBackend::new(out, (640, 360)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .margin(10)
+        .caption("Running winnings", ("sans-serif", 25.0))
+        .build_cartesian_2d(0..summary.hands.max(1), lo..hi)?;
+
+    chart
+        .configure_mesh()
+        .bold_line_style(WHITE.mix(0.3))
+        .y_desc("Winnings")
+        .x_desc("Hands")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+
+    let start = std::iter::once((0, 0.0));
+    let running = summary.running.iter().enumerate();
+    let actual = start
+        .clone()
+        .chain(running.clone().map(|(n, r)| (n + 1, r.0)));
+    let adjusted = start.chain(running.map(|(n, r)| (n + 1, r.1)));
+
+    chart
+        .draw_series(LineSeries::new(actual, &RED))?
+        .label("Actual")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+    chart
+        .draw_series(LineSeries::new(adjusted, &BLUE))?
+        .label("Luck-adjusted")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+
+    root.present()?;
+    Ok(())
+}
@@ -0,0 +1,927 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::{Duration, SystemTime},
+};
+
+use compact_poker::{SCard, Wild};
+use plotters::{
+    backend::BitMapBackend,
+    chart::ChartBuilder,
+    coord::{combinators::IntoLinspace, ranged1d::IntoSegmentedCoord, Shift},
+    drawing::{DrawingArea, IntoDrawingArea},
+    series::Histogram,
+    style::{Color, RED, WHITE},
+};
+use poker_assistant::prediction::{
+    category::HandCategory,
+    deck::Deck,
+    draw::{DrawPolicy, Exchanges},
+    model::{HandVec, PartialHand},
+    montecarlo::SimParams,
+    range::Range,
+    sampling::Strategy,
+    stats::{ScoreSummary, Z_95},
+    strength::{EquityDistribution, StrengthParams},
+};
+use serde::Serialize;
+use strum::IntoEnumIterator;
+
+use crate::{
+    dsl::{evaluate_directives, Evaluation},
+    scenario::read_program,
+    strength::EQUITY_BINS,
+    textplot::{rebin, sparkline},
+};
+
+#[derive(clap::Args, Clone)]
+pub struct SimulateArgs {
+    /// File to simulate with, as S-expressions, JSON, YAML or TOML
+    pub file: PathBuf,
+
+    /// Output file. If not provided, results are printed to the terminal.
+    #[clap(short, long)]
+    pub out: Option<PathBuf>,
+
+    /// Output format. Defaults to the extension of the output file, or a PNG
+    /// plot if it is not recognized, or text if there is no output file.
+    #[clap(short, long)]
+    pub format: Option<OutputFormat>,
+
+    /// Number of samples to simulate, or the most to simulate with
+    /// --precision, or the number of runouts with --equity-against
+    #[clap(short = 'n', default_value = "100000")]
+    pub samples: u64,
+
+    /// Keep sampling until the 95% confidence interval of each hand's mean
+    /// score is at most this wide either side, e.g. 0.005. Only the mean is
+    /// targeted: quantiles get whatever precision those samples give, and
+    /// equities, as with --equity-against, always use a fixed number of
+    /// samples.
+    #[clap(short, long)]
+    pub precision: Option<f32>,
+
+    /// Seed for reproducible results. Runs with the same seed give the same
+    /// results, regardless of the number of threads.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Keep running, and simulate again whenever the file changes.
+    #[clap(short, long)]
+    pub watch: bool,
+
+    /// Most cards each hand may exchange in a draw still to come, e.g. 3 for
+    /// five-card draw. Repeat for each draw.
+    #[clap(long = "exchange")]
+    pub exchanges: Vec<u8>,
+
+    /// How hands choose which cards to exchange.
+    #[clap(long, default_value = "heuristic")]
+    pub draw_policy: DrawChoice,
+
+    /// How the unknown cards are sampled. Strategies other than uniform
+    /// score hands as dealt, so they cannot be combined with --exchange or
+    /// --precision.
+    #[clap(long, default_value = "uniform")]
+    pub sampling: SamplingChoice,
+
+    /// Range of the first two unknown cards of each hand with --sampling
+    /// importance, e.g. "AA, AKs:0.5".
+    #[clap(long, required_if_eq("sampling", "importance"))]
+    pub importance_range: Option<String>,
+
+    /// Plot the distribution of each hand's equity against this range over
+    /// runouts of the board, e.g. "AA, KK, AKs", instead of its score. Each
+    /// hand must be two cards of its own plus the board, and -n sets the
+    /// number of runouts.
+    #[clap(long, conflicts_with_all = ["precision", "exchanges"])]
+    pub equity_against: Option<Range>,
+
+    /// Hand holding the community cards, for --equity-against.
+    #[clap(long, default_value = "community")]
+    pub board: String,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingChoice {
+    /// Draw each sample independently.
+    #[default]
+    Uniform,
+
+    /// Deal every card first equally often.
+    Stratified,
+
+    /// Pair each sample with one taking the opposite cards in deck order.
+    Antithetic,
+
+    /// Score each sample again with the suits of each rank rotated.
+    SuitPermuted,
+
+    /// Deal the first two unknown cards from --importance-range, weighting
+    /// each sample by the range.
+    Importance,
+}
+
+impl SimulateArgs {
+    fn strategy(&self) -> anyhow::Result<Strategy> {
+        Ok(match self.sampling {
+            SamplingChoice::Uniform => Strategy::Uniform,
+            SamplingChoice::Stratified => Strategy::Stratified,
+            SamplingChoice::Antithetic => Strategy::Antithetic,
+            SamplingChoice::SuitPermuted => Strategy::SuitPermuted,
+            SamplingChoice::Importance => {
+                let range = self.importance_range.as_deref().unwrap_or_default();
+                Strategy::Importance(range.parse()?)
+            }
+        })
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DrawChoice {
+    /// Never exchange anything.
+    StandPat,
+
+    /// Keep pairs and better, otherwise four to a flush or straight,
+    /// otherwise the highest cards.
+    #[default]
+    Heuristic,
+
+    /// Make the exchange with the best mean final score. Only hands with no
+    /// unknown cards can plan ahead like this; the rest use the heuristic.
+    Best,
+}
+
+/// Draws still to come, played out by every simulated hand.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FutureDraws {
+    /// Most cards that may be exchanged in each draw.
+    pub rounds: Vec<u8>,
+
+    pub choice: DrawChoice,
+}
+
+impl FutureDraws {
+    /// Exchanges made by a hand with `undrawn` cards still to come besides
+    /// the `known` ones.
+    fn exchanges(&self, known: &[SCard], undrawn: u8, deck: &[SCard], samples: u64) -> Exchanges {
+        let policy = match (self.choice, self.rounds.first()) {
+            (DrawChoice::StandPat, _) => DrawPolicy::StandPat,
+            (DrawChoice::Best, Some(max)) if undrawn == 0 => {
+                DrawPolicy::best_mean(known, *max, deck, samples)
+            }
+            _ => DrawPolicy::Heuristic,
+        };
+        Exchanges::new(self.rounds.iter().copied(), policy)
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Png,
+    Json,
+    Csv,
+}
+
+impl SimulateArgs {
+    fn output_format(&self) -> OutputFormat {
+        self.format.unwrap_or(match &self.out {
+            None => OutputFormat::Text,
+            Some(out) => match out.extension().and_then(|e| e.to_str()) {
+                Some("txt") => OutputFormat::Text,
+                Some("json") => OutputFormat::Json,
+                Some("csv") => OutputFormat::Csv,
+                _ => OutputFormat::Png,
+            },
+        })
+    }
+}
+
+/// Everything that determines the outcome of simulating a hand.
+#[derive(PartialEq, Eq, Hash)]
+struct SimKey {
+    known: Vec<SCard>,
+    undrawn: u8,
+    deck: Vec<SCard>,
+    ranking: Deck,
+    wild: Wild,
+    samples: u64,
+    precision: Option<u32>,
+    seed: Option<u64>,
+    n_bins: usize,
+    draws: FutureDraws,
+    /// Ranges hold weights, which are not hashable, so the strategy is keyed
+    /// by its debug form.
+    strategy: String,
+}
+
+/// Results of previous simulations, so that unchanged hands are not simulated
+/// again. Only the results of the last run are kept.
+#[derive(Default)]
+pub struct SimCache(HashMap<SimKey, ScoreSummary>);
+
+pub fn simulate(args: SimulateArgs) -> anyhow::Result<()> {
+    if !args.watch {
+        return simulate_once(&args, &mut SimCache::default());
+    }
+
+    let mut cache = SimCache::default();
+    let mut last_modified = None;
+    eprintln!("Watching {} for changes", args.file.to_string_lossy());
+    loop {
+        let modified = modified_time(&args.file);
+        if modified != last_modified {
+            last_modified = modified;
+            if let Err(e) = simulate_once(&args, &mut cache) {
+                eprintln!("Error: {e}");
+            }
+            eprintln!("Waiting for changes...");
+        }
+        sleep(Duration::from_millis(250));
+    }
+}
+
+fn strategy_name(strategy: &Strategy) -> &'static str {
+    match strategy {
+        Strategy::Uniform => "uniform",
+        Strategy::Stratified => "stratified",
+        Strategy::Antithetic => "antithetic",
+        Strategy::SuitPermuted => "suit-permuted",
+        Strategy::Importance(_) => "importance",
+    }
+}
+
+/// Importance sampling needs two unknown cards in every hand it deals, and a
+/// range with a hand left in the deck.
+fn check_importance_range(eval: &Evaluation, range: &Range) -> anyhow::Result<()> {
+    if let Some(p) = eval
+        .hands
+        .values()
+        .find(|p| p.should_plot && p.n_holes() == 1)
+    {
+        anyhow::bail!(
+            "Importance sampling needs two unknown cards, but {} has one",
+            p.name
+        );
+    }
+    let deck = eval.sample_deck();
+    let in_deck = deck
+        .iter()
+        .enumerate()
+        .any(|(i, a)| deck[i + 1..].iter().any(|b| range.weight(*a, *b) > 0.0));
+    if !in_deck {
+        anyhow::bail!("The importance range has no hand left in the deck");
+    }
+    Ok(())
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    path.metadata().and_then(|m| m.modified()).ok()
+}
+
+fn simulate_once(args: &SimulateArgs, cache: &mut SimCache) -> anyhow::Result<()> {
+    let program = read_program(&args.file)?;
+    let eval = evaluate_directives(&program)?;
+
+    let draws = FutureDraws {
+        rounds: args.exchanges.clone(),
+        choice: args.draw_policy,
+    };
+    if eval.has_wild_cards() && !draws.rounds.is_empty() {
+        anyhow::bail!("Exchanges cannot be simulated with wild cards");
+    }
+    let strategy = args.strategy()?;
+    if strategy != Strategy::Uniform && (args.precision.is_some() || !draws.rounds.is_empty()) {
+        anyhow::bail!("Only uniform sampling works with --precision or --exchange");
+    }
+    if let Strategy::Importance(range) = &strategy {
+        check_importance_range(&eval, range)?;
+    }
+    if let Some(range) = &args.equity_against {
+        if strategy != Strategy::Uniform {
+            anyhow::bail!("Only uniform sampling works with --equity-against");
+        }
+        let results = equity_distributions(&eval, &args.board, range, args.samples, args.seed)?;
+        return write_equities(args, &results);
+    }
+    let options = SimOptions {
+        samples: args.samples,
+        precision: args.precision,
+        seed: args.seed,
+        n_bins: 100,
+        draws,
+        strategy,
+    };
+    let results = run_simulations(&eval, &options, cache);
+
+    let format = args.output_format();
+    if format == OutputFormat::Png {
+        let out = args
+            .out
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("PNG output needs an output file"))?;
+        plot_summaries(out, &results)?;
+        if args.watch {
+            write_text(&mut stdout(), &results)?;
+        }
+        return Ok(());
+    }
+
+    let mut out: Box<dyn Write> = match &args.out {
+        Some(p) => Box::new(File::create(p)?),
+        None => Box::new(stdout()),
+    };
+    match format {
+        OutputFormat::Text => write_text(&mut out, &results)?,
+        OutputFormat::Json => write_json(&mut out, &results)?,
+        OutputFormat::Csv => write_csv(&mut out, &results)?,
+        OutputFormat::Png => unreachable!(),
+    }
+    Ok(())
+}
+
+/// How [`run_simulations`] simulates each hand.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SimOptions {
+    /// Number of samples to simulate, or the most to simulate with a
+    /// `precision`.
+    pub samples: u64,
+
+    /// Most the 95% confidence interval of each mean may be wide either side.
+    pub precision: Option<f32>,
+
+    pub seed: Option<u64>,
+
+    /// Number of bins in each histogram.
+    pub n_bins: usize,
+
+    pub draws: FutureDraws,
+
+    /// How the unknown cards are sampled without a `precision`.
+    pub strategy: Strategy,
+}
+
+/// Simulate every hand to be plotted, reusing cached results where possible.
+/// Afterwards the cache holds only these hands' results, so it never grows
+/// beyond one run.
+pub fn run_simulations(
+    eval: &Evaluation,
+    options: &SimOptions,
+    cache: &mut SimCache,
+) -> Vec<(String, ScoreSummary)> {
+    let &SimOptions {
+        samples,
+        precision,
+        seed,
+        n_bins,
+        ref draws,
+        ref strategy,
+    } = options;
+    let deck = eval.sample_deck();
+
+    let mut used = SimCache::default();
+    let results = eval
+        .hands
+        .values()
+        .filter(|p| p.should_plot)
+        .map(|p| {
+            let mut known = p.known_cards.iter().copied().collect::<Vec<_>>();
+            known.sort();
+            let key = SimKey {
+                known,
+                undrawn: p.n_holes() as u8,
+                deck: deck.clone(),
+                ranking: eval.deck,
+                wild: eval.wild,
+                samples,
+                precision: precision.map(f32::to_bits),
+                seed,
+                n_bins,
+                draws: draws.clone(),
+                strategy: format!("{strategy:?}"),
+            };
+
+            if let Some(summary) = cache.0.remove(&key).or_else(|| used.0.get(&key).cloned()) {
+                eprintln!("Simulating {} (unchanged)", p.name);
+                used.0.insert(key, summary.clone());
+                return (p.name.clone(), summary);
+            }
+
+            let sim_params = SimParams {
+                player: PartialHand {
+                    drawn: key.known.iter().copied().collect(),
+                    undrawn: key.undrawn,
+                },
+                sample_deck: &deck,
+                exchanges: draws.exchanges(&key.known, key.undrawn, &deck, samples),
+                seed,
+                deck: eval.deck,
+                wild: eval.wild,
+            };
+
+            // Exchanges are always sampled, so there is no fixed number of
+            // possibilities to report.
+            let n_possibilities = sim_params.n_possibilities();
+            let out_of = match n_possibilities {
+                u64::MAX => " samples with draws".to_owned(),
+                n => format!("/{n} possibilities"),
+            };
+            let summary = match precision {
+                Some(precision) => {
+                    let summary = sim_params.summarize_to_precision(precision, samples, n_bins);
+                    eprintln!(
+                        "Simulating {} to within {precision} ({}{}{out_of})",
+                        p.name,
+                        if summary.exhaustive { "all " } else { "" },
+                        summary.samples,
+                    );
+                    summary
+                }
+                None if *strategy != Strategy::Uniform => {
+                    eprintln!(
+                        "Simulating {} ({samples}{out_of}, {} sampling)",
+                        p.name,
+                        strategy_name(strategy)
+                    );
+                    sim_params.summarize_with(strategy, samples, n_bins)
+                }
+                None => {
+                    if samples > n_possibilities {
+                        eprintln!(
+                            "Simulating {} (all {n_possibilities} possibilities)",
+                            p.name
+                        );
+                    } else {
+                        eprintln!("Simulating {} ({samples}{out_of})", p.name);
+                    }
+                    sim_params.summarize(samples, n_bins)
+                }
+            };
+            used.0.insert(key, summary.clone());
+            (p.name.clone(), summary)
+        })
+        .collect();
+    *cache = used;
+    results
+}
+
+pub fn write_text(w: &mut impl Write, results: &[(String, ScoreSummary)]) -> anyhow::Result<()> {
+    for (name, summary) in results {
+        writeln!(
+            w,
+            "{} (u={:.04}±{:.04}, s={:.04}, p50={:.04} [{:.04}, {:.04}])",
+            name,
+            summary.mean,
+            summary.mean_margin(Z_95),
+            summary.stddev,
+            summary.p50,
+            summary.p50_interval.0,
+            summary.p50_interval.1
+        )?;
+        writeln!(w, "  |{}|", sparkline(&rebin(&summary.histogram, 50)))?;
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct NamedSummary<'a, S> {
+    name: &'a str,
+
+    #[serde(flatten)]
+    summary: &'a S,
+}
+
+/// A JSON list of summaries, each with a `name` field.
+pub fn write_json<S: Serialize>(w: &mut impl Write, results: &[(String, S)]) -> anyhow::Result<()> {
+    let named = results
+        .iter()
+        .map(|(name, summary)| NamedSummary { name, summary })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(&mut *w, &named)?;
+    writeln!(w)?;
+    Ok(())
+}
+
+/// One row per hand, with a column for each percentile, histogram bin and
+/// category.
+pub fn write_csv(w: &mut impl Write, results: &[(String, ScoreSummary)]) -> anyhow::Result<()> {
+    let n_bins = results.first().map_or(0, |(_, s)| s.histogram.len());
+
+    let mut header = [
+        "name",
+        "samples",
+        "exhaustive",
+        "mean",
+        "mean_stderr",
+        "stddev",
+        "p50_low",
+        "p50_high",
+    ]
+    .map(String::from)
+    .to_vec();
+    header.extend((0..=100).map(|i| format!("p{i}")));
+    header.extend((0..n_bins).map(|i| format!("bin{i}")));
+    header.extend(HandCategory::iter().map(|c| format!("{c:?}")));
+
+    let mut csv = csv::Writer::from_writer(w);
+    csv.write_record(&header)?;
+    for (name, s) in results {
+        let mut row = vec![
+            name.clone(),
+            s.samples.to_string(),
+            s.exhaustive.to_string(),
+            s.mean.to_string(),
+            s.mean_stderr.to_string(),
+            s.stddev.to_string(),
+            s.p50_interval.0.to_string(),
+            s.p50_interval.1.to_string(),
+        ];
+        row.extend(s.percentiles.iter().map(|p| p.to_string()));
+        row.extend(s.histogram.iter().map(|b| b.to_string()));
+        row.extend(s.categories.iter().map(|c| c.to_string()));
+        csv.write_record(&row)?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+pub fn plot_summaries(out: &Path, results: &[(String, ScoreSummary)]) -> anyhow::Result<()> {
+    let root = BitMapBackend::new(out, (640, 240 * results.len() as u32)).into_drawing_area();
+    root.fill(&WHITE)?;
+
+    let regions = root.split_evenly((results.len(), 1));
+
+    for (region, (name, summary)) in regions.iter().zip(results) {
+        let caption = format!(
+            "{} (u={:.04}, s={:.04}, p50={:.04})",
+            name, summary.mean, summary.stddev, summary.p50
+        );
+        draw_histogram(region, &caption, "Quantile", &summary.histogram)?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+/// Distribution of the equity of each hand to be plotted against `range`,
+/// over `runouts` runouts of the `board` hand. Every other plotted hand must
+/// be two known cards of its own plus the board.
+pub fn equity_distributions(
+    eval: &Evaluation,
+    board: &str,
+    range: &Range,
+    runouts: u64,
+    seed: Option<u64>,
+) -> anyhow::Result<Vec<(String, EquityDistribution)>> {
+    if eval.has_wild_cards() || eval.deck != Deck::Standard {
+        anyhow::bail!("Equity distributions need a standard deck without wild cards");
+    }
+    let board_hand = eval
+        .hands
+        .get(board)
+        .ok_or_else(|| anyhow::anyhow!("No hand named {board}"))?;
+    if board_hand.known_cards.len() < 3 {
+        anyhow::bail!("The {board} hand needs at least 3 known cards");
+    }
+    let deck = eval.sample_deck();
+    let in_deck = deck
+        .iter()
+        .enumerate()
+        .any(|(i, a)| deck[i + 1..].iter().any(|b| range.weight(*a, *b) > 0.0));
+    if !in_deck {
+        anyhow::bail!("The range has no hands left in the deck");
+    }
+
+    eval.hands
+        .values()
+        .filter(|p| p.should_plot && p.name != board)
+        .map(|p| {
+            let hole = p
+                .known_cards
+                .difference(&board_hand.known_cards)
+                .copied()
+                .collect::<HandVec>();
+            if hole.len() != 2
+                || p.holes != board_hand.holes
+                || !board_hand.known_cards.is_subset(&p.known_cards)
+            {
+                anyhow::bail!("{} must be two known cards and the {board} hand", p.name);
+            }
+            eprintln!("Simulating the equity of {} ({runouts} runouts)", p.name);
+            let params = StrengthParams {
+                hole,
+                board: board_hand.known_cards.iter().copied().collect(),
+                board_undrawn: board_hand.n_holes() as u8,
+                sample_deck: &deck,
+                opponent: Some(range),
+                seed,
+            };
+            Ok((
+                p.name.clone(),
+                params.equity_distribution(runouts, EQUITY_BINS),
+            ))
+        })
+        .collect()
+}
+
+fn equity_caption(name: &str, d: &EquityDistribution) -> String {
+    format!(
+        "{name} (equity over {}{} runouts, mean {:.2}%)",
+        if d.exhaustive { "all " } else { "" },
+        d.runouts,
+        d.mean * 100.0
+    )
+}
+
+/// Write equity distributions in the output format of `args`.
+fn write_equities(
+    args: &SimulateArgs,
+    results: &[(String, EquityDistribution)],
+) -> anyhow::Result<()> {
+    let format = args.output_format();
+    if format == OutputFormat::Png {
+        let out = args
+            .out
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("PNG output needs an output file"))?;
+        let root = BitMapBackend::new(out, (640, 240 * results.len() as u32)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let regions = root.split_evenly((results.len(), 1));
+        for (region, (name, d)) in regions.iter().zip(results) {
+            draw_histogram(region, &equity_caption(name, d), "Equity", &d.histogram)?;
+        }
+        root.present()?;
+        return Ok(());
+    }
+
+    let mut out: Box<dyn Write> = match &args.out {
+        Some(p) => Box::new(File::create(p)?),
+        None => Box::new(stdout()),
+    };
+    match format {
+        OutputFormat::Text => {
+            for (name, d) in results {
+                writeln!(out, "{}", equity_caption(name, d))?;
+                writeln!(out, "  |{}|", sparkline(&d.histogram))?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut out, results)?,
+        OutputFormat::Csv => {
+            let n_bins = results.first().map_or(0, |(_, d)| d.histogram.len());
+            let mut header = ["name", "runouts", "exhaustive", "mean"]
+                .map(String::from)
+                .to_vec();
+            header.extend((0..n_bins).map(|i| format!("bin{i}")));
+
+            let mut csv = csv::Writer::from_writer(out);
+            csv.write_record(&header)?;
+            for (name, d) in results {
+                let mut row = vec![
+                    name.clone(),
+                    d.runouts.to_string(),
+                    d.exhaustive.to_string(),
+                    d.mean.to_string(),
+                ];
+                row.extend(d.histogram.iter().map(|b| b.to_string()));
+                csv.write_record(&row)?;
+            }
+            csv.flush()?;
+        }
+        OutputFormat::Png => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Draw a histogram of values from 0 to 1 as densities.
+pub fn draw_histogram(
+    region: &DrawingArea<BitMapBackend, Shift>,
+    caption: &str,
+    x_desc: &str,
+    histogram: &[usize],
+) -> anyhow::Result<()> {
+    let n_bins = histogram.len();
+    let n_samples = histogram.iter().sum::<usize>().max(1);
+    let max = *histogram.iter().max().unwrap() as f32 / n_samples as f32;
+
+    let mut chart = ChartBuilder::on(region)
+        .x_label_area_size(35)
+        .y_label_area_size(40)
+        .margin(10)
+        .caption(caption, ("sans-serif", 25.0))
+        .build_cartesian_2d(
+            (0f32..1f32)
+                .step(1.0 / n_bins as f32)
+                .use_round()
+                .into_segmented(),
+            0f32..max,
+        )?;
+
+    chart
+        .configure_mesh()
+        .disable_x_mesh()
+        .bold_line_style(WHITE.mix(0.3))
+        .y_desc("Density")
+        .x_desc(x_desc)
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+
+    let histogram = Histogram::vertical(&chart)
+        .style(RED.mix(0.8).filled())
+        .margin(0)
+        .data(
+            histogram
+                .iter()
+                .enumerate()
+                .map(|(i, count)| (i as f32 / n_bins as f32, *count as f32 / n_samples as f32)),
+        );
+
+    chart.draw_series(histogram)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<(String, ScoreSummary)> {
+        let scores = [1, 2_000_000, 1_000_000];
+        vec![("self".into(), ScoreSummary::from_scores(&scores, 4))]
+    }
+
+    #[test]
+    fn plots_equity_distributions() {
+        let eval = |program: &str| {
+            let program = crate::dsl::parse_program_from_str(program).unwrap();
+            evaluate_directives(&program).unwrap()
+        };
+        let range = "AA, 77".parse().unwrap();
+
+        let eval_kings = eval(
+            r#"
+            (define-cards community "Kd Kh 7s ? ?")
+            (plot-cards quads "Ks Kc $community")
+            (plot-cards air "2c 3d $community")
+            "#,
+        );
+        let results = equity_distributions(&eval_kings, "community", &range, 200, Some(1)).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "quads");
+        assert!(results[0].1.mean > 0.95, "{:?}", results[0].1);
+        assert!(results[1].1.mean < results[0].1.mean);
+        assert_eq!(results[0].1.histogram.len(), EQUITY_BINS);
+
+        assert!(equity_distributions(&eval_kings, "board", &range, 200, Some(1)).is_err());
+        let eval_stud = eval(r#"(plot-cards self "As Ah Kd ? ? ? ?")"#);
+        assert!(equity_distributions(&eval_stud, "community", &range, 200, Some(1)).is_err());
+    }
+
+    #[test]
+    fn cache_keeps_only_the_last_run() {
+        let run = |program: &str, cache: &mut SimCache| {
+            let program = crate::dsl::parse_program_from_str(program).unwrap();
+            let eval = evaluate_directives(&program).unwrap();
+            let options = SimOptions {
+                samples: 100,
+                seed: Some(1),
+                n_bins: 10,
+                ..Default::default()
+            };
+            run_simulations(&eval, &options, cache)
+        };
+        let mut cache = SimCache::default();
+
+        let first = run(
+            r#"(plot-cards a "As Kh ? ? ?") (plot-cards b "2s 7h ? ? ?")"#,
+            &mut cache,
+        );
+        assert_eq!(cache.0.len(), 2);
+        let second = run(
+            r#"(plot-cards a "As Kh ? ? ?") (define-cards b "2s 7h ? ? ?")"#,
+            &mut cache,
+        );
+        assert_eq!(cache.0.len(), 1);
+        assert_eq!(first[0], second[0]);
+    }
+
+    #[test]
+    fn seeded_simulations_are_reproducible() {
+        let program = crate::dsl::parse_program_from_str(
+            r#"(plot-cards self "As Kh ? ? ? ? ?") (plot-cards other "? ? ? ? ? ? ?")"#,
+        )
+        .unwrap();
+        let eval = evaluate_directives(&program).unwrap();
+        let run = |seed| {
+            let options = SimOptions {
+                samples: 5000,
+                seed: Some(seed),
+                n_bins: 10,
+                ..Default::default()
+            };
+            run_simulations(&eval, &options, &mut SimCache::default())
+        };
+
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
+
+    #[test]
+    fn draws_improve_hands() {
+        let program =
+            crate::dsl::parse_program_from_str(r#"(plot-cards self "As Ah Kd 7c 2s")"#).unwrap();
+        let eval = evaluate_directives(&program).unwrap();
+        let run = |rounds: Vec<u8>, choice| {
+            let options = SimOptions {
+                samples: 5000,
+                seed: Some(1),
+                n_bins: 10,
+                draws: FutureDraws { rounds, choice },
+                ..Default::default()
+            };
+            run_simulations(&eval, &options, &mut SimCache::default())
+                .remove(0)
+                .1
+        };
+
+        let pat = run(vec![], DrawChoice::Heuristic);
+        let stand_pat = run(vec![3], DrawChoice::StandPat);
+        let heuristic = run(vec![3], DrawChoice::Heuristic);
+        let best = run(vec![3], DrawChoice::Best);
+
+        assert!(pat.exhaustive && !heuristic.exhaustive);
+        assert_eq!(pat.categories, stand_pat.categories);
+        assert!(heuristic.categories[HandCategory::Pair.index()] < 0.8);
+        assert!(best.mean > pat.mean);
+    }
+
+    #[test]
+    fn sampling_strategies_agree() {
+        let program =
+            crate::dsl::parse_program_from_str(r#"(plot-cards self "As Kh ? ? ? ? ?")"#).unwrap();
+        let eval = evaluate_directives(&program).unwrap();
+        let run = |strategy| {
+            let options = SimOptions {
+                samples: 4000,
+                seed: Some(1),
+                n_bins: 10,
+                strategy,
+                ..Default::default()
+            };
+            run_simulations(&eval, &options, &mut SimCache::default())
+                .remove(0)
+                .1
+                .mean
+        };
+
+        let uniform = run(Strategy::Uniform);
+        for strategy in [
+            Strategy::Stratified,
+            Strategy::Antithetic,
+            Strategy::SuitPermuted,
+        ] {
+            let mean = run(strategy.clone());
+            assert!(
+                (mean - uniform).abs() < 0.02,
+                "{strategy:?}: {mean} vs {uniform}"
+            );
+        }
+
+        let aces = "AA".parse().unwrap();
+        assert!(check_importance_range(&eval, &aces).is_ok());
+        let gone = "KK".parse::<Range>().unwrap();
+        let program =
+            crate::dsl::parse_program_from_str(r#"(plot-cards self "Ks Kh Kd Kc ? ? ?")"#).unwrap();
+        let eval = evaluate_directives(&program).unwrap();
+        assert!(check_importance_range(&eval, &gone).is_err());
+    }
+
+    #[test]
+    fn json_output_names_each_hand() {
+        let mut out = vec![];
+        write_json(&mut out, &results()).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json[0]["name"], "self");
+        assert_eq!(json[0]["samples"], 3);
+        assert_eq!(json[0]["percentiles"].as_array().unwrap().len(), 101);
+    }
+
+    #[test]
+    fn csv_output_has_a_column_per_statistic() {
+        let mut out = vec![];
+        write_csv(&mut out, &results()).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0]
+            .starts_with("name,samples,exhaustive,mean,mean_stderr,stddev,p50_low,p50_high,p0,"));
+        assert!(lines[0].ends_with(",bin3,HighCard,Pair,TwoPair,ThreeOfAKind,Straight,Flush,FullHouse,FourOfAKind,StraightFlush,FiveOfAKind"));
+        assert_eq!(lines[1].split(',').count(), 8 + 101 + 4 + 10);
+        assert!(lines[1].starts_with("self,3,false,"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+
+use anyhow::bail;
+use compact_poker::SCard;
+use plotters::{backend::BitMapBackend, drawing::IntoDrawingArea, style::WHITE};
+use poker_assistant::prediction::{
+    range::Range,
+    strength::{EquityDistribution, HandStrength, StrengthParams},
+};
+
+use crate::{
+    cards::{check_distinct, parse_cards},
+    sim::draw_histogram,
+    textplot::{bar, rebin, sparkline},
+};
+
+/// Number of bins in equity distributions.
+pub(crate) const EQUITY_BINS: usize = 50;
+
+#[derive(clap::Args, Clone)]
+pub struct StrengthArgs {
+    /// Our hole cards, e.g. "As Kh".
+    pub hole: String,
+
+    /// Community cards dealt so far, e.g. "Qs Jd 2c".
+    #[clap(short, long)]
+    pub board: String,
+
+    /// Total number of community cards once all are dealt.
+    #[clap(long, default_value = "5")]
+    pub board_size: u8,
+
+    /// Range of the opponent's hole cards, e.g. "AA, KK, AKs, AQo:0.5". If
+    /// not provided, every hand is equally likely.
+    #[clap(short, long)]
+    pub range: Option<Range>,
+
+    /// Number of samples to simulate
+    #[clap(short = 'n', default_value = "100000")]
+    pub samples: u64,
+
+    /// Seed for reproducible results.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Also show the distribution of our equity against the range over this
+    /// many runouts of the rest of the board. `sim --equity-against` plots
+    /// the same for the hands of a scenario.
+    #[clap(long)]
+    pub runouts: Option<u64>,
+
+    /// Plot the equity distribution to this PNG file instead of the terminal.
+    #[clap(short, long, requires = "runouts")]
+    pub out: Option<PathBuf>,
+}
+
+pub fn evaluate(args: &StrengthArgs) -> anyhow::Result<(HandStrength, Option<EquityDistribution>)> {
+    let hole = parse_cards(&args.hole)?;
+    let board = parse_cards(&args.board)?;
+
+    let known = hole.iter().chain(&board).collect::<Vec<_>>();
+    check_distinct(known.iter().copied())?;
+    if hole.len() + board.len() < 5 {
+        bail!(
+            "Expected at least 5 hole and community cards, got {}",
+            known.len()
+        );
+    }
+    let Some(board_undrawn) = args.board_size.checked_sub(board.len() as u8) else {
+        bail!("The board has more than {} cards", args.board_size);
+    };
+
+    let deck = SCard::deck()
+        .filter(|c| !known.contains(&c))
+        .collect::<Vec<_>>();
+    let params = StrengthParams {
+        hole,
+        board,
+        board_undrawn,
+        sample_deck: &deck,
+        opponent: args.range.as_ref(),
+        seed: args.seed,
+    };
+    if params.opponent.is_some_and(|r| {
+        (0..deck.len()).all(|a| (a + 1..deck.len()).all(|b| r.weight(deck[a], deck[b]) == 0.0))
+    }) {
+        bail!("The range has no hands left in the deck");
+    }
+
+    let distribution = args
+        .runouts
+        .map(|n| params.equity_distribution(n, EQUITY_BINS));
+    Ok((params.evaluate(args.samples), distribution))
+}
+
+pub fn strength(args: StrengthArgs) -> anyhow::Result<()> {
+    let (s, distribution) = evaluate(&args)?;
+
+    println!("Hand strength:           {:.2}%", s.hs * 100.0);
+    println!("Positive potential:      {:.2}%", s.ppot * 100.0);
+    println!("Negative potential:      {:.2}%", s.npot * 100.0);
+    println!("Effective hand strength: {:.2}%", s.ehs * 100.0);
+    if s.exhaustive {
+        println!("(every outcome enumerated)");
+    }
+
+    let Some(d) = distribution else {
+        return Ok(());
+    };
+    let caption = format!(
+        "Equity over {}{} runouts (mean {:.2}%)",
+        if d.exhaustive { "all " } else { "" },
+        d.runouts,
+        d.mean * 100.0
+    );
+    match &args.out {
+        Some(out) => {
+            let root = BitMapBackend::new(out, (640, 240)).into_drawing_area();
+            root.fill(&WHITE)?;
+            draw_histogram(&root, &caption, "Equity", &d.histogram)?;
+            root.present()?;
+        }
+        None => {
+            println!("\n{caption}");
+            println!("  |{}|", sparkline(&d.histogram));
+            for (i, count) in rebin(&d.histogram, 10).iter().enumerate() {
+                let fraction = *count as f64 / d.runouts.max(1) as f64;
+                println!(
+                    "  {:>3}-{:<3}% {} {:.1}%",
+                    i * 10,
+                    (i + 1) * 10,
+                    bar(fraction, 30),
+                    fraction * 100.0
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    #[test]
+    fn rejects_bad_input() {
+        let evaluate = |argv: &[&str]| evaluate(&parse_args(argv));
+
+        assert!(evaluate(&["As Kh", "-b", "Qs Jd"]).is_err());
+        assert!(evaluate(&["As Kh", "-b", "As Jd 2c"]).is_err());
+        assert!(evaluate(&["As Kh", "-b", "Qs Jd 2c 3c 4c 5c"]).is_err());
+        assert!(evaluate(&["As Xx", "-b", "Qs Jd 2c"]).is_err());
+        assert!(evaluate(&["As Ah", "-b", "Ad Ac 2c", "-r", "AA"]).is_err());
+    }
+
+    #[test]
+    fn reports_strength() {
+        let evaluate = |argv: &[&str]| {
+            evaluate(&StrengthArgs {
+                samples: 1000,
+                seed: Some(1),
+                ..parse_args(argv)
+            })
+        };
+
+        let (s, distribution) = evaluate(&["As Ks", "-b", "Qs Js Ts 2d 3c"]).unwrap();
+        assert_eq!(s.ehs, 1.0);
+        assert!(distribution.is_none());
+
+        let (_, distribution) = evaluate(&["As Ks", "-b", "Qs Js 2d", "--runouts", "100"]).unwrap();
+        let distribution = distribution.unwrap();
+        assert_eq!(distribution.runouts, 100);
+        assert_eq!(distribution.histogram.len(), EQUITY_BINS);
+    }
+}
@@ -0,0 +1,419 @@
+use anyhow::bail;
+use poker_assistant::{
+    game_repr::{self, get_deals, Deal, Round},
+    prediction::{
+        equity::{Equity, Showdown},
+        low::Pot,
+        model::{Game, HandVec},
+        stats::Z_95,
+        stud::{share_last_card, Draw, LiveCards, Ranking},
+    },
+};
+use serde::Serialize;
+
+use crate::cards::{check_distinct, parse_cards};
+
+#[derive(clap::Args, Clone)]
+pub struct StudArgs {
+    /// Our face-down cards, e.g. "As Ah".
+    pub down: String,
+
+    /// Our face-up cards, e.g. "Kd 7c".
+    #[clap(default_value = "")]
+    pub up: String,
+
+    /// Face-up cards of an opponent still in the hand. Repeat for each
+    /// opponent, in order of play after us.
+    #[clap(short = 'p', long = "opponent")]
+    pub opponents: Vec<String>,
+
+    /// Face-up cards of a player who folded. Repeat for each player.
+    #[clap(short, long)]
+    pub folded: Vec<String>,
+
+    /// Any other cards seen and out of play.
+    #[clap(short, long, default_value = "")]
+    pub dead: String,
+
+    /// Game being played.
+    #[clap(short, long, default_value = "7-card")]
+    pub game: StudGame,
+
+    /// Number of samples to simulate
+    #[clap(short = 'n', default_value = "20000")]
+    pub samples: u64,
+
+    /// Seed for reproducible results.
+    #[clap(short, long)]
+    pub seed: Option<u64>,
+
+    /// Print the report as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StudGame {
+    #[clap(name = "5-card")]
+    FiveCard,
+
+    #[clap(name = "7-card")]
+    SevenCard,
+
+    /// Seven-card stud where the lowest hand wins.
+    #[clap(name = "razz")]
+    Razz,
+
+    /// Seven-card stud where the pot is split between the best hand and the
+    /// lowest with no card above an eight.
+    #[clap(name = "stud-8")]
+    StudEight,
+}
+
+impl StudGame {
+    /// How hands are ranked to decide who brings in and who acts first.
+    pub fn ranking(&self) -> Ranking {
+        match self {
+            StudGame::Razz => Ranking::Low,
+            _ => Ranking::High,
+        }
+    }
+
+    /// How the pot is won at showdown.
+    pub fn pot(&self) -> Pot {
+        match self {
+            StudGame::Razz => Pot::Low,
+            StudGame::StudEight => Pot::HighLow,
+            _ => Pot::High,
+        }
+    }
+
+    fn rounds(&self) -> Vec<Round> {
+        match self {
+            StudGame::FiveCard => game_repr::five_card_stud(),
+            StudGame::SevenCard => game_repr::seven_card_stud(),
+            StudGame::Razz => game_repr::razz(),
+            StudGame::StudEight => game_repr::stud_eight(),
+        }
+    }
+
+    /// Cards each player is dealt over the whole hand.
+    pub fn deal(&self) -> Deal {
+        get_deals(self.rounds()).into_iter().sum()
+    }
+
+    /// Face-down cards each player holds once `upcards` face-up cards have
+    /// been dealt, before any later face-down card.
+    pub fn down_cards(&self, upcards: usize) -> u8 {
+        let mut dealt = Deal::default();
+        for deal in get_deals(self.rounds()) {
+            if dealt.stud as usize >= upcards {
+                break;
+            }
+            dealt = dealt + deal;
+        }
+        dealt.hole
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StudReport {
+    /// Our equity against every opponent still in the hand jointly.
+    pub equity: Equity,
+
+    /// Each opponent's equity, in the order they were given.
+    pub opponents: Vec<Equity>,
+
+    /// Cards still live for our draws.
+    pub live: LiveCards,
+
+    /// Seat which brings in on third street, if every seat shows an upcard.
+    /// We are seat 0, each opponent follows in order, then each player who
+    /// folded.
+    pub bring_in: Option<usize>,
+
+    /// Seat which acts first on this street, if it is after third street.
+    pub first_to_act: Option<usize>,
+
+    /// Whether the last card is dealt as one community card, as there are
+    /// not enough cards left to give everyone their own.
+    pub shared_last_card: bool,
+}
+
+/// The hand as a game where every folded player has been removed, their
+/// upcards are dead and their face-down cards are mucked.
+pub fn game_from_args(args: &StudArgs) -> anyhow::Result<Game> {
+    Ok(without_folded(table_from_args(args)?, args.opponents.len()))
+}
+
+/// The hand as a game where players who folded are still seated, after
+/// every opponent in the hand.
+pub fn table_from_args(args: &StudArgs) -> anyhow::Result<Game> {
+    let deal = args.game.deal();
+    let down = parse_cards(&args.down)?;
+    let up = parse_cards(&args.up)?;
+    let opponents = args
+        .opponents
+        .iter()
+        .map(|s| parse_cards(s))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let folded = args
+        .folded
+        .iter()
+        .map(|s| parse_cards(s))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let dead = parse_cards(&args.dead)?;
+
+    if opponents.is_empty() {
+        bail!("Expected at least one opponent still in the hand");
+    }
+    if down.len() > deal.hole as usize {
+        bail!("Expected at most {} face-down cards", deal.hole);
+    }
+    if let Some(up) = [&up]
+        .into_iter()
+        .chain(&opponents)
+        .chain(&folded)
+        .find(|u| u.len() > deal.stud as usize)
+    {
+        bail!(
+            "Expected at most {} face-up cards, got {}",
+            deal.stud,
+            up.len()
+        );
+    }
+    let seen = [&down, &up]
+        .into_iter()
+        .chain(&opponents)
+        .chain(&folded)
+        .chain([&dead])
+        .flatten()
+        .collect::<Vec<_>>();
+    check_distinct(seen.iter().copied())?;
+
+    // Every seat has as many face-down cards as we do, unless it folded on
+    // an earlier street.
+    let down_cards = |upcards: &HandVec| match upcards.len() == up.len() {
+        true => down.len() as u8,
+        false => args.game.down_cards(upcards.len()),
+    };
+    let mut game = Game::from_deals(opponents.len() + folded.len(), [deal]);
+    for (o, cards) in game
+        .opponents
+        .iter_mut()
+        .zip(opponents.into_iter().chain(folded))
+    {
+        o.hole.add_cards(down_cards(&cards));
+        o.stud.add_cards(cards);
+    }
+    game.player.hole.add_cards(down);
+    game.player.stud.add_cards(up);
+    game.dead.extend(dead);
+    Ok(game)
+}
+
+/// Fold every seat after the first `in_hand` opponents.
+fn without_folded(mut table: Game, in_hand: usize) -> Game {
+    while table.opponents.len() > in_hand {
+        table.fold(table.opponents.len() - 1);
+    }
+    table
+}
+
+pub fn evaluate(args: &StudArgs) -> anyhow::Result<StudReport> {
+    let table = table_from_args(args)?;
+    let mut game = without_folded(table.clone(), args.opponents.len());
+    let shared_last_card = share_last_card(&mut game)?;
+    let deck = game.sample_deck();
+    let showdown = Showdown::from_game(&game, &deck)
+        .with_seed(args.seed)
+        .with_pot(args.game.pot());
+
+    let mut equities = showdown.equity(args.samples);
+    let equity = equities.remove(0);
+    let hand = game
+        .player
+        .hole
+        .drawn
+        .iter()
+        .chain(&game.player.stud.drawn)
+        .copied()
+        .collect::<Vec<_>>();
+    Ok(StudReport {
+        equity,
+        opponents: equities,
+        live: LiveCards::new(&hand, &deck),
+        bring_in: args.game.ranking().bring_in(&table).ok(),
+        first_to_act: match game.player.stud.drawn.len() {
+            0 | 1 => None,
+            _ => args.game.ranking().first_to_act(&game).ok(),
+        },
+        shared_last_card,
+    })
+}
+
+fn seat_name(seat: usize, in_hand: usize) -> String {
+    match seat {
+        0 => "you".to_owned(),
+        n if n <= in_hand => format!("opponent {n}"),
+        n => format!("folded player {}", n - in_hand),
+    }
+}
+
+fn draw_string(d: &Draw) -> String {
+    format!("{} {} held, {} live", d.to, d.held, d.live)
+}
+
+pub fn stud(args: StudArgs) -> anyhow::Result<()> {
+    let report = evaluate(&args)?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let e = report.equity;
+    println!(
+        "Equity against {} opponents: {:.2}% ±{:.2}% (win {:.2}%, tie {:.2}%)",
+        report.opponents.len(),
+        e.equity * 100.0,
+        e.margin(Z_95) * 100.0,
+        e.win * 100.0,
+        e.tie * 100.0
+    );
+    for (i, (o, up)) in report.opponents.iter().zip(&args.opponents).enumerate() {
+        println!(
+            "  Opponent {} ({}): {:.2}%",
+            i + 1,
+            up.trim(),
+            o.equity * 100.0
+        );
+    }
+
+    if let Some(seat) = report.bring_in {
+        println!("Bring-in: {}", seat_name(seat, report.opponents.len()));
+    }
+    if let Some(seat) = report.first_to_act {
+        println!("First to act: {}", seat_name(seat, report.opponents.len()));
+    }
+    if report.shared_last_card {
+        println!("The last card is dealt face up to share, as the deck runs out");
+    }
+
+    println!("\nLive cards:");
+    for d in &report.live.ranks {
+        println!("  Rank     {}", draw_string(d));
+    }
+    if let Some(d) = &report.live.flush {
+        println!("  Flush    {}", draw_string(d));
+    }
+    if let Some(d) = &report.live.straight {
+        println!(
+            "  Straight {}-high, {} ranks held, {} live",
+            d.to, d.held, d.live
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_args;
+
+    /// Evaluates the hand in `argv` with few samples and a fixed seed.
+    fn evaluate(argv: &[&str]) -> anyhow::Result<StudReport> {
+        super::evaluate(&StudArgs {
+            samples: 2000,
+            seed: Some(1),
+            ..parse_args(argv)
+        })
+    }
+
+    #[test]
+    fn rejects_bad_hands() {
+        assert!(evaluate(&["As Ah", "Kd"]).is_err());
+        assert!(evaluate(&["As Ah Kd 2c", "", "-p", "7c"]).is_err());
+        assert!(evaluate(&["As Ah", "Kd", "-p", "Kd"]).is_err());
+        assert!(evaluate(&["As Ah", "Kd", "-p", "7c 8c 9c Tc Jc"]).is_err());
+    }
+
+    #[test]
+    fn folded_upcards_are_dead() {
+        let live = evaluate(&["2h 3h", "4h", "-p", "Kc"]).unwrap().live;
+        assert_eq!(live.flush.unwrap().live, 10);
+
+        let hearts = [
+            "2h 3h", "4h", "-p", "Kc", "-f", "9h Th", "-f", "Jh", "-d", "Qh",
+        ];
+        let game = game_from_args(&parse_args(&hearts)).unwrap();
+        assert_eq!(game.opponents.len(), 1);
+        assert_eq!(game.dead.len(), 4);
+        // The player who folded on fourth street mucks two face-down cards,
+        // as does the one who folded on third street.
+        assert_eq!(game.mucked, 4);
+
+        let report = evaluate(&hearts).unwrap();
+        assert_eq!(report.live.flush.unwrap().live, 6);
+        assert_eq!(report.opponents.len(), 1);
+        let total = report.equity.equity + report.opponents[0].equity;
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn low_games_rank_low_hands() {
+        // A wheel draw against a pair of kings showing.
+        let wheel = |game: &str| {
+            evaluate(&["As 2s", "3d 4c 5h", "-p", "Kc Kd Qh", "-g", game])
+                .unwrap()
+                .equity
+        };
+        let high = wheel("7-card").equity;
+        let low = wheel("razz").equity;
+        let split = wheel("stud-8");
+
+        assert!(low > 0.95, "{low}");
+        assert!(low > split.equity && split.equity > high, "{split:?}");
+        assert!(split.tie > 0.0);
+    }
+
+    #[test]
+    fn equity_is_against_every_opponent_jointly() {
+        let heads_up = evaluate(&["As Ah", "Ad", "-p", "7c"]).unwrap();
+        let three_way = evaluate(&["As Ah", "Ad", "-p", "7c", "-p", "8d"]).unwrap();
+
+        // Rolled-up aces are big favourites, but less so against two hands.
+        assert!(heads_up.equity.equity > 0.75, "{:?}", heads_up.equity);
+        assert!(three_way.equity.equity < heads_up.equity.equity);
+        assert_eq!(three_way.opponents.len(), 2);
+        assert_eq!(three_way.live.ranks[0].live, 1);
+    }
+
+    #[test]
+    fn reports_stud_rules() {
+        let third_street = evaluate(&["As Ah", "Kd", "-p", "2c", "-p", "2h"]).unwrap();
+        assert_eq!(third_street.bring_in, Some(1));
+        assert_eq!(third_street.first_to_act, None);
+        assert!(!third_street.shared_last_card);
+
+        // A player who has since folded may have brought in.
+        let folded = ["As Ah", "Kd Qd", "-p", "7c 8c", "-f", "2d", "-f", "3d 4d"];
+        assert_eq!(evaluate(&folded).unwrap().bring_in, Some(2));
+
+        let fourth_street = ["As Ah", "Kd Kc", "-p", "2c 7h", "-p", "2h 9s"];
+        assert_eq!(evaluate(&fourth_street).unwrap().first_to_act, Some(0));
+
+        // In razz the king brings in, and the lowest hand showing acts first.
+        let razz = ["As Ah", "Kd", "-p", "2c", "-p", "2h", "-g", "razz"];
+        assert_eq!(evaluate(&razz).unwrap().bring_in, Some(0));
+        let razz = ["As Ah", "Kd Kc", "-p", "2c 7h", "-p", "2h 9s", "-g", "razz"];
+        assert_eq!(evaluate(&razz).unwrap().first_to_act, Some(1));
+
+        let mut eight_players = vec!["As Ah", "Kd"];
+        for up in ["2c", "3c", "4c", "5c", "6c", "7c", "8c"] {
+            eight_players.extend(["-p", up]);
+        }
+        let full_table = evaluate(&eight_players).unwrap();
+        assert!(full_table.shared_last_card);
+        assert_eq!(full_table.opponents.len(), 7);
+    }
+}
@@ -0,0 +1,192 @@
+//! Scenario templates generated from a game's round schedule.
+
+use poker_assistant::{
+    game_repr::{get_deals, get_discards, Deal, Round},
+    prediction::{deck::Deck, low::Pot},
+};
+
+/// Number of opponents listed separately in stud templates.
+const STUD_OPPONENTS: usize = 3;
+
+/// What each round deals, e.g. "2 face down, 1 face up".
+fn describe(round: &Round) -> String {
+    match round {
+        Round::Deal { counts, .. } => [
+            (counts.hole, "face down"),
+            (counts.stud, "face up"),
+            (counts.community, "community"),
+        ]
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, what)| format!("{n} {what}"))
+        .collect::<Vec<_>>()
+        .join(", "),
+        Round::Exchange { max, .. } => format!("up to {max} exchanged"),
+        Round::Discard { n, .. } => format!("{n} face down discarded"),
+    }
+}
+
+/// `n` unknown cards.
+fn holes(n: u8) -> String {
+    vec!["?"; n as usize].join(" ")
+}
+
+/// Whether each of a player's cards is dealt face down (`d`) or face up
+/// (`u`), in the order they are dealt.
+fn positions(rounds: &[Round]) -> String {
+    get_deals(rounds.to_vec())
+        .into_iter()
+        .flat_map(|d| {
+            std::iter::repeat("d")
+                .take(d.hole as usize)
+                .chain(std::iter::repeat("u").take(d.stud as usize))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A comment marking the face-up cards of the `plot-cards` line after it.
+fn position_hint(rounds: &[Round]) -> String {
+    let indent = " ".repeat("(plot-cards self \"".len() - "; ".len());
+    format!("; {indent}{}\n", positions(rounds))
+}
+
+/// A scenario for a game dealt with `rounds` from `deck`, whose pot is won
+/// as `pot`, with every card unknown.
+pub fn from_rounds(rounds: &[Round], deck: Deck, pot: Pot) -> String {
+    let total: Deal = get_deals(rounds.to_vec()).into_iter().sum();
+    let mut out = String::from("; Fill in the cards as they are dealt:\n");
+    for (i, round) in rounds.iter().enumerate() {
+        let name = round
+            .name()
+            .map_or_else(|| format!("Round {}", i + 1), str::to_owned);
+        out += &format!(";   {name}: {}\n", describe(round));
+    }
+    out += "\n";
+    if deck != Deck::Standard {
+        out += &format!("(deck {deck})\n\n");
+    }
+    if pot != Pot::High {
+        out += &format!("(pot {pot})\n\n");
+    }
+
+    // Each community deal gets a name, so it can be filled in on its own.
+    let community = rounds
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| match r {
+            Round::Deal { name, counts } if counts.community > 0 => {
+                let name = name.as_deref().map_or_else(
+                    || format!("community{}", i + 1),
+                    |n| n.to_lowercase().replace(' ', "-"),
+                );
+                Some((name, counts.community))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let own = holes(total.hole - get_discards(rounds) + total.stud);
+    let with_community = |cards: String| match community.is_empty() {
+        true => cards,
+        false => format!("{cards} $community"),
+    };
+    if !community.is_empty() {
+        for (name, n) in &community {
+            out += &format!("(define-cards {name} \"{}\")\n", holes(*n));
+        }
+        let subs = community
+            .iter()
+            .map(|(name, _)| format!("${name}"))
+            .collect::<Vec<_>>();
+        out += &format!("(define-cards community \"{}\")\n\n", subs.join(" "));
+    }
+
+    out += "; Fill in your cards here.\n";
+    if total.stud > 0 {
+        out += "; Cards are in the order dealt, face down (d) or face up (u):\n";
+        out += &position_hint(rounds);
+    }
+    out += &format!("(plot-cards self \"{}\")\n\n", with_community(own.clone()));
+
+    if total.stud > 0 {
+        out += "; Fill in each opponent's face-up cards. When a player folds, add e.g.\n";
+        out += "; (fold opp2) to keep their upcards out of the deck.\n";
+        out += &position_hint(rounds);
+        for i in 1..=STUD_OPPONENTS {
+            out += &format!("(plot-cards opp{i} \"{}\")\n", with_community(own.clone()));
+        }
+    } else {
+        out += "; This line does not need changes.\n";
+        out += &format!("(plot-cards opponents \"{}\")\n", with_community(own));
+    }
+
+    if rounds.iter().any(|r| matches!(r, Round::Exchange { .. })) {
+        out += "\n; When you exchange cards in your hand, record the cards you discarded here,\n";
+        out += "; and update the new cards listed above.\n";
+        out += "(discard \"\")\n";
+    } else if get_discards(rounds) > 0 {
+        out += "\n; When you throw away hole cards, record them here. Only the cards you\n";
+        out += "; keep are listed above.\n";
+        out += "(discard \"\")\n";
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use poker_assistant::game_repr::{
+        crazy_pineapple, five_card_draw, holdem, razz, seven_card_stud, short_deck_holdem,
+    };
+
+    use super::*;
+    use crate::dsl::{evaluate_directives, parse_program_from_str};
+
+    #[test]
+    fn holdem_template_shares_community_cards() {
+        let template = from_rounds(&holdem(), Deck::Standard, Pot::High);
+        let eval = evaluate_directives(&parse_program_from_str(&template).unwrap()).unwrap();
+
+        assert!(template.contains("(define-cards flop \"? ? ?\")"));
+        assert!(template.contains(";   Flop: 3 community\n"));
+        assert_eq!(eval.hands["self"].n_holes(), 7);
+        assert_eq!(eval.hands["opponents"].n_holes(), 7);
+        assert!(!eval.hands["community"].should_plot);
+
+        let short = from_rounds(&short_deck_holdem(), Deck::Short, Pot::High);
+        let eval = evaluate_directives(&parse_program_from_str(&short).unwrap()).unwrap();
+        assert!(short.contains("(deck short)\n"));
+        assert_eq!(eval.sample_deck().len(), 36);
+    }
+
+    #[test]
+    fn templates_follow_the_schedule() {
+        let stud = from_rounds(&seven_card_stud(), Deck::Standard, Pot::High);
+        let eval = evaluate_directives(&parse_program_from_str(&stud).unwrap()).unwrap();
+        assert!(stud.contains(";   Third Street: 2 face down, 1 face up\n"));
+        assert!(
+            stud.contains(";                 d d u u u u d\n(plot-cards self \"? ? ? ? ? ? ?\")")
+        );
+        assert_eq!(eval.hands.len(), 1 + STUD_OPPONENTS);
+        assert!(eval.hands.values().all(|h| h.n_holes() == 7));
+
+        let razz = from_rounds(&razz(), Deck::Standard, Pot::Low);
+        let eval = evaluate_directives(&parse_program_from_str(&razz).unwrap()).unwrap();
+        assert!(razz.contains("(pot low)\n"));
+        assert_eq!(eval.pot, Pot::Low);
+
+        let draw = from_rounds(&five_card_draw(), Deck::Standard, Pot::High);
+        let directives = parse_program_from_str(&draw).unwrap();
+        assert!(draw.contains(";   Exchange: up to 3 exchanged\n"));
+        assert_eq!(evaluate_directives(&directives).unwrap().hands.len(), 2);
+    }
+
+    #[test]
+    fn pineapple_templates_list_the_cards_kept() {
+        let template = from_rounds(&crazy_pineapple(), Deck::Standard, Pot::High);
+        let eval = evaluate_directives(&parse_program_from_str(&template).unwrap()).unwrap();
+
+        assert!(template.contains(";   Discard: 1 face down discarded\n"));
+        assert!(template.contains("(discard \"\")\n"));
+        assert_eq!(eval.hands["self"].n_holes(), 7);
+    }
+}
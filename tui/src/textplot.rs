@@ -0,0 +1,49 @@
+//! Plain-text renderings of distributions, for use in terminals.
+
+const EIGHTHS: [char; 9] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+const LEVELS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A horizontal bar `width` characters wide, filled to `fraction`.
+pub fn bar(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut s = "█".repeat(eighths / 8);
+    if let rest @ 1.. = eighths % 8 {
+        s.push(EIGHTHS[rest]);
+    }
+    format!("{s:<width$}")
+}
+
+/// A one-line histogram with one character per bin, scaled to the largest bin.
+pub fn sparkline(bins: &[usize]) -> String {
+    let max = bins.iter().copied().max().unwrap_or_default().max(1);
+    bins.iter().map(|b| LEVELS[(b * 8).div_ceil(max)]).collect()
+}
+
+/// Merge adjacent bins so that there are at most `n` of them.
+pub fn rebin(bins: &[usize], n: usize) -> Vec<usize> {
+    let width = bins.len().div_ceil(n.max(1)).max(1);
+    bins.chunks(width).map(|c| c.iter().sum()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bar_works() {
+        assert_eq!(bar(0.5, 4), "██  ");
+        assert_eq!(bar(1.0 / 16.0, 2), "▏ ");
+        assert_eq!(bar(2.0, 2), "██");
+    }
+
+    #[test]
+    fn rebin_works() {
+        assert_eq!(rebin(&[1, 2, 3, 4, 5], 2), vec![6, 9]);
+        assert_eq!(rebin(&[1, 2], 4), vec![1, 2]);
+    }
+
+    #[test]
+    fn sparkline_works() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), " ▁▄█");
+    }
+}